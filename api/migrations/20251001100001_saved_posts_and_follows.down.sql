@@ -0,0 +1,3 @@
+-- Drop bookmark and follow tables
+DROP TABLE IF EXISTS user_follows;
+DROP TABLE IF EXISTS saved_posts;
@@ -0,0 +1,23 @@
+-- Posts bookmarked by users
+CREATE TABLE saved_posts (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, post_id)
+);
+
+CREATE INDEX idx_saved_posts_post_id ON saved_posts(post_id);
+CREATE INDEX idx_saved_posts_user_created ON saved_posts(user_id, created_at DESC);
+
+-- Users following other users
+CREATE TABLE user_follows (
+    follower_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (follower_id, followee_id),
+    CONSTRAINT check_no_self_follow CHECK (follower_id != followee_id)
+);
+
+CREATE INDEX idx_user_follows_followee_id ON user_follows(followee_id);
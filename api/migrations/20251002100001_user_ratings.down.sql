@@ -0,0 +1,10 @@
+-- Restore the denormalized post rating column
+ALTER TABLE posts
+ADD COLUMN owner_rating DECIMAL(3, 2) NOT NULL DEFAULT 4.5,
+ADD CONSTRAINT chk_posts_rating CHECK (owner_rating >= 0 AND owner_rating <= 5);
+
+-- Drop rating aggregate and related objects
+DROP TRIGGER IF EXISTS trigger_update_user_ratings ON reviews;
+DROP FUNCTION IF EXISTS update_user_ratings();
+DROP FUNCTION IF EXISTS apply_user_rating(UUID, INTEGER, INTEGER);
+DROP TABLE IF EXISTS user_ratings;
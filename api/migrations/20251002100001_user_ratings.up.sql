@@ -0,0 +1,85 @@
+-- Per-user rating aggregate maintained from the reviews table
+CREATE TABLE user_ratings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    review_count INTEGER NOT NULL DEFAULT 0,
+    score_sum INTEGER NOT NULL DEFAULT 0,
+    one_star INTEGER NOT NULL DEFAULT 0,
+    two_stars INTEGER NOT NULL DEFAULT 0,
+    three_stars INTEGER NOT NULL DEFAULT 0,
+    four_stars INTEGER NOT NULL DEFAULT 0,
+    five_stars INTEGER NOT NULL DEFAULT 0,
+    average_score DECIMAL(3, 2) GENERATED ALWAYS AS (
+        CASE WHEN review_count = 0 THEN 0 ELSE ROUND(score_sum::numeric / review_count, 2) END
+    ) STORED,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_user_ratings_non_negative CHECK (
+        review_count >= 0 AND score_sum >= 0 AND one_star >= 0 AND two_stars >= 0
+        AND three_stars >= 0 AND four_stars >= 0 AND five_stars >= 0
+    )
+);
+
+-- Adds (direction = 1) or removes (direction = -1) a single score from a user's aggregate
+CREATE OR REPLACE FUNCTION apply_user_rating(target UUID, score INTEGER, direction INTEGER)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO user_ratings (user_id) VALUES (target)
+    ON CONFLICT (user_id) DO NOTHING;
+
+    UPDATE user_ratings SET
+        review_count = review_count + direction,
+        score_sum = score_sum + direction * score,
+        one_star = one_star + CASE WHEN score = 1 THEN direction ELSE 0 END,
+        two_stars = two_stars + CASE WHEN score = 2 THEN direction ELSE 0 END,
+        three_stars = three_stars + CASE WHEN score = 3 THEN direction ELSE 0 END,
+        four_stars = four_stars + CASE WHEN score = 4 THEN direction ELSE 0 END,
+        five_stars = five_stars + CASE WHEN score = 5 THEN direction ELSE 0 END,
+        updated_at = NOW()
+    WHERE user_id = target;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Keeps user_ratings in sync inside the same transaction as the review change
+CREATE OR REPLACE FUNCTION update_user_ratings()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        -- The receiver may already be gone when the delete cascades from users
+        IF EXISTS (SELECT 1 FROM users WHERE id = OLD.review_receiver_id) THEN
+            PERFORM apply_user_rating(OLD.review_receiver_id, OLD.score, -1);
+        END IF;
+    END IF;
+
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_user_rating(NEW.review_receiver_id, NEW.score, 1);
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_user_ratings
+    AFTER INSERT OR DELETE OR UPDATE OF score, review_receiver_id ON reviews
+    FOR EACH ROW
+    EXECUTE FUNCTION update_user_ratings();
+
+-- Backfill from existing reviews
+INSERT INTO user_ratings (
+    user_id, review_count, score_sum, one_star, two_stars, three_stars, four_stars, five_stars
+)
+SELECT
+    review_receiver_id,
+    COUNT(*),
+    SUM(score),
+    COUNT(*) FILTER (WHERE score = 1),
+    COUNT(*) FILTER (WHERE score = 2),
+    COUNT(*) FILTER (WHERE score = 3),
+    COUNT(*) FILTER (WHERE score = 4),
+    COUNT(*) FILTER (WHERE score = 5)
+FROM reviews
+GROUP BY review_receiver_id;
+
+-- The copied rating on posts was never kept up to date, ratings are read from user_ratings now
+ALTER TABLE posts
+DROP CONSTRAINT IF EXISTS chk_posts_rating,
+DROP COLUMN IF EXISTS owner_rating;
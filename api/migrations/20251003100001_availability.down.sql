@@ -0,0 +1,5 @@
+-- Drop availability tables and related objects
+DROP FUNCTION IF EXISTS is_user_available(UUID, TIMESTAMPTZ);
+DROP TABLE IF EXISTS availability_exceptions;
+DROP TABLE IF EXISTS availability_slots;
+DROP TABLE IF EXISTS user_availability;
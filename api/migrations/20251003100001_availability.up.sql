@@ -0,0 +1,77 @@
+-- Weekly availability of users (used for tutors publishing offers)
+CREATE TABLE user_availability (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    time_zone TEXT NOT NULL DEFAULT 'Europe/Warsaw',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Recurring time ranges, weekday follows ISO numbering (1 = Monday, 7 = Sunday)
+-- and times are local to the user's time zone
+CREATE TABLE availability_slots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES user_availability(user_id) ON DELETE CASCADE,
+    weekday SMALLINT NOT NULL CHECK (weekday BETWEEN 1 AND 7),
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+
+    CONSTRAINT chk_availability_slots_range CHECK (start_time < end_time)
+);
+
+CREATE INDEX idx_availability_slots_user_weekday ON availability_slots(user_id, weekday);
+
+-- One-off changes for a specific date, a missing time range covers the whole day
+CREATE TABLE availability_exceptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES user_availability(user_id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    is_available BOOLEAN NOT NULL,
+    start_time TIME,
+    end_time TIME,
+
+    CONSTRAINT chk_availability_exceptions_range CHECK (
+        (start_time IS NULL AND end_time IS NULL) OR
+        (start_time IS NOT NULL AND end_time IS NOT NULL AND start_time < end_time)
+    )
+);
+
+CREATE INDEX idx_availability_exceptions_user_date ON availability_exceptions(user_id, date);
+
+-- A user is available when a weekly slot or an available exception covers the moment
+-- and no unavailable exception does. Users without a schedule are never available.
+CREATE OR REPLACE FUNCTION is_user_available(target UUID, at TIMESTAMPTZ)
+RETURNS BOOLEAN AS $$
+DECLARE
+    local_at TIMESTAMP;
+BEGIN
+    SELECT at AT TIME ZONE a.time_zone INTO local_at
+    FROM user_availability a
+    WHERE a.user_id = target;
+
+    IF local_at IS NULL THEN
+        RETURN FALSE;
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM availability_exceptions e
+        WHERE e.user_id = target
+          AND e.date = local_at::date
+          AND NOT e.is_available
+          AND (e.start_time IS NULL OR (local_at::time >= e.start_time AND local_at::time < e.end_time))
+    ) THEN
+        RETURN FALSE;
+    END IF;
+
+    RETURN EXISTS (
+        SELECT 1 FROM availability_slots s
+        WHERE s.user_id = target
+          AND s.weekday = EXTRACT(ISODOW FROM local_at)
+          AND local_at::time >= s.start_time AND local_at::time < s.end_time
+    ) OR EXISTS (
+        SELECT 1 FROM availability_exceptions e
+        WHERE e.user_id = target
+          AND e.date = local_at::date
+          AND e.is_available
+          AND (e.start_time IS NULL OR (local_at::time >= e.start_time AND local_at::time < e.end_time))
+    );
+END;
+$$ LANGUAGE plpgsql STABLE;
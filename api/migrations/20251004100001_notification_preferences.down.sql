@@ -0,0 +1,4 @@
+-- Drop notification preference tables
+DROP TABLE IF EXISTS muted_threads;
+DROP TABLE IF EXISTS notification_settings;
+DROP TABLE IF EXISTS notification_preferences;
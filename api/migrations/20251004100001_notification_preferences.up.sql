@@ -0,0 +1,38 @@
+-- Per user opt-in/opt-out for each notification event and delivery channel.
+-- Missing rows fall back to the defaults defined in the API.
+CREATE TABLE notification_preferences (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_type VARCHAR(32) NOT NULL,
+    channel VARCHAR(16) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, event_type, channel),
+    CONSTRAINT chk_notification_preferences_event CHECK (
+        event_type IN ('new_message', 'new_review', 'post_reply', 'deadline_reminder')
+    ),
+    CONSTRAINT chk_notification_preferences_channel CHECK (channel IN ('in_app', 'email'))
+);
+
+-- Quiet hours are stored as local times in the given time zone, the range may wrap past midnight
+CREATE TABLE notification_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    time_zone TEXT NOT NULL DEFAULT 'Europe/Warsaw',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_notification_settings_quiet_hours CHECK (
+        (quiet_hours_start IS NULL AND quiet_hours_end IS NULL) OR
+        (quiet_hours_start IS NOT NULL AND quiet_hours_end IS NOT NULL AND quiet_hours_start <> quiet_hours_end)
+    )
+);
+
+-- Chat threads a user does not want to be notified about
+CREATE TABLE muted_threads (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    thread_id UUID NOT NULL REFERENCES msg_threads(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, thread_id)
+);
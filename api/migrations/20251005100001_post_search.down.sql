@@ -0,0 +1,8 @@
+-- Drop post search objects
+DROP FUNCTION IF EXISTS post_search_headline(TEXT, TEXT, BOOLEAN);
+DROP FUNCTION IF EXISTS post_search_query(TEXT);
+DROP INDEX IF EXISTS idx_posts_search_vector;
+DROP TRIGGER IF EXISTS trigger_posts_search_vector ON posts;
+DROP FUNCTION IF EXISTS update_posts_search_vector();
+ALTER TABLE posts DROP COLUMN IF EXISTS search_vector;
+DROP TEXT SEARCH CONFIGURATION IF EXISTS polish_search;
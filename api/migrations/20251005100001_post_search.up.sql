@@ -0,0 +1,60 @@
+-- Full text search over posts in Polish and English
+
+-- PostgreSQL ships no Polish configuration, this one folds case and diacritics
+-- so "calki" and "całki" match each other
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+CREATE TEXT SEARCH CONFIGURATION polish_search (COPY = simple);
+ALTER TEXT SEARCH CONFIGURATION polish_search
+    ALTER MAPPING FOR hword, hword_part, word WITH unaccent, simple;
+
+ALTER TABLE posts ADD COLUMN search_vector TSVECTOR;
+
+-- Title weighs most, then subject, then description. Every field is indexed with both configurations.
+CREATE OR REPLACE FUNCTION update_posts_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(NEW.subject, '')), 'B') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.subject, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.description, '')), 'C') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.description, '')), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_posts_search_vector
+    BEFORE INSERT OR UPDATE OF title, description, subject ON posts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_posts_search_vector();
+
+-- Backfill existing posts, the trigger fires on the touched columns
+UPDATE posts SET title = title;
+
+CREATE INDEX idx_posts_search_vector ON posts USING GIN (search_vector);
+
+-- Turns user input (web search syntax: quotes, OR, -word) into a query matching either language
+CREATE OR REPLACE FUNCTION post_search_query(input TEXT)
+RETURNS TSQUERY AS $$
+    SELECT websearch_to_tsquery('english', input) || websearch_to_tsquery('polish_search', input);
+$$ LANGUAGE sql STABLE;
+
+-- Highlights query matches in a text as <mark>...</mark>, the text is HTML escaped first.
+-- Whole texts are returned when whole_text is set (titles), otherwise short fragments.
+CREATE OR REPLACE FUNCTION post_search_headline(doc TEXT, input TEXT, whole_text BOOLEAN)
+RETURNS TEXT AS $$
+DECLARE
+    escaped TEXT := replace(replace(replace(doc, '&', '&amp;'), '<', '&lt;'), '>', '&gt;');
+    options TEXT := CASE WHEN whole_text
+        THEN 'StartSel=<mark>, StopSel=</mark>, HighlightAll=true'
+        ELSE 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10, FragmentDelimiter=" … "'
+    END;
+BEGIN
+    IF to_tsvector('english', doc) @@ websearch_to_tsquery('english', input) THEN
+        RETURN ts_headline('english', escaped, websearch_to_tsquery('english', input), options);
+    END IF;
+    RETURN ts_headline('polish_search', escaped, websearch_to_tsquery('polish_search', input), options);
+END;
+$$ LANGUAGE plpgsql STABLE;
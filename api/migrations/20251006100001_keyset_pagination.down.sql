@@ -0,0 +1,3 @@
+-- Drop cursor pagination indexes
+DROP INDEX IF EXISTS idx_reviews_created_at_id;
+DROP INDEX IF EXISTS idx_posts_created_at_id;
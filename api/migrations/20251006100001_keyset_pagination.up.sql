@@ -0,0 +1,3 @@
+-- Indexes matching the (created_at, id) ordering used by cursor pagination
+CREATE INDEX idx_posts_created_at_id ON posts(created_at DESC, id DESC);
+CREATE INDEX idx_reviews_created_at_id ON reviews(created_at DESC, id DESC);
@@ -0,0 +1,8 @@
+-- Drop post status history and restore the original status values
+DROP TABLE IF EXISTS post_status_history;
+
+UPDATE posts SET status = 'active' WHERE status = 'in_progress';
+
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (status IN ('active', 'completed', 'cancelled'));
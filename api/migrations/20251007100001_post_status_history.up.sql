@@ -0,0 +1,19 @@
+-- Post lifecycle: active -> in_progress -> completed, active/in_progress -> cancelled, reopening back to active
+
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (status IN ('active', 'in_progress', 'completed', 'cancelled'));
+
+-- Every status change, oldest first per post
+CREATE TABLE post_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    from_status VARCHAR(20) NOT NULL,
+    to_status VARCHAR(20) NOT NULL,
+    -- NULL when the user was deleted or the change was made by the server
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_post_status_history_post ON post_status_history(post_id, created_at);
@@ -0,0 +1,13 @@
+-- Drop proposals
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS trigger_post_proposals_response_count ON post_proposals;
+DROP FUNCTION IF EXISTS refresh_post_response_count();
+DROP TABLE IF EXISTS post_proposals;
+ALTER TABLE posts DROP COLUMN IF EXISTS assigned_helper_id;
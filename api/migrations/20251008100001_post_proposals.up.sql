@@ -0,0 +1,57 @@
+-- Proposals: users apply to requests or book offers, the owner accepts one of them
+
+ALTER TABLE posts ADD COLUMN assigned_helper_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE TABLE post_proposals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    applicant_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    proposed_price DECIMAL(10, 2) CHECK (proposed_price >= 0),
+    proposed_deadline TIMESTAMPTZ,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_post_proposals_status CHECK (status IN ('pending', 'accepted', 'rejected', 'withdrawn'))
+);
+
+-- A user can have only one open proposal per post
+CREATE UNIQUE INDEX idx_post_proposals_pending ON post_proposals(post_id, applicant_id) WHERE status = 'pending';
+CREATE INDEX idx_post_proposals_post ON post_proposals(post_id, created_at);
+CREATE INDEX idx_post_proposals_applicant ON post_proposals(applicant_id, created_at DESC);
+
+-- response_count is the number of proposals that were not withdrawn
+CREATE OR REPLACE FUNCTION refresh_post_response_count()
+RETURNS TRIGGER AS $$
+DECLARE
+    target UUID := CASE WHEN TG_OP = 'DELETE' THEN OLD.post_id ELSE NEW.post_id END;
+BEGIN
+    UPDATE posts SET response_count = (
+        SELECT COUNT(*) FROM post_proposals
+        WHERE post_id = target AND status <> 'withdrawn'
+    )
+    WHERE id = target;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_post_proposals_response_count
+    AFTER INSERT OR DELETE OR UPDATE OF status ON post_proposals
+    FOR EACH ROW
+    EXECUTE FUNCTION refresh_post_response_count();
+
+-- Counters changing is not an edit of the post
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- response_count was never maintained before, nothing backs the old values
+UPDATE posts SET response_count = 0;
@@ -0,0 +1,26 @@
+-- Drop background job tables and columns
+DROP TABLE IF EXISTS scheduled_jobs;
+DROP TRIGGER IF EXISTS notification_notify_trigger ON notifications;
+DROP FUNCTION IF EXISTS notify_new_notification();
+DROP TABLE IF EXISTS notifications;
+
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP INDEX IF EXISTS idx_posts_active_deadline;
+ALTER TABLE posts DROP COLUMN IF EXISTS deadline_reminded_for;
+
+UPDATE posts SET status = 'cancelled' WHERE status = 'expired';
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (status IN ('active', 'in_progress', 'completed', 'cancelled'));
+
+ALTER TABLE users DROP COLUMN IF EXISTS is_admin;
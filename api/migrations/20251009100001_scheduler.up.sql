@@ -0,0 +1,65 @@
+-- Background jobs: expiring overdue posts, deadline reminders and the in-app notifications they produce
+
+-- Admins are granted by hand: UPDATE users SET is_admin = TRUE WHERE ...
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Active posts past their deadline expire, the owner can reopen them after moving the deadline
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (status IN ('active', 'in_progress', 'completed', 'cancelled', 'expired'));
+
+-- Deadline the owner was last reminded about, moving the deadline earns a new reminder
+ALTER TABLE posts ADD COLUMN deadline_reminded_for TIMESTAMPTZ;
+
+CREATE INDEX idx_posts_active_deadline ON posts(deadline) WHERE status = 'active' AND deadline IS NOT NULL;
+
+-- Bookkeeping columns are not edits of the post
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- In-app notifications, newest first per user
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_type VARCHAR(32) NOT NULL,
+    post_id UUID REFERENCES posts(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_notifications_user ON notifications(user_id, created_at DESC);
+
+-- Pushes new notifications to the user's WebSocket connections on whichever instance holds them
+CREATE OR REPLACE FUNCTION notify_new_notification()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('user_' || NEW.user_id::text, row_to_json(NEW)::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notification_notify_trigger
+    AFTER INSERT ON notifications
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_new_notification();
+
+-- Outcome of the last run of every background job, shared by all instances
+CREATE TABLE scheduled_jobs (
+    name VARCHAR(64) PRIMARY KEY,
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_succeeded BOOLEAN,
+    last_error TEXT,
+    last_affected BIGINT,
+    last_instance TEXT,
+    run_count BIGINT NOT NULL DEFAULT 0
+);
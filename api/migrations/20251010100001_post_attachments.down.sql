@@ -0,0 +1,2 @@
+-- Drop post attachments, stored blobs have to be removed separately
+DROP TABLE IF EXISTS post_attachments;
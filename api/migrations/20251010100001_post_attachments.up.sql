@@ -0,0 +1,16 @@
+-- Files attached to posts, the content lives in the blob store under storage_key
+
+CREATE TABLE post_attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    uploader_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_post_attachments_post ON post_attachments(post_id, created_at);
+-- Quota checks sum the sizes per uploader
+CREATE INDEX idx_post_attachments_uploader ON post_attachments(uploader_id) INCLUDE (size_bytes);
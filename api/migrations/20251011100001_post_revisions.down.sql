@@ -0,0 +1,2 @@
+-- Drop post revision history
+DROP TABLE IF EXISTS post_revisions;
@@ -0,0 +1,16 @@
+-- Revision history of post edits, one row per update that changed at least one field
+
+CREATE TABLE post_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    -- 1 for the first edit of a post, increasing by one per edit
+    revision INTEGER NOT NULL CHECK (revision > 0),
+    editor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Array of {"field", "old", "new"} objects
+    changes JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_post_revision UNIQUE (post_id, revision)
+);
+
+CREATE INDEX idx_post_revisions_post_created ON post_revisions(post_id, created_at DESC);
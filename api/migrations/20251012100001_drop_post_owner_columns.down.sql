@@ -0,0 +1,21 @@
+-- Restore the denormalized owner columns from users
+
+ALTER TABLE posts
+ADD COLUMN owner_name VARCHAR(255),
+ADD COLUMN owner_username VARCHAR(255),
+ADD COLUMN owner_email VARCHAR(255),
+ADD COLUMN owner_avatar BYTEA;
+
+UPDATE posts p
+SET
+    owner_name = u.username,
+    owner_username = u.username,
+    owner_email = u.email,
+    owner_avatar = u.avatar
+FROM users u
+WHERE p.owner_id = u.id;
+
+ALTER TABLE posts
+ALTER COLUMN owner_name SET NOT NULL,
+ALTER COLUMN owner_username SET NOT NULL,
+ALTER COLUMN owner_email SET NOT NULL;
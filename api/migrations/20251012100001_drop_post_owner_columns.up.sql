@@ -0,0 +1,7 @@
+-- Owner data is read from users on every query, the copies taken at creation time went stale
+
+ALTER TABLE posts
+DROP COLUMN IF EXISTS owner_name,
+DROP COLUMN IF EXISTS owner_username,
+DROP COLUMN IF EXISTS owner_email,
+DROP COLUMN IF EXISTS owner_avatar;
@@ -0,0 +1,60 @@
+-- Back to a free-text subject, posts keep the name of their primary subject
+
+ALTER TABLE posts ADD COLUMN subject VARCHAR(255) NOT NULL DEFAULT 'General';
+
+UPDATE posts p
+SET subject = s.name
+FROM post_subjects ps
+JOIN subjects s ON s.id = ps.subject_id
+WHERE ps.post_id = p.id AND ps.position = 0;
+
+CREATE INDEX idx_posts_subject ON posts(subject);
+
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_subjects_search ON subjects;
+DROP TRIGGER IF EXISTS trigger_post_subjects_insert_search ON post_subjects;
+DROP TRIGGER IF EXISTS trigger_post_subjects_delete_search ON post_subjects;
+DROP FUNCTION IF EXISTS refresh_subject_posts_search_vector();
+DROP FUNCTION IF EXISTS refresh_tagged_posts_search_vector();
+
+CREATE OR REPLACE FUNCTION update_posts_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(NEW.subject, '')), 'B') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.subject, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.description, '')), 'C') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.description, '')), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_posts_search_vector ON posts;
+CREATE TRIGGER trigger_posts_search_vector
+    BEFORE INSERT OR UPDATE OF title, description, subject ON posts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_posts_search_vector();
+
+UPDATE posts SET title = title;
+
+DROP FUNCTION IF EXISTS post_subject_text(UUID);
+DROP FUNCTION IF EXISTS subject_subtree(TEXT[]);
+DROP FUNCTION IF EXISTS subject_descendants(UUID);
+DROP FUNCTION IF EXISTS resolve_subject(TEXT);
+DROP FUNCTION IF EXISTS next_subject_slug(TEXT);
+DROP TABLE IF EXISTS post_subjects;
+DROP TABLE IF EXISTS subject_aliases;
+DROP TABLE IF EXISTS subjects;
+DROP FUNCTION IF EXISTS normalize_subject(TEXT);
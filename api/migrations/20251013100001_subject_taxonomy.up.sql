@@ -0,0 +1,282 @@
+-- Managed subject taxonomy replacing the free-text posts.subject.
+-- Subjects form a tree, posts carry up to a few of them as tags. Old spellings and merged
+-- subjects live on as aliases so free-text input keeps resolving to the right subject.
+
+CREATE TABLE subjects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slug VARCHAR(120) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    parent_id UUID REFERENCES subjects(id) ON DELETE RESTRICT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_subjects_slug CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    CONSTRAINT chk_subjects_parent CHECK (parent_id IS DISTINCT FROM id)
+);
+
+-- Sibling names are unique regardless of case
+CREATE UNIQUE INDEX idx_subjects_sibling_name
+    ON subjects (COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'::uuid), lower(name));
+CREATE INDEX idx_subjects_parent ON subjects(parent_id);
+
+CREATE TABLE subject_aliases (
+    -- Normalized with normalize_subject
+    alias VARCHAR(255) PRIMARY KEY,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_subject_aliases_subject ON subject_aliases(subject_id);
+
+CREATE TABLE post_subjects (
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE RESTRICT,
+    -- 0 is the primary subject of the post
+    position SMALLINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (post_id, subject_id)
+);
+
+CREATE INDEX idx_post_subjects_subject ON post_subjects(subject_id);
+
+-- Form in which user input is compared to names and aliases
+CREATE OR REPLACE FUNCTION normalize_subject(input TEXT)
+RETURNS TEXT AS $$
+    SELECT lower(unaccent(regexp_replace(trim(input), '\s+', ' ', 'g')));
+$$ LANGUAGE sql STABLE;
+
+-- URL friendly slug that is not taken yet, "Analiza matematyczna" -> "analiza-matematyczna"
+CREATE OR REPLACE FUNCTION next_subject_slug(name TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    base TEXT := trim(BOTH '-' FROM regexp_replace(normalize_subject(name), '[^a-z0-9]+', '-', 'g'));
+    candidate TEXT;
+    n INTEGER := 1;
+BEGIN
+    IF base = '' THEN
+        base := 'subject';
+    END IF;
+    base := left(base, 100);
+    candidate := base;
+    WHILE EXISTS (SELECT 1 FROM subjects WHERE slug = candidate) LOOP
+        n := n + 1;
+        candidate := base || '-' || n;
+    END LOOP;
+    RETURN candidate;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Subject matching a slug, name or alias, in that order of preference
+CREATE OR REPLACE FUNCTION resolve_subject(input TEXT)
+RETURNS UUID AS $$
+    SELECT id FROM (
+        SELECT id, 1 AS preference FROM subjects WHERE slug = lower(trim(input))
+        UNION ALL
+        SELECT id, 2 FROM subjects WHERE normalize_subject(name) = normalize_subject(input)
+        UNION ALL
+        SELECT subject_id, 3 FROM subject_aliases WHERE alias = normalize_subject(input)
+    ) matches
+    ORDER BY preference, id
+    LIMIT 1;
+$$ LANGUAGE sql STABLE;
+
+-- A subject and everything below it
+CREATE OR REPLACE FUNCTION subject_descendants(root UUID)
+RETURNS SETOF UUID AS $$
+    WITH RECURSIVE tree AS (
+        SELECT id FROM subjects WHERE id = root
+        UNION
+        SELECT s.id FROM subjects s JOIN tree t ON s.parent_id = t.id
+    )
+    SELECT id FROM tree;
+$$ LANGUAGE sql STABLE;
+
+-- Subjects a post filter on the given inputs matches, unknown inputs match nothing
+CREATE OR REPLACE FUNCTION subject_subtree(inputs TEXT[])
+RETURNS SETOF UUID AS $$
+    SELECT DISTINCT subject_descendants(resolve_subject(input)) FROM unnest(inputs) AS input;
+$$ LANGUAGE sql STABLE;
+
+-- Names and aliases of a post's subjects and their ancestors, indexed for full text search
+CREATE OR REPLACE FUNCTION post_subject_text(post UUID)
+RETURNS TEXT AS $$
+    WITH RECURSIVE tagged AS (
+        SELECT s.id, s.parent_id FROM post_subjects ps JOIN subjects s ON s.id = ps.subject_id
+        WHERE ps.post_id = post
+        UNION
+        SELECT s.id, s.parent_id FROM subjects s JOIN tagged t ON s.id = t.parent_id
+    )
+    SELECT coalesce(string_agg(term, ' '), '') FROM (
+        SELECT s.name AS term FROM subjects s WHERE s.id IN (SELECT id FROM tagged)
+        UNION
+        SELECT a.alias FROM subject_aliases a WHERE a.subject_id IN (SELECT id FROM tagged)
+    ) terms;
+$$ LANGUAGE sql STABLE;
+
+-- Seed taxonomy, the roots match the subjects offered by the web client
+WITH roots(name, aliases) AS (
+    VALUES
+        ('Matematyka', ARRAY['math', 'maths', 'mathematics', 'matma']),
+        ('Fizyka', ARRAY['physics']),
+        ('Chemia', ARRAY['chemistry']),
+        ('Biologia', ARRAY['biology']),
+        ('Informatyka', ARRAY['computer science', 'cs', 'it', 'computing']),
+        ('Historia', ARRAY['history']),
+        ('Języki obce', ARRAY['foreign languages', 'languages']),
+        ('Statystyka', ARRAY['statistics', 'stats']),
+        ('Inżynieria', ARRAY['engineering']),
+        ('Zawodowe', ARRAY['vocational']),
+        ('Inne', ARRAY['other', 'general', 'ogólne'])
+),
+inserted AS (
+    INSERT INTO subjects (slug, name)
+    SELECT trim(BOTH '-' FROM regexp_replace(normalize_subject(name), '[^a-z0-9]+', '-', 'g')), name
+    FROM roots
+    RETURNING id, name
+)
+INSERT INTO subject_aliases (alias, subject_id)
+SELECT normalize_subject(alias), i.id
+FROM inserted i
+JOIN roots r ON r.name = i.name
+CROSS JOIN LATERAL unnest(r.aliases) AS alias;
+
+WITH children(parent, name, aliases) AS (
+    VALUES
+        ('Matematyka', 'Analiza matematyczna', ARRAY['calculus', 'analiza']),
+        ('Matematyka', 'Algebra', ARRAY['linear algebra', 'algebra liniowa']),
+        ('Matematyka', 'Geometria', ARRAY['geometry']),
+        ('Matematyka', 'Matematyka dyskretna', ARRAY['discrete mathematics', 'discrete math']),
+        ('Fizyka', 'Mechanika', ARRAY['mechanics']),
+        ('Fizyka', 'Elektryczność i magnetyzm', ARRAY['electromagnetism', 'elektromagnetyzm']),
+        ('Chemia', 'Chemia organiczna', ARRAY['organic chemistry']),
+        ('Chemia', 'Chemia nieorganiczna', ARRAY['inorganic chemistry']),
+        ('Informatyka', 'Programowanie', ARRAY['programming', 'coding']),
+        ('Informatyka', 'Algorytmy', ARRAY['algorithms', 'algorytmy i struktury danych']),
+        ('Informatyka', 'Bazy danych', ARRAY['databases', 'sql']),
+        ('Informatyka', 'Sieci komputerowe', ARRAY['networking', 'sieci']),
+        ('Języki obce', 'Język angielski', ARRAY['english', 'angielski']),
+        ('Języki obce', 'Język niemiecki', ARRAY['german', 'niemiecki']),
+        ('Języki obce', 'Język hiszpański', ARRAY['spanish', 'hiszpański'])
+),
+inserted AS (
+    INSERT INTO subjects (slug, name, parent_id)
+    SELECT trim(BOTH '-' FROM regexp_replace(normalize_subject(c.name), '[^a-z0-9]+', '-', 'g')), c.name, p.id
+    FROM children c
+    JOIN subjects p ON p.name = c.parent AND p.parent_id IS NULL
+    RETURNING id, name
+)
+INSERT INTO subject_aliases (alias, subject_id)
+SELECT normalize_subject(alias), i.id
+FROM inserted i
+JOIN children c ON c.name = i.name
+CROSS JOIN LATERAL unnest(c.aliases) AS alias;
+
+-- Every distinct free-text subject becomes a tag, values matching nothing above become new
+-- root subjects that admins can merge later
+DO $$
+DECLARE
+    raw TEXT;
+    found UUID;
+BEGIN
+    FOR raw IN
+        SELECT DISTINCT ON (normalize_subject(subject)) trim(subject)
+        FROM posts
+        WHERE trim(subject) <> ''
+        ORDER BY normalize_subject(subject), trim(subject)
+    LOOP
+        found := resolve_subject(raw);
+        IF found IS NULL THEN
+            INSERT INTO subjects (slug, name)
+            VALUES (next_subject_slug(raw), left(raw, 255))
+            RETURNING id INTO found;
+        END IF;
+
+        INSERT INTO post_subjects (post_id, subject_id, position)
+        SELECT id, found, 0 FROM posts WHERE normalize_subject(subject) = normalize_subject(raw)
+        ON CONFLICT DO NOTHING;
+    END LOOP;
+END;
+$$;
+
+INSERT INTO post_subjects (post_id, subject_id, position)
+SELECT p.id, resolve_subject('Inne'), 0
+FROM posts p
+WHERE NOT EXISTS (SELECT 1 FROM post_subjects ps WHERE ps.post_id = p.id);
+
+-- The search vector now takes the subject from the tags
+CREATE OR REPLACE FUNCTION update_posts_search_vector()
+RETURNS TRIGGER AS $$
+DECLARE
+    subject_text TEXT := post_subject_text(NEW.id);
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', subject_text), 'B') ||
+        setweight(to_tsvector('polish_search', subject_text), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.description, '')), 'C') ||
+        setweight(to_tsvector('polish_search', coalesce(NEW.description, '')), 'C');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_posts_search_vector ON posts;
+DROP INDEX IF EXISTS idx_posts_subject;
+ALTER TABLE posts DROP COLUMN subject;
+
+CREATE TRIGGER trigger_posts_search_vector
+    BEFORE INSERT OR UPDATE OF title, description ON posts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_posts_search_vector();
+
+-- Touching the title recomputes the search vector through the trigger above
+CREATE OR REPLACE FUNCTION refresh_tagged_posts_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE posts SET title = title WHERE id IN (SELECT post_id FROM changed_tags);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_post_subjects_insert_search
+    AFTER INSERT ON post_subjects
+    REFERENCING NEW TABLE AS changed_tags
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION refresh_tagged_posts_search_vector();
+
+CREATE TRIGGER trigger_post_subjects_delete_search
+    AFTER DELETE ON post_subjects
+    REFERENCING OLD TABLE AS changed_tags
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION refresh_tagged_posts_search_vector();
+
+-- Renaming or moving a subject changes the text of every post tagged below it
+CREATE OR REPLACE FUNCTION refresh_subject_posts_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE posts SET title = title
+    WHERE id IN (
+        SELECT ps.post_id FROM post_subjects ps
+        WHERE ps.subject_id IN (SELECT subject_descendants(NEW.id))
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_subjects_search
+    AFTER UPDATE OF name, parent_id ON subjects
+    FOR EACH ROW
+    EXECUTE FUNCTION refresh_subject_posts_search_vector();
+
+-- Search vector refreshes are not edits of the post
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Backfill with the new subject text
+UPDATE posts SET title = title;
@@ -0,0 +1,11 @@
+-- Drop drafts and scheduled publishing, unpublished posts are cancelled
+DROP INDEX IF EXISTS idx_posts_listed_at_id;
+ALTER TABLE posts DROP COLUMN IF EXISTS published_at;
+DROP INDEX IF EXISTS idx_posts_scheduled_publish_at;
+ALTER TABLE posts DROP CONSTRAINT IF EXISTS chk_posts_publish_at;
+ALTER TABLE posts DROP COLUMN IF EXISTS publish_at;
+
+UPDATE posts SET status = 'cancelled' WHERE status IN ('draft', 'scheduled');
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (status IN ('active', 'in_progress', 'completed', 'cancelled', 'expired'));
@@ -0,0 +1,24 @@
+-- Drafts and scheduled publishing: unpublished posts are only visible to their owner
+
+ALTER TABLE posts DROP CONSTRAINT chk_posts_status;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_status CHECK (
+    status IN ('draft', 'scheduled', 'active', 'in_progress', 'completed', 'cancelled', 'expired')
+);
+
+-- When a scheduled post goes live, only set while it is scheduled
+ALTER TABLE posts ADD COLUMN publish_at TIMESTAMPTZ;
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_publish_at CHECK ((status = 'scheduled') = (publish_at IS NOT NULL));
+
+CREATE INDEX idx_posts_scheduled_publish_at ON posts(publish_at) WHERE status = 'scheduled';
+
+-- When a post went live, newest first listings order by it so publishing a draft lists it as new
+-- while created_at stays the creation time
+ALTER TABLE posts ADD COLUMN published_at TIMESTAMPTZ;
+
+UPDATE posts SET published_at = created_at;
+
+-- Newest first listings and their cursor, drafts and scheduled posts in an owner's own
+-- listing have no publication yet and fall back to their creation
+CREATE INDEX idx_posts_listed_at_id ON posts ((COALESCE(published_at, created_at)) DESC, id DESC);
@@ -0,0 +1,27 @@
+-- Drop post reports and moderation
+DELETE FROM notification_preferences WHERE event_type IN ('report_outcome', 'moderation_notice');
+ALTER TABLE notification_preferences DROP CONSTRAINT chk_notification_preferences_event;
+ALTER TABLE notification_preferences
+ADD CONSTRAINT chk_notification_preferences_event CHECK (
+    event_type IN ('new_message', 'new_review', 'post_reply', 'deadline_reminder')
+);
+
+DROP TABLE IF EXISTS moderation_actions;
+DROP TABLE IF EXISTS post_reports;
+
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION IF EXISTS is_post_public(posts);
+ALTER TABLE posts DROP COLUMN IF EXISTS hidden_at;
+
+ALTER TABLE users DROP COLUMN IF EXISTS suspended_until;
+ALTER TABLE users DROP COLUMN IF EXISTS is_moderator;
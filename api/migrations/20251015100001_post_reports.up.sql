@@ -0,0 +1,78 @@
+-- Post reports and moderation: users flag posts, moderators act on them
+
+-- Granted by hand like admins, admins can moderate too
+ALTER TABLE users ADD COLUMN is_moderator BOOLEAN NOT NULL DEFAULT FALSE;
+-- Suspended users cannot log in or refresh their tokens until then
+ALTER TABLE users ADD COLUMN suspended_until TIMESTAMPTZ;
+
+-- Hidden posts are only visible to their owner
+ALTER TABLE posts ADD COLUMN hidden_at TIMESTAMPTZ;
+
+-- Whether everyone can see a post, drafts, scheduled and hidden posts are visible to the owner only
+CREATE OR REPLACE FUNCTION is_post_public(p posts)
+RETURNS BOOLEAN AS $$
+    SELECT p.status NOT IN ('draft', 'scheduled') AND p.hidden_at IS NULL
+$$ LANGUAGE sql STABLE;
+
+-- Moderation is not an edit of the post
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- A report stays open until a moderator resolves it, every open report of a post is resolved at once
+CREATE TABLE post_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(32) NOT NULL,
+    details TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolution VARCHAR(32),
+
+    CONSTRAINT chk_post_reports_reason CHECK (
+        reason IN ('scam', 'spam', 'inappropriate', 'harassment', 'academic_dishonesty', 'other')
+    ),
+    CONSTRAINT chk_post_reports_resolution CHECK (
+        resolution IN ('dismiss', 'hide_post', 'warn_user', 'suspend_user')
+    ),
+    CONSTRAINT chk_post_reports_resolved CHECK ((resolved_at IS NULL) = (resolution IS NULL))
+);
+
+-- One open report per user and post, so report counts are independent reporters
+CREATE UNIQUE INDEX idx_post_reports_open ON post_reports(post_id, reporter_id) WHERE resolved_at IS NULL;
+CREATE INDEX idx_post_reports_queue ON post_reports(created_at) WHERE resolved_at IS NULL;
+
+-- Everything moderators, or the automatic hiding, did
+CREATE TABLE moderation_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID REFERENCES posts(id) ON DELETE SET NULL,
+    target_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- NULL for automatic actions
+    moderator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(32) NOT NULL,
+    note TEXT,
+    suspended_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_moderation_actions_action CHECK (
+        action IN ('auto_hide', 'dismiss', 'hide_post', 'warn_user', 'suspend_user')
+    )
+);
+
+CREATE INDEX idx_moderation_actions_target ON moderation_actions(target_user_id, created_at DESC);
+
+-- Reporters hear about the outcome, owners about actions taken against them
+ALTER TABLE notification_preferences DROP CONSTRAINT chk_notification_preferences_event;
+ALTER TABLE notification_preferences
+ADD CONSTRAINT chk_notification_preferences_event CHECK (
+    event_type IN ('new_message', 'new_review', 'post_reply', 'deadline_reminder', 'report_outcome', 'moderation_notice')
+);
@@ -0,0 +1,21 @@
+-- Drop soft deletion, posts that are still deleted go away for good
+DELETE FROM posts WHERE deleted_at IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION is_post_public(p posts)
+RETURNS BOOLEAN AS $$
+    SELECT p.status NOT IN ('draft', 'scheduled') AND p.hidden_at IS NULL
+$$ LANGUAGE sql STABLE;
+
+DROP INDEX IF EXISTS idx_posts_deleted_at;
+ALTER TABLE posts DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,24 @@
+-- Soft deletion of posts: deleted posts leave feeds but keep their threads, reviews and history
+
+-- Set when the owner deletes the post, cleared again by a restore
+ALTER TABLE posts ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_posts_deleted_at ON posts(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Deleted posts are not public either
+CREATE OR REPLACE FUNCTION is_post_public(p posts)
+RETURNS BOOLEAN AS $$
+    SELECT p.status NOT IN ('draft', 'scheduled') AND p.hidden_at IS NULL AND p.deleted_at IS NULL
+$$ LANGUAGE sql STABLE;
+
+-- Deleting or restoring is not an edit of the post
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'deleted_at' - 'updated_at'
+        IS DISTINCT FROM to_jsonb(OLD) - 'view_count' - 'response_count' - 'deadline_reminded_for' - 'search_vector' - 'hidden_at' - 'deleted_at' - 'updated_at' THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
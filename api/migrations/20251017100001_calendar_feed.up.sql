@@ -0,0 +1,4 @@
+-- Secret calendar feed address per user, created on first use and rotated on request
+ALTER TABLE users ADD COLUMN calendar_token TEXT;
+
+CREATE UNIQUE INDEX idx_users_calendar_token ON users(calendar_token) WHERE calendar_token IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Back to a single mandatory price, posts without one get 0 again
+DROP INDEX IF EXISTS idx_posts_pricing_type;
+
+ALTER TABLE posts
+DROP CONSTRAINT IF EXISTS chk_posts_pricing,
+DROP CONSTRAINT IF EXISTS chk_posts_pricing_type,
+DROP CONSTRAINT IF EXISTS chk_posts_currency;
+
+UPDATE posts SET price = 0 WHERE price IS NULL;
+
+ALTER TABLE posts
+ALTER COLUMN price SET DEFAULT 0,
+ALTER COLUMN price SET NOT NULL,
+DROP COLUMN IF EXISTS pricing_type,
+DROP COLUMN IF EXISTS currency;
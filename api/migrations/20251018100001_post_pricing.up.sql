@@ -0,0 +1,24 @@
+-- Pricing model of posts: a currency and a pricing type next to the exact amount.
+-- Only fixed and hourly posts must have a price, negotiable ones may name a starting point
+-- and free (or barter) posts never have one.
+ALTER TABLE posts
+ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'PLN',
+ADD COLUMN pricing_type VARCHAR(16) NOT NULL DEFAULT 'fixed',
+ALTER COLUMN price DROP NOT NULL,
+ALTER COLUMN price DROP DEFAULT;
+
+-- A price of 0 was the only way to leave the amount open, it did not say the help was free
+UPDATE posts SET pricing_type = 'negotiable', price = NULL WHERE price = 0;
+
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_currency CHECK (currency ~ '^[A-Z]{3}$'),
+ADD CONSTRAINT chk_posts_pricing_type CHECK (pricing_type IN ('fixed', 'hourly', 'negotiable', 'free')),
+ADD CONSTRAINT chk_posts_pricing CHECK (
+    CASE pricing_type
+        WHEN 'free' THEN price IS NULL
+        WHEN 'negotiable' THEN price IS NULL OR price > 0
+        ELSE price IS NOT NULL AND price > 0
+    END
+);
+
+CREATE INDEX idx_posts_pricing_type ON posts(pricing_type);
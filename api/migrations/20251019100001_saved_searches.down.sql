@@ -0,0 +1,9 @@
+-- Drop saved searches
+DELETE FROM notification_preferences WHERE event_type = 'saved_search_match';
+ALTER TABLE notification_preferences DROP CONSTRAINT chk_notification_preferences_event;
+ALTER TABLE notification_preferences
+ADD CONSTRAINT chk_notification_preferences_event CHECK (
+    event_type IN ('new_message', 'new_review', 'post_reply', 'deadline_reminder', 'report_outcome', 'moderation_notice')
+);
+
+DROP TABLE IF EXISTS saved_searches;
@@ -0,0 +1,29 @@
+-- Saved post searches, users with alerts on are notified when a new post matches
+CREATE TABLE saved_searches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- Filters of GET /posts with their query parameter names
+    filters JSONB NOT NULL,
+    -- The same filters in the form post_matches_filters reads, so a new post is checked
+    -- against every saved search in a single query
+    post_filters JSONB NOT NULL,
+    alerts BOOLEAN NOT NULL DEFAULT TRUE,
+    last_alerted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_saved_searches_filters CHECK (jsonb_typeof(filters) = 'object'),
+    CONSTRAINT chk_saved_searches_post_filters CHECK (jsonb_typeof(post_filters) = 'object')
+);
+
+CREATE INDEX idx_saved_searches_user ON saved_searches(user_id, created_at);
+CREATE INDEX idx_saved_searches_alerts ON saved_searches(user_id) WHERE alerts;
+
+ALTER TABLE notification_preferences DROP CONSTRAINT chk_notification_preferences_event;
+ALTER TABLE notification_preferences
+ADD CONSTRAINT chk_notification_preferences_event CHECK (
+    event_type IN (
+        'new_message', 'new_review', 'post_reply', 'deadline_reminder', 'report_outcome',
+        'moderation_notice', 'saved_search_match'
+    )
+);
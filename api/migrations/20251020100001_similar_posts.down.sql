@@ -0,0 +1,4 @@
+-- Drop the similar posts ranking, pg_trgm stays installed in case anything else uses it
+DROP FUNCTION IF EXISTS similar_posts(UUID, INT);
+DROP FUNCTION IF EXISTS post_similarity_text(TEXT, TEXT);
+DROP FUNCTION IF EXISTS post_subject_closure(UUID);
@@ -0,0 +1,70 @@
+-- Ranking of posts similar to a given one, computed entirely in the database
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Subjects of a post together with their ancestors, so a Mechanics post and an Optics post
+-- still share Physics
+CREATE OR REPLACE FUNCTION post_subject_closure(post UUID)
+RETURNS UUID[] AS $$
+    WITH RECURSIVE tagged AS (
+        SELECT s.id, s.parent_id FROM post_subjects ps JOIN subjects s ON s.id = ps.subject_id
+        WHERE ps.post_id = post
+        UNION
+        SELECT s.id, s.parent_id FROM subjects s JOIN tagged t ON s.id = t.parent_id
+    )
+    SELECT coalesce(array_agg(id), '{}') FROM tagged;
+$$ LANGUAGE sql STABLE;
+
+-- Text compared with trigrams, folded like the search configuration so "całki" matches "calki".
+-- Long descriptions are cut so they do not drown the title.
+CREATE OR REPLACE FUNCTION post_similarity_text(title TEXT, description TEXT)
+RETURNS TEXT AS $$
+    SELECT unaccent(lower(title || ' ' || left(description, 1000)));
+$$ LANGUAGE sql STABLE;
+
+-- Other active public posts ranked by similarity to the given one, best first.
+-- The score is a weighted sum of parts between 0 and 1:
+--   0.35 subjects shared (Jaccard index of the subject closures)
+--   0.20 trigram similarity of the titles
+--   0.20 trigram similarity of title and description
+--   0.05 same difficulty, 0.05 same academic level
+--   0.15 recency, halving every 14 days
+-- Posts sharing no subject and hardly any text are left out whatever their score.
+CREATE OR REPLACE FUNCTION similar_posts(post UUID, max_results INT)
+RETURNS TABLE (post_id UUID, score FLOAT8) AS $$
+    WITH source AS (
+        SELECT p.id,
+               post_subject_closure(p.id) AS subjects,
+               unaccent(lower(p.title)) AS title,
+               post_similarity_text(p.title, p.description) AS body,
+               p.difficulty,
+               p.academic_level
+        FROM posts p
+        WHERE p.id = post
+    ), parts AS (
+        SELECT c.id,
+               CASE WHEN cardinality(closure.subjects) + cardinality(source.subjects) = 0 THEN 0
+                    ELSE (SELECT COUNT(*) FROM unnest(closure.subjects) s WHERE s = ANY(source.subjects))::FLOAT8
+                       / (SELECT COUNT(DISTINCT s) FROM unnest(closure.subjects || source.subjects) s)
+               END AS subject_overlap,
+               similarity(unaccent(lower(c.title)), source.title)::FLOAT8 AS title_similarity,
+               similarity(post_similarity_text(c.title, c.description), source.body)::FLOAT8 AS body_similarity,
+               (c.difficulty = source.difficulty) IS TRUE AS same_difficulty,
+               (c.academic_level = source.academic_level) IS TRUE AS same_level,
+               GREATEST(EXTRACT(EPOCH FROM NOW() - c.created_at)::FLOAT8 / 86400, 0) AS age_days
+        FROM source
+        JOIN posts c ON c.id <> source.id
+        CROSS JOIN LATERAL (SELECT post_subject_closure(c.id) AS subjects) closure
+        WHERE c.status = 'active' AND is_post_public(c)
+    )
+    SELECT id,
+           0.35 * subject_overlap
+           + 0.20 * title_similarity
+           + 0.20 * body_similarity
+           + 0.05 * same_difficulty::INT
+           + 0.05 * same_level::INT
+           + 0.15 * power(0.5, age_days / 14)
+    FROM parts
+    WHERE subject_overlap > 0 OR title_similarity >= 0.2 OR body_similarity >= 0.1
+    ORDER BY 2 DESC, id
+    LIMIT max_results;
+$$ LANGUAGE sql STABLE;
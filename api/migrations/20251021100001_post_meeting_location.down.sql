@@ -0,0 +1,16 @@
+-- Back to the free-text location only
+
+DROP INDEX IF EXISTS idx_posts_school_location;
+DROP INDEX IF EXISTS idx_posts_meeting_mode;
+
+ALTER TABLE posts
+DROP CONSTRAINT IF EXISTS chk_posts_school_location,
+DROP CONSTRAINT IF EXISTS chk_posts_meeting_mode,
+DROP COLUMN IF EXISTS school_location_id,
+DROP COLUMN IF EXISTS meeting_mode;
+
+DROP FUNCTION IF EXISTS school_location_tag(UUID);
+DROP FUNCTION IF EXISTS school_location_descendants(UUID);
+DROP FUNCTION IF EXISTS next_school_location_slug(TEXT, UUID);
+
+DROP TABLE IF EXISTS school_locations;
@@ -0,0 +1,93 @@
+-- Structured meeting details of posts: whether help happens online, in person or either way,
+-- and for in-person help an optional place at school. The free-text location stays as a note.
+
+-- Places defined by the school. Campuses hold buildings and buildings hold rooms.
+CREATE TABLE school_locations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slug VARCHAR(200) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    parent_id UUID REFERENCES school_locations(id) ON DELETE RESTRICT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_school_locations_slug CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    CONSTRAINT chk_school_locations_kind CHECK (kind IN ('campus', 'building', 'room')),
+    -- Which kind a parent must be is checked when a location is added
+    CONSTRAINT chk_school_locations_parent CHECK ((kind = 'campus') = (parent_id IS NULL))
+);
+
+-- Sibling names are unique regardless of case
+CREATE UNIQUE INDEX idx_school_locations_sibling_name
+    ON school_locations (COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'::uuid), lower(name));
+CREATE INDEX idx_school_locations_parent ON school_locations(parent_id);
+
+-- Slug that is not taken yet, prefixed with the parent's slug so rooms named "101" in
+-- different buildings stay apart, "Budynek A" in "Kampus Główny" -> "kampus-glowny-budynek-a"
+CREATE OR REPLACE FUNCTION next_school_location_slug(name TEXT, parent UUID)
+RETURNS TEXT AS $$
+DECLARE
+    base TEXT := trim(BOTH '-' FROM regexp_replace(normalize_subject(name), '[^a-z0-9]+', '-', 'g'));
+    candidate TEXT;
+    n INTEGER := 1;
+BEGIN
+    IF base = '' THEN
+        base := 'location';
+    END IF;
+    IF parent IS NOT NULL THEN
+        base := (SELECT slug FROM school_locations WHERE id = parent) || '-' || base;
+    END IF;
+    base := left(base, 180);
+    candidate := base;
+    WHILE EXISTS (SELECT 1 FROM school_locations WHERE slug = candidate) LOOP
+        n := n + 1;
+        candidate := base || '-' || n;
+    END LOOP;
+    RETURN candidate;
+END;
+$$ LANGUAGE plpgsql;
+
+-- A location and everything inside it
+CREATE OR REPLACE FUNCTION school_location_descendants(root UUID)
+RETURNS SETOF UUID AS $$
+    WITH RECURSIVE tree AS (
+        SELECT id FROM school_locations WHERE id = root
+        UNION
+        SELECT l.id FROM school_locations l JOIN tree t ON l.parent_id = t.id
+    )
+    SELECT id FROM tree;
+$$ LANGUAGE sql STABLE;
+
+-- Location as embedded in posts, with the names from the campus down, e.g. "Kampus Główny › Budynek A › 101"
+CREATE OR REPLACE FUNCTION school_location_tag(location UUID)
+RETURNS JSONB AS $$
+    WITH RECURSIVE path AS (
+        SELECT id, parent_id, name, 0 AS depth FROM school_locations WHERE id = location
+        UNION ALL
+        SELECT l.id, l.parent_id, l.name, p.depth + 1
+        FROM school_locations l JOIN path p ON l.id = p.parent_id
+    )
+    SELECT jsonb_build_object(
+        'id', l.id,
+        'slug', l.slug,
+        'name', l.name,
+        'kind', l.kind,
+        'path', (SELECT string_agg(name, ' › ' ORDER BY depth DESC) FROM path)
+    )
+    FROM school_locations l
+    WHERE l.id = location;
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE posts
+ADD COLUMN meeting_mode VARCHAR(16) NOT NULL DEFAULT 'either',
+ADD COLUMN school_location_id UUID REFERENCES school_locations(id) ON DELETE RESTRICT;
+
+-- Posts whose note only names a way of meeting online were online all along
+UPDATE posts SET meeting_mode = 'online'
+WHERE location ~* '\m(online|on-line|zdaln\w*|discord|teams|zoom|skype|google meet)\M';
+
+ALTER TABLE posts
+ADD CONSTRAINT chk_posts_meeting_mode CHECK (meeting_mode IN ('online', 'in_person', 'either')),
+ADD CONSTRAINT chk_posts_school_location CHECK (meeting_mode <> 'online' OR school_location_id IS NULL);
+
+CREATE INDEX idx_posts_meeting_mode ON posts(meeting_mode);
+CREATE INDEX idx_posts_school_location ON posts(school_location_id) WHERE school_location_id IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Drops post analytics events, view counts on posts stay
+
+DROP TABLE IF EXISTS post_events;
@@ -0,0 +1,29 @@
+-- Events behind the analytics post owners see: counted views and opened chat threads.
+-- Proposals are counted from post_proposals directly.
+CREATE TABLE post_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    event VARCHAR(32) NOT NULL,
+    -- Signed in user behind the event
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- HMAC of an anonymous viewer's IP keyed with VIEWER_HASH_SECRET
+    visitor_hash BIGINT,
+    thread_id UUID REFERENCES msg_threads(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_post_events_event CHECK (event IN ('view', 'thread_opened'))
+);
+
+CREATE INDEX idx_post_events_post ON post_events(post_id, event, created_at);
+
+-- Opening an existing thread again is not a new event
+CREATE UNIQUE INDEX idx_post_events_thread_opened ON post_events(thread_id) WHERE event = 'thread_opened';
+
+-- Threads opened so far, who opened them was not recorded so it is taken to be the
+-- participant other than the post owner
+INSERT INTO post_events (post_id, event, user_id, thread_id, created_at)
+SELECT t.post_id, 'thread_opened',
+       CASE WHEN t.user_a = p.owner_id THEN t.user_b ELSE t.user_a END,
+       t.id, t.created_at
+FROM msg_threads t
+JOIN posts p ON p.id = t.post_id;
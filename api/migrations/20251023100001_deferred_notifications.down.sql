@@ -0,0 +1,12 @@
+-- Pushes every notification on insert again, deferred ones are not pushed anymore
+
+DROP TRIGGER IF EXISTS notification_released_notify_trigger ON notifications;
+DROP TRIGGER IF EXISTS notification_notify_trigger ON notifications;
+
+CREATE TRIGGER notification_notify_trigger
+    AFTER INSERT ON notifications
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_new_notification();
+
+DROP INDEX IF EXISTS idx_notifications_deferred;
+ALTER TABLE notifications DROP COLUMN IF EXISTS deliver_after;
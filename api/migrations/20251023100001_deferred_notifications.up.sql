@@ -0,0 +1,20 @@
+-- Notifications created during the recipient's quiet hours are stored right away,
+-- only pushing them waits until the quiet hours end
+ALTER TABLE notifications ADD COLUMN deliver_after TIMESTAMPTZ;
+
+CREATE INDEX idx_notifications_deferred ON notifications(deliver_after) WHERE deliver_after IS NOT NULL;
+
+DROP TRIGGER notification_notify_trigger ON notifications;
+
+CREATE TRIGGER notification_notify_trigger
+    AFTER INSERT ON notifications
+    FOR EACH ROW
+    WHEN (NEW.deliver_after IS NULL)
+    EXECUTE FUNCTION notify_new_notification();
+
+-- Deferred notifications are pushed once the delivery job releases them
+CREATE TRIGGER notification_released_notify_trigger
+    AFTER UPDATE OF deliver_after ON notifications
+    FOR EACH ROW
+    WHEN (OLD.deliver_after IS NOT NULL AND NEW.deliver_after IS NULL)
+    EXECUTE FUNCTION notify_new_notification();
@@ -0,0 +1,17 @@
+-- Only changes to what the owner edits move updated_at. The columns are listed explicitly so
+-- bookkeeping columns added later (counters, moderation, deletion, reminders) never count as edits.
+CREATE OR REPLACE FUNCTION update_posts_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (NEW.title, NEW.description, NEW.type, NEW.status, NEW.publish_at, NEW.deadline, NEW.urgent,
+        NEW.price, NEW.currency, NEW.pricing_type, NEW.location, NEW.meeting_mode, NEW.school_location_id,
+        NEW.preferred_contact_method, NEW.academic_level, NEW.difficulty, NEW.assigned_helper_id)
+        IS DISTINCT FROM
+       (OLD.title, OLD.description, OLD.type, OLD.status, OLD.publish_at, OLD.deadline, OLD.urgent,
+        OLD.price, OLD.currency, OLD.pricing_type, OLD.location, OLD.meeting_mode, OLD.school_location_id,
+        OLD.preferred_contact_method, OLD.academic_level, OLD.difficulty, OLD.assigned_helper_id) THEN
+        NEW.updated_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
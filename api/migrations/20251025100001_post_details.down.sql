@@ -0,0 +1,6 @@
+-- Queries spell out the post columns and filters again
+
+DROP FUNCTION IF EXISTS post_matches_filters(posts, JSONB);
+DROP FUNCTION IF EXISTS is_school_location_within(UUID, TEXT);
+DROP FUNCTION IF EXISTS post_has_subject(UUID, JSONB);
+DROP VIEW IF EXISTS post_details;
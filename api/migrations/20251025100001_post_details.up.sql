@@ -0,0 +1,71 @@
+-- Posts as the API returns them and the filters of post listings, defined once for every query
+
+-- Post with its subjects, school location and the owner's name and rating
+CREATE VIEW post_details AS
+SELECT p.id, p.title, p.description, p.type,
+       COALESCE((
+           SELECT jsonb_agg(jsonb_build_object('id', s.id, 'slug', s.slug, 'name', s.name) ORDER BY ps.position)
+           FROM post_subjects ps
+           JOIN subjects s ON s.id = ps.subject_id
+           WHERE ps.post_id = p.id
+       ), '[]') AS subjects,
+       p.price, p.currency, p.pricing_type, p.deadline, p.urgent,
+       p.meeting_mode,
+       school_location_tag(p.school_location_id) AS school_location,
+       p.status, p.publish_at, p.hidden_at, p.deleted_at, p.created_at, p.updated_at, p.owner_id,
+       COALESCE(u.name, u.username) AS owner_name, u.username AS owner_username,
+       u.email AS owner_email, u.avatar AS owner_avatar,
+       COALESCE(r.average_score, 0) AS owner_rating,
+       COALESCE(r.review_count, 0) AS owner_review_count,
+       p.view_count, p.response_count,
+       p.location, p.preferred_contact_method, p.academic_level, p.difficulty,
+       p.assigned_helper_id, p.published_at
+FROM posts p
+JOIN users u ON u.id = p.owner_id
+LEFT JOIN user_ratings r ON r.user_id = p.owner_id;
+
+-- Whether the post is tagged with any of the subjects (slugs, names or aliases) or anything below them
+CREATE OR REPLACE FUNCTION post_has_subject(post UUID, inputs JSONB)
+RETURNS BOOLEAN AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM post_subjects ps
+        WHERE ps.post_id = post
+          AND ps.subject_id IN (SELECT subject_subtree(ARRAY(SELECT jsonb_array_elements_text(inputs))))
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Whether the location is the one with the slug or inside it
+CREATE OR REPLACE FUNCTION is_school_location_within(location UUID, location_slug TEXT)
+RETURNS BOOLEAN AS $$
+    SELECT location IN (
+        SELECT school_location_descendants(l.id) FROM school_locations l WHERE l.slug = lower(location_slug)
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Whether the post matches the criteria of a listing, as serialized from PostFilters.
+-- Missing keys match everything. Subqueries live in the helpers above so the planner can inline
+-- this function and drop the criteria that are not set.
+CREATE OR REPLACE FUNCTION post_matches_filters(p posts, f JSONB)
+RETURNS BOOLEAN AS $$
+    SELECT (f->>'owner_id' IS NULL OR p.owner_id = (f->>'owner_id')::uuid)
+       AND (f->>'type' IS NULL OR p.type = f->>'type')
+       AND (f->'subjects' IS NULL OR post_has_subject(p.id, f->'subjects'))
+       AND (f->>'min_price' IS NULL OR p.price >= (f->>'min_price')::numeric)
+       AND (f->>'max_price' IS NULL OR p.price <= (f->>'max_price')::numeric)
+       AND (f->'pricing_types' IS NULL OR f->'pricing_types' ? p.pricing_type)
+       AND (f->>'currency' IS NULL OR p.currency = f->>'currency')
+       AND (f->>'deadline_from' IS NULL OR p.deadline >= (f->>'deadline_from')::timestamptz)
+       AND (f->>'deadline_to' IS NULL OR p.deadline <= (f->>'deadline_to')::timestamptz)
+       AND (f->>'urgent' IS NULL OR p.urgent = (f->>'urgent')::boolean)
+       AND (f->>'status' IS NULL OR p.status = f->>'status')
+       AND (f->>'academic_level' IS NULL OR p.academic_level = f->>'academic_level')
+       AND (f->'difficulties' IS NULL OR f->'difficulties' ? p.difficulty)
+       AND (f->>'location' IS NULL OR strpos(lower(p.location), lower(f->>'location')) > 0)
+       AND (f->'meeting_modes' IS NULL OR f->'meeting_modes' ? p.meeting_mode)
+       AND (f->>'school_location' IS NULL OR is_school_location_within(p.school_location_id, f->>'school_location'))
+       AND (f->>'available_at' IS NULL
+            OR (p.type = 'offer' AND is_user_available(p.owner_id, (f->>'available_at')::timestamptz)))
+       AND (f->>'search' IS NULL OR p.search_vector @@ post_search_query(f->>'search'))
+       AND ((f->>'include_unpublished')::boolean IS TRUE OR is_post_public(p))
+       AND (p.deleted_at IS NOT NULL) = ((f->>'deleted')::boolean IS TRUE);
+$$ LANGUAGE sql STABLE;
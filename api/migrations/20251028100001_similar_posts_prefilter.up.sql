@@ -0,0 +1,92 @@
+-- Similar posts are picked through indexes first and only those candidates are ranked,
+-- instead of scoring every active post
+
+-- unaccent is only stable as it reads its dictionary by name, naming it here makes the folding
+-- usable in indexes
+CREATE OR REPLACE FUNCTION fold_for_similarity(input TEXT)
+RETURNS TEXT AS $$
+    SELECT public.unaccent('public.unaccent'::regdictionary, lower(input));
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT;
+
+CREATE OR REPLACE FUNCTION post_similarity_text(title TEXT, description TEXT)
+RETURNS TEXT AS $$
+    SELECT fold_for_similarity(title || ' ' || left(description, 1000));
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE;
+
+CREATE INDEX idx_posts_title_trgm ON posts USING GIN (fold_for_similarity(title) gin_trgm_ops);
+CREATE INDEX idx_posts_similarity_text_trgm ON posts
+    USING GIN (post_similarity_text(title, description) gin_trgm_ops);
+
+-- Posts whose title is at least as similar as similar_posts asks for, through the title index
+CREATE OR REPLACE FUNCTION posts_with_similar_title(folded_title TEXT)
+RETURNS SETOF UUID AS $$
+    SELECT p.id FROM posts p WHERE fold_for_similarity(p.title) % folded_title;
+$$ LANGUAGE sql STABLE SET pg_trgm.similarity_threshold = 0.2;
+
+-- Same for title and description together, which similar_posts accepts at a lower similarity
+CREATE OR REPLACE FUNCTION posts_with_similar_text(similarity_text TEXT)
+RETURNS SETOF UUID AS $$
+    SELECT p.id FROM posts p WHERE post_similarity_text(p.title, p.description) % similarity_text;
+$$ LANGUAGE sql STABLE SET pg_trgm.similarity_threshold = 0.1;
+
+-- Other active public posts ranked by similarity to the given one, best first.
+-- The score is a weighted sum of parts between 0 and 1:
+--   0.35 subjects shared (Jaccard index of the subject closures)
+--   0.20 trigram similarity of the titles
+--   0.20 trigram similarity of title and description
+--   0.05 same difficulty, 0.05 same academic level
+--   0.15 recency, halving every 14 days since publication
+-- Candidates share a subject or are close in text, both found through indexes. Posts sharing
+-- no subject and hardly any text are left out whatever their score.
+CREATE OR REPLACE FUNCTION similar_posts(post UUID, max_results INT)
+RETURNS TABLE (post_id UUID, score FLOAT8) AS $$
+    WITH source AS (
+        SELECT p.id,
+               post_subject_closure(p.id) AS subjects,
+               -- The closure holds the top level subjects, a post tagged anywhere below them shares one
+               ARRAY(
+                   SELECT subject_descendants(s) FROM unnest(post_subject_closure(p.id)) AS s
+               ) AS related_subjects,
+               fold_for_similarity(p.title) AS title,
+               post_similarity_text(p.title, p.description) AS body,
+               p.difficulty,
+               p.academic_level
+        FROM posts p
+        WHERE p.id = post
+    ), candidates AS (
+        SELECT ps.post_id AS id
+        FROM source
+        JOIN post_subjects ps ON ps.subject_id = ANY(source.related_subjects)
+        UNION
+        SELECT posts_with_similar_title(source.title) FROM source
+        UNION
+        SELECT posts_with_similar_text(source.body) FROM source
+    ), parts AS (
+        SELECT c.id,
+               CASE WHEN cardinality(closure.subjects) + cardinality(source.subjects) = 0 THEN 0
+                    ELSE (SELECT COUNT(*) FROM unnest(closure.subjects) s WHERE s = ANY(source.subjects))::FLOAT8
+                       / (SELECT COUNT(DISTINCT s) FROM unnest(closure.subjects || source.subjects) s)
+               END AS subject_overlap,
+               similarity(fold_for_similarity(c.title), source.title)::FLOAT8 AS title_similarity,
+               similarity(post_similarity_text(c.title, c.description), source.body)::FLOAT8 AS body_similarity,
+               (c.difficulty = source.difficulty) IS TRUE AS same_difficulty,
+               (c.academic_level = source.academic_level) IS TRUE AS same_level,
+               GREATEST(EXTRACT(EPOCH FROM NOW() - c.published_at)::FLOAT8 / 86400, 0) AS age_days
+        FROM source
+        JOIN candidates k ON k.id <> source.id
+        JOIN posts c ON c.id = k.id
+        CROSS JOIN LATERAL (SELECT post_subject_closure(c.id) AS subjects) closure
+        WHERE c.status = 'active' AND is_post_public(c)
+    )
+    SELECT id,
+           0.35 * subject_overlap
+           + 0.20 * title_similarity
+           + 0.20 * body_similarity
+           + 0.05 * same_difficulty::INT
+           + 0.05 * same_level::INT
+           + 0.15 * power(0.5, age_days / 14)
+    FROM parts
+    WHERE subject_overlap > 0 OR title_similarity >= 0.2 OR body_similarity >= 0.1
+    ORDER BY 2 DESC, id
+    LIMIT max_results;
+$$ LANGUAGE sql STABLE;
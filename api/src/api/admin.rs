@@ -0,0 +1,74 @@
+// Admin only endpoints
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{
+        auth::AdminToken,
+        scheduler::{Job, JobStatus},
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct JobStatusResponse {
+    #[serde(flatten)]
+    pub status: JobStatus,
+    pub interval_seconds: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetJobsResponse {
+    pub jobs: Vec<JobStatusResponse>,
+}
+
+// GET /admin/jobs - Last run of every background job, across all instances
+pub async fn get_jobs(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+) -> impl IntoResponse {
+    let db = &app.db;
+    tracing::info!("Admin {} requested job statuses", admin.sub);
+
+    let mut statuses = match db::jobs::get_job_statuses(db).await {
+        Ok(statuses) => statuses,
+        Err(e) => {
+            tracing::error!("Failed to fetch job statuses: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch job statuses".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    // Jobs that never ran are listed too
+    let jobs = Job::ALL
+        .iter()
+        .map(|job| {
+            let status = statuses
+                .iter()
+                .position(|s| s.name == job.name())
+                .map(|i| statuses.swap_remove(i))
+                .unwrap_or_else(|| JobStatus {
+                    name: job.name().to_string(),
+                    last_started_at: None,
+                    last_finished_at: None,
+                    last_succeeded: None,
+                    last_error: None,
+                    last_affected: None,
+                    last_instance: None,
+                    run_count: 0,
+                });
+            JobStatusResponse {
+                status,
+                interval_seconds: job.interval().as_secs(),
+            }
+        })
+        .collect();
+
+    (StatusCode::OK, Json(GetJobsResponse { jobs })).into_response()
+}
@@ -2,17 +2,27 @@
 
 use crate::{
     api::{
+        admin,
+        attachment,
         auth::{login, refresh, register},
+        availability,
         chat,
+        feed,
+        moderation,
+        notifications,
         post,
+        proposal,
         review,
+        saved_search,
+        school_location,
+        subject,
         user,
     },
-    server::auth::AccessToken,
+    server::{attachment::MAX_ATTACHMENT_SIZE, auth::AccessToken},
 };
 use axum::{
     Router,
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     http::{HeaderValue, Method},
     routing::{delete, get, post as post_method, put},
 };
@@ -21,73 +31,136 @@ use tower_http::{trace::TraceLayer, cors::{CorsLayer, AllowOrigin}};
 use crate::app::AppState;
 
 pub fn init_router() -> Router<AppState> {
-    let router =
-        Router::new()
-            .nest(
-                "/auth",
-                Router::new()
-                    .route("/register", post_method(register))
-                    .route("/login", post_method(login))
-                    .route("/refresh", post_method(refresh)),
-            )
-            .nest(
-                "/posts",
-                Router::new()
-                    .route("/", get(post::get_posts))
-                    .route("/{id}", get(post::get_post_by_id))
-                    .route("/create", post_method(post::create_post))
-                    .route("/{id}", put(post::update_post))
-                    .route("/{id}", delete(post::delete_post)),
-            )
-            .nest(
-                "/user",
-                Router::new()
-                    .route("/me", get(user::get_current_user)),
-            )
-            .nest(
-                "/users",
-                Router::new()
-                    .route("/", get(user::get_all_users))
-                    .route("/{id}", get(user::get_user_by_id))
-                    .route("/{id}", put(user::update_user)),
-            )
-            .nest(
-                "/reviews",
-                Router::new()
-                    .route("/", get(review::get_reviews))
-                    .route("/", post_method(review::create_review))
-                    .route("/stats/{id}", get(review::get_review_stats))
-                    .route("/{id}", delete(review::delete_review)),
-            )
-            .nest(
-                "/chat",
-                Router::new()
-                    .route("/ws", get(chat::websocket_handler)),
-            )
-            .route(
-                "/test",
-                get(
-                    |State(_app): State<AppState>, token: AccessToken| async move {
-                        token.sub.to_string()
-                    },
-                ),
-            )
-            .layer(
-                CorsLayer::new()
-                    .allow_origin(AllowOrigin::list(vec![
-                        "https://oxylize.com".parse::<HeaderValue>().unwrap(),
-                        "https://www.oxylize.com".parse::<HeaderValue>().unwrap(),
-                        "http://localhost:3000".parse::<HeaderValue>().unwrap(),
-                    ]))
-                    .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-                    .allow_headers([
-                        axum::http::header::CONTENT_TYPE,
-                        axum::http::header::AUTHORIZATION,
-                        axum::http::header::HeaderName::from_static("x-requested-with"),
-                    ])
-                    .allow_credentials(true)
-            )
-            .layer(TraceLayer::new_for_http());
-
-    router
+    Router::new()
+        .nest(
+            "/auth",
+            Router::new()
+                .route("/register", post_method(register))
+                .route("/login", post_method(login))
+                .route("/refresh", post_method(refresh)),
+        )
+        .nest(
+            "/posts",
+            Router::new()
+                .route("/", get(post::get_posts))
+                .route("/{id}", get(post::get_post_by_id))
+                .route("/create", post_method(post::create_post))
+                .route("/{id}", put(post::update_post))
+                .route("/{id}", delete(post::delete_post))
+                .route("/{id}/save", post_method(post::save_post))
+                .route("/{id}/save", delete(post::unsave_post))
+                .route("/{id}/status", post_method(post::change_post_status))
+                .route("/{id}/publish", post_method(post::publish_post))
+                .route("/{id}/restore", post_method(post::restore_post))
+                .route("/{id}/report", post_method(moderation::report_post))
+                .route("/{id}/status/history", get(post::get_post_status_history))
+                .route("/{id}/revisions", get(post::get_post_revisions))
+                .route("/{id}/similar", get(post::get_similar_posts))
+                .route("/{id}/analytics", get(post::get_post_analytics))
+                .route(
+                    "/{id}/attachments",
+                    post_method(attachment::upload_attachment)
+                        // Room for the multipart framing around the largest allowed file
+                        .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_SIZE + 64 * 1024)),
+                )
+                .route("/{id}/attachments/{attachment_id}", get(attachment::download_attachment))
+                .route("/{id}/attachments/{attachment_id}", delete(attachment::delete_attachment))
+                .route("/{id}/proposals", get(proposal::get_proposals))
+                .route("/{id}/proposals", post_method(proposal::create_proposal))
+                .route("/{id}/proposals/{proposal_id}", delete(proposal::withdraw_proposal))
+                .route("/{id}/proposals/{proposal_id}/accept", post_method(proposal::accept_proposal))
+                .route("/{id}/proposals/{proposal_id}/reject", post_method(proposal::reject_proposal)),
+        )
+        .nest(
+            "/user",
+            Router::new()
+                .route("/me", get(user::get_current_user))
+                .route("/me/posts", get(user::get_my_posts))
+                .route("/me/posts/deleted", get(user::get_my_deleted_posts))
+                .route("/me/saved", get(user::get_saved_posts))
+                .route("/me/saved-searches", get(saved_search::get_my_saved_searches))
+                .route("/me/saved-searches", post_method(saved_search::create_saved_search))
+                .route("/me/saved-searches/{id}", delete(saved_search::delete_saved_search))
+                .route("/me/availability", get(availability::get_my_availability))
+                .route("/me/availability", put(availability::update_my_availability))
+                .route("/me/calendar", get(feed::get_my_calendar))
+                .route("/me/calendar/reset", post_method(feed::reset_my_calendar))
+                .route("/me/notifications", get(notifications::get_my_notifications))
+                .route("/me/notifications/read", post_method(notifications::mark_my_notifications_read))
+                .route("/me/notifications/preferences", get(notifications::get_my_preferences))
+                .route("/me/notifications/preferences", put(notifications::update_my_preferences))
+                .route("/me/following/posts", get(user::get_following_posts)),
+        )
+        .nest(
+            "/users",
+            Router::new()
+                .route("/", get(user::get_all_users))
+                .route("/{id}", get(user::get_user_by_id))
+                .route("/{id}", put(user::update_user))
+                .route("/{id}/stats", get(user::get_user_stats))
+                .route("/{id}/follow", post_method(user::follow_user))
+                .route("/{id}/follow", delete(user::unfollow_user)),
+        )
+        .nest(
+            "/reviews",
+            Router::new()
+                .route("/", get(review::get_reviews))
+                .route("/", post_method(review::create_review))
+                .route("/stats/{id}", get(review::get_review_stats))
+                .route("/{id}", delete(review::delete_review)),
+        )
+        .route("/subjects", get(subject::get_subjects))
+        .route("/school-locations", get(school_location::get_school_locations))
+        .nest(
+            "/admin",
+            Router::new()
+                .route("/jobs", get(admin::get_jobs))
+                .route("/subjects", post_method(subject::create_subject))
+                .route("/subjects/{id}", put(subject::rename_subject))
+                .route("/subjects/{id}/merge", post_method(subject::merge_subject))
+                .route("/school-locations", post_method(school_location::create_school_location))
+                .route("/school-locations/{id}", put(school_location::rename_school_location)),
+        )
+        .nest(
+            "/feeds",
+            Router::new()
+                .route("/posts.atom", get(feed::get_posts_atom))
+                .route("/posts.rss", get(feed::get_posts_rss))
+                .route("/calendar/{file}", get(feed::get_calendar)),
+        )
+        .nest(
+            "/moderation",
+            Router::new()
+                .route("/reports", get(moderation::get_moderation_queue))
+                .route("/posts/{id}/resolve", post_method(moderation::resolve_reports)),
+        )
+        .nest(
+            "/chat",
+            Router::new()
+                .route("/ws", get(chat::websocket_handler)),
+        )
+        .route(
+            "/test",
+            get(
+                |State(_app): State<AppState>, token: AccessToken| async move {
+                    token.sub.to_string()
+                },
+            ),
+        )
+        .layer(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(vec![
+                    "https://oxylize.com".parse::<HeaderValue>().unwrap(),
+                    "https://www.oxylize.com".parse::<HeaderValue>().unwrap(),
+                    "http://localhost:3000".parse::<HeaderValue>().unwrap(),
+                ]))
+                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+                .allow_headers([
+                    axum::http::header::CONTENT_TYPE,
+                    axum::http::header::AUTHORIZATION,
+                    axum::http::header::HeaderName::from_static("x-requested-with"),
+                ])
+                .allow_credentials(true)
+        )
+        .layer(TraceLayer::new_for_http())
 }
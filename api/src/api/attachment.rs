@@ -0,0 +1,260 @@
+// Attachment endpoints: files uploaded by post owners
+
+use axum::{
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        Multipart, Path, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::{Bytes, BytesMut};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    api::{post::find_readable_post, user::ErrorResponse},
+    app::AppState,
+    db::{self, attachments::CreateAttachment},
+    server::{
+        attachment::{
+            content_disposition, sanitize_file_name, sniff_content_type, storage_key,
+            PostAttachment, MAX_ATTACHMENTS_PER_POST, MAX_ATTACHMENT_SIZE, USER_ATTACHMENT_QUOTA,
+        },
+        auth::AccessToken,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentResponse {
+    pub attachment: PostAttachment,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteAttachmentResponse {
+    pub message: String,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+/// Checks that the post exists and belongs to the user
+async fn require_owner(app: &AppState, post_id: Uuid, user_id: Uuid) -> Result<(), Response> {
+    match db::posts::get_post_by_id(&app.db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.deleted_at.is_some() => Err(error(StatusCode::NOT_FOUND, "Post not found")),
+        Ok(Some(post)) if post.owner_id == user_id => Ok(()),
+        Ok(Some(post)) if post.is_visible_to(Some(user_id)) => Err(error(
+            StatusCode::FORBIDDEN,
+            "Only the owner can manage attachments of this post",
+        )),
+        // Drafts and scheduled posts of others are reported missing rather than forbidden
+        Ok(_) => Err(error(StatusCode::NOT_FOUND, "Post not found")),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch post"))
+        }
+    }
+}
+
+/// Reads the `file` field of the form, refusing anything larger than the upload limit
+async fn read_file(multipart: &mut Multipart) -> Result<(String, Bytes), Response> {
+    let too_large = || {
+        error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            &format!("Files can be at most {} MB", MAX_ATTACHMENT_SIZE / (1024 * 1024)),
+        )
+    };
+    let invalid = |e: MultipartError| {
+        if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            return too_large();
+        }
+        tracing::warn!("Invalid attachment upload: {:?}", e);
+        error(StatusCode::BAD_REQUEST, "Invalid multipart body")
+    };
+
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = sanitize_file_name(field.file_name());
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.chunk().await.map_err(invalid)? {
+            if data.len() + chunk.len() > MAX_ATTACHMENT_SIZE {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        return Ok((file_name, data.freeze()));
+    }
+
+    Err(error(StatusCode::BAD_REQUEST, "Missing 'file' field"))
+}
+
+// POST /posts/:id/attachments - Upload a file to a post, multipart form with a `file` field
+pub async fn upload_attachment(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let mut multipart = match multipart {
+        Ok(multipart) => multipart,
+        Err(rejection) => return error(StatusCode::BAD_REQUEST, &rejection.body_text()),
+    };
+
+    if let Err(response) = require_owner(&app, post_id, user_id).await {
+        return response;
+    }
+
+    let (file_name, data) = match read_file(&mut multipart).await {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+
+    if data.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "File cannot be empty");
+    }
+
+    let Some(content_type) = sniff_content_type(&data) else {
+        return error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Only images, PDFs, office documents, zip archives and plain text can be attached",
+        );
+    };
+
+    let attachment_id = Uuid::new_v4();
+    let key = storage_key(post_id, attachment_id);
+
+    let attachment = match db::attachments::create_attachment(
+        &app.db,
+        attachment_id,
+        post_id,
+        user_id,
+        &file_name,
+        content_type,
+        data.len() as i64,
+        &key,
+        USER_ATTACHMENT_QUOTA,
+        MAX_ATTACHMENTS_PER_POST,
+    )
+    .await
+    {
+        Ok(CreateAttachment::Created(attachment)) => attachment,
+        Ok(CreateAttachment::PostFull) => {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("A post can have at most {} attachments", MAX_ATTACHMENTS_PER_POST),
+            );
+        }
+        Ok(CreateAttachment::OverQuota(used)) => {
+            return error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                &format!(
+                    "Attachment quota exceeded, {} of {} bytes used",
+                    used, USER_ATTACHMENT_QUOTA
+                ),
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to create attachment: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to upload attachment");
+        }
+    };
+
+    // The row is written first so the quota holds, it is removed again if storing fails
+    if let Err(e) = app.blob_store.put(&key, data, content_type).await {
+        tracing::error!("Failed to store attachment {}: {:?}", attachment_id, e);
+        if let Err(e) = db::attachments::delete_attachment(&app.db, attachment_id).await {
+            tracing::error!("Failed to remove attachment {}: {:?}", attachment_id, e);
+        }
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to upload attachment");
+    }
+
+    (StatusCode::CREATED, Json(AttachmentResponse { attachment })).into_response()
+}
+
+// GET /posts/:id/attachments/:attachment_id - Download an attachment, for everyone who can read the post
+pub async fn download_attachment(
+    State(app): State<AppState>,
+    token: Option<AccessToken>,
+    Path((post_id, attachment_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let viewer_id = token.map(|t| t.sub);
+    if let Err(response) = find_readable_post(&app.db, post_id, viewer_id, "Failed to fetch attachment").await {
+        return response;
+    }
+
+    let attachment = match db::attachments::get_attachment(&app.db, post_id, attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Attachment not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch attachment: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch attachment");
+        }
+    };
+
+    match app.blob_store.get(&attachment.storage_key).await {
+        Ok(Some(data)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, attachment.content_type.clone()),
+                (header::CONTENT_DISPOSITION, content_disposition(&attachment.file_name)),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            ],
+            data,
+        )
+            .into_response(),
+        Ok(None) => {
+            tracing::error!("Attachment {} is missing from the blob store", attachment_id);
+            error(StatusCode::NOT_FOUND, "Attachment not found")
+        }
+        Err(e) => {
+            tracing::error!("Failed to read attachment {}: {:?}", attachment_id, e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch attachment")
+        }
+    }
+}
+
+// DELETE /posts/:id/attachments/:attachment_id - Remove an attachment, owner only
+pub async fn delete_attachment(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path((post_id, attachment_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    if let Err(response) = require_owner(&app, post_id, token.sub).await {
+        return response;
+    }
+
+    let attachment = match db::attachments::get_attachment(&app.db, post_id, attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Attachment not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch attachment: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete attachment");
+        }
+    };
+
+    if let Err(e) = db::attachments::delete_attachment(&app.db, attachment_id).await {
+        tracing::error!("Failed to delete attachment: {:?}", e);
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete attachment");
+    }
+
+    // A leftover blob only wastes space, the attachment is already gone for clients
+    if let Err(e) = app.blob_store.delete(&attachment.storage_key).await {
+        tracing::error!("Failed to remove blob of attachment {}: {:?}", attachment_id, e);
+    }
+
+    (
+        StatusCode::OK,
+        Json(DeleteAttachmentResponse {
+            message: "Attachment deleted successfully".to_string(),
+        }),
+    )
+        .into_response()
+}
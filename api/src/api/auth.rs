@@ -15,6 +15,14 @@ use crate::{
 
 const REFRESH_COOKIE_IDENT: &str = "refresh_token";
 
+// Suspended accounts are refused rather than treated as bad input
+fn credential_error_status(err: &CredentialError) -> StatusCode {
+    match err {
+        CredentialError::AccountSuspended => StatusCode::FORBIDDEN,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
 fn build_refresh_cookie(token: String) -> Cookie<'static> {
     let cookie = Cookie::build((REFRESH_COOKIE_IDENT, token))
         .http_only(true)
@@ -102,7 +110,7 @@ pub async fn login(
         Auth::mint_refresh_token(credentials, db)
             .await
             .map_err(|err| match err {
-                AppError::CredentialError(err) => (StatusCode::BAD_REQUEST, Json(vec![err])),
+                AppError::CredentialError(err) => (credential_error_status(&err), Json(vec![err])),
                 _ => (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::new())),
             })?;
 
@@ -110,7 +118,7 @@ pub async fn login(
         Auth::mint_access_token(&refresh_token, db)
             .await
             .map_err(|err| match err {
-                AppError::CredentialError(err) => (StatusCode::BAD_REQUEST, Json(vec![err])),
+                AppError::CredentialError(err) => (credential_error_status(&err), Json(vec![err])),
                 _ => (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::new())),
             })?;
 
@@ -157,7 +165,7 @@ pub async fn refresh(
         Auth::mint_access_token(&refresh_token, db)
             .await
             .map_err(|err| match err {
-                AppError::CredentialError(err) => (StatusCode::BAD_REQUEST, Json(vec![err])),
+                AppError::CredentialError(err) => (credential_error_status(&err), Json(vec![err])),
                 _ => (StatusCode::UNAUTHORIZED, Json(Vec::new())),
             })?;
 
@@ -0,0 +1,105 @@
+// Availability schedule endpoints
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{auth::AccessToken, availability::Availability},
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetAvailabilityResponse {
+    pub availability: Availability,
+}
+
+// GET /user/me/availability - Get the current user's weekly schedule and exceptions
+pub async fn get_my_availability(
+    State(app): State<AppState>,
+    token: AccessToken,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::availability::get_availability(db, token.sub).await {
+        Ok(availability) => (
+            StatusCode::OK,
+            Json(GetAvailabilityResponse { availability }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch availability: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch availability".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// PUT /user/me/availability - Replace the current user's schedule
+pub async fn update_my_availability(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Json(request): Json<Availability>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    if let Err(message) = request.validate() {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+    }
+
+    match db::availability::is_valid_time_zone(db, &request.time_zone).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(format!(
+                    "Unknown time zone '{}'",
+                    request.time_zone
+                ))),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to validate time zone: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to update availability".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    if let Err(e) = db::availability::set_availability(db, token.sub, &request).await {
+        tracing::error!("Failed to update availability: {:?}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("Failed to update availability".to_string())),
+        )
+            .into_response();
+    }
+
+    match db::availability::get_availability(db, token.sub).await {
+        Ok(availability) => (
+            StatusCode::OK,
+            Json(GetAvailabilityResponse { availability }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch availability: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch availability".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
@@ -3,11 +3,12 @@
 use crate::{
     app::AppState,
     common::env,
-    db::messages as db_messages,
+    db::{self, messages as db_messages},
     error::{AppError, Result},
     server::{
         auth::{AccessToken, JwtToken},
         chat::{ChatCommand, ChatResponse, MessageInfo, MessageNotification},
+        notifications::{self, Notification, NotificationChannel, NotificationEvent},
     },
 };
 use axum::extract::{
@@ -179,6 +180,12 @@ async fn start_postgres_listener(
         info!("User {} listening to channel {}", user_id, channel);
     }
 
+    // In-app notifications addressed to this user
+    let user_channel = format!("user_{}", user_id);
+    if let Err(e) = listener.listen(&user_channel).await {
+        error!("Failed to listen to channel {} for user {}: {}", user_channel, user_id, e);
+    }
+
     // Process notifications with proper error handling
     loop {
         match listener.try_recv().await {
@@ -212,8 +219,14 @@ async fn handle_notification(
     let channel = notification.channel();
     let payload = notification.payload();
 
+    if channel.starts_with("user_") {
+        let notification: Notification = serde_json::from_str(payload)?;
+        send_to_user(connection_manager, user_id, ChatResponse::Notification { notification }).await;
+        return Ok(());
+    }
+
     // Parse thread_id from channel name
-    let _thread_id = if let Some(id_str) = channel.strip_prefix("thread_") {
+    let thread_id = if let Some(id_str) = channel.strip_prefix("thread_") {
         Uuid::parse_str(id_str)?
     } else {
         warn!("Invalid channel name: {}", channel);
@@ -240,11 +253,22 @@ async fn handle_notification(
         sent_at: msg_notification.sent_at,
     };
 
+    // The sender's own messages come back on the channel too, those never notify
+    let notify = msg_notification.sender_id != user_id
+        && new_message_notify(db, user_id, thread_id).await;
+
     let response = ChatResponse::NewMessage {
         message: message_info,
+        notify,
     };
 
-    // Send to user's connections
+    send_to_user(connection_manager, user_id, response).await;
+
+    Ok(())
+}
+
+/// Sends a response to all of the user's connections on this instance
+pub async fn send_to_user(connection_manager: &ConnectionManager, user_id: Uuid, response: ChatResponse) {
     let connections = connection_manager.read().await;
     if let Some(user_connections) = connections.get(&user_id) {
         for conn in user_connections {
@@ -253,8 +277,25 @@ async fn handle_notification(
             }
         }
     }
+}
 
-    Ok(())
+/// Consults the notification preferences for an incoming chat message,
+/// falls back to notifying if they cannot be read. The message itself is always delivered,
+/// during quiet hours it only arrives without an alert.
+async fn new_message_notify(db: &sqlx::PgPool, user_id: Uuid, thread_id: Uuid) -> bool {
+    notifications::should_notify(
+        db,
+        user_id,
+        NotificationEvent::NewMessage,
+        NotificationChannel::InApp,
+        Some(thread_id),
+    )
+    .await
+    .map(|delivery| delivery.is_now())
+    .unwrap_or_else(|e| {
+        error!("Failed to read notification preferences for user {}: {:?}", user_id, e);
+        true
+    })
 }
 
 /// Send messages to WebSocket
@@ -263,11 +304,11 @@ async fn start_message_sender(
     mut rx: mpsc::UnboundedReceiver<ChatResponse>,
 ) {
     while let Some(response) = rx.recv().await {
-        if let Ok(json) = serde_json::to_string(&response) {
-            if let Err(e) = ws_sender.send(Message::Text(json.into())).await {
-                error!("Failed to send WebSocket message: {:?}", e);
-                break;
-            }
+        if let Ok(json) = serde_json::to_string(&response)
+            && let Err(e) = ws_sender.send(Message::Text(json.into())).await
+        {
+            error!("Failed to send WebSocket message: {:?}", e);
+            break;
         }
     }
 }
@@ -280,10 +321,10 @@ async fn start_command_receiver(
     connection_manager: ConnectionManager,
 ) {
     while let Some(msg) = ws_receiver.next().await {
-        if let Ok(Message::Text(text)) = msg {
-            if let Err(e) = process_command(text.to_string(), &db, user_id, &connection_manager).await {
-                error!("Error processing command: {:?}", e);
-            }
+        if let Ok(Message::Text(text)) = msg
+            && let Err(e) = process_command(text.to_string(), &db, user_id, &connection_manager).await
+        {
+            error!("Error processing command: {:?}", e);
         }
     }
 }
@@ -301,6 +342,11 @@ async fn process_command(
         ChatCommand::CreateThread { post_id, other_user_id } => {
             let thread = db_messages::create_thread(db, post_id, user_id, other_user_id).await?;
 
+            // Analytics are not worth failing the thread for
+            if let Err(e) = db::post_events::add_thread_opened(db, thread.post_id, thread.id, user_id).await {
+                error!("Failed to record opened thread: {:?}", e);
+            }
+
             // Get thread info for creator response
             let creator_threads = db_messages::get_user_threads(db, user_id).await?;
             let thread_info = creator_threads.into_iter().find(|t| t.id == thread.id)
@@ -339,9 +385,11 @@ async fn process_command(
             };
 
             // Proactively fanout to the other participant as a NewMessage
-            if let Some(thread) = db_messages::get_thread_by_id(db, thread_id, user_id).await? {
-                let other_user_id = if thread.user_a == user_id { thread.user_b } else { thread.user_a };
-                let new_msg_response = ChatResponse::NewMessage { message: message_info.clone() };
+            if let Some(thread) = db_messages::get_thread_by_id(db, thread_id, user_id).await?
+                && let Some(other_user_id) = thread.get_other_user(&user_id)
+            {
+                let notify = new_message_notify(db, other_user_id, thread_id).await;
+                let new_msg_response = ChatResponse::NewMessage { message: message_info.clone(), notify };
                 let connections = connection_manager.read().await;
                 if let Some(other_user_conns) = connections.get(&other_user_id) {
                     for conn in other_user_conns {
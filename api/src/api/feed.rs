@@ -0,0 +1,172 @@
+// Syndication feeds: posts for feed readers and a per-user deadline calendar
+
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, RawQuery, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+
+use crate::{
+    api::{post::GetPostsQuery, user::ErrorResponse},
+    app::AppState,
+    db,
+    server::{
+        auth::AccessToken,
+        feed::{generate_calendar_token, render_calendar, FeedFormat, PublicUrls, CALENDAR_PAST_DAYS},
+        pagination::clamp_per_page,
+        post::PostStatus,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct CalendarFeedResponse {
+    // Secret address to subscribe to, anyone who has it can read the calendar
+    pub url: String,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+// GET /feeds/posts.atom - Newest posts as an Atom feed, takes the same filters as GET /posts
+pub async fn get_posts_atom(
+    State(app): State<AppState>,
+    RawQuery(raw_query): RawQuery,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    posts_feed(&app, FeedFormat::Atom, "posts.atom", raw_query, query).await
+}
+
+// GET /feeds/posts.rss - Newest posts as an RSS feed, takes the same filters as GET /posts
+pub async fn get_posts_rss(
+    State(app): State<AppState>,
+    RawQuery(raw_query): RawQuery,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    posts_feed(&app, FeedFormat::Rss, "posts.rss", raw_query, query).await
+}
+
+/// Posts feed in the given format, feeds are anonymous so only public posts are listed
+async fn posts_feed(
+    app: &AppState,
+    format: FeedFormat,
+    file: &str,
+    raw_query: Option<String>,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
+) -> Response {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(rejection) => return error(StatusCode::BAD_REQUEST, &rejection.body_text()),
+    };
+
+    let filters = match query.filters() {
+        Ok(filters) => filters,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+
+    if let Some(status) = filters.status.as_deref().and_then(PostStatus::parse)
+        && !status.is_published()
+    {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Drafts and scheduled posts are not part of feeds",
+        );
+    }
+
+    let per_page = clamp_per_page(query.per_page, 20);
+    let offset = query.page.unwrap_or(0).max(0).saturating_mul(per_page);
+
+    let posts = match db::posts::get_posts_filtered(&app.db, &filters, None, offset, per_page, None).await {
+        Ok(posts) => posts,
+        Err(e) => {
+            tracing::error!("Failed to fetch posts for feed: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch posts");
+        }
+    };
+
+    let urls = PublicUrls::from_env();
+    let self_url = match raw_query.filter(|q| !q.is_empty()) {
+        Some(raw_query) => format!("{}/feeds/{}?{}", urls.api, file, raw_query),
+        None => format!("{}/feeds/{}", urls.api, file),
+    };
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, format.content_type())],
+        format.render(&posts, &self_url, &urls),
+    )
+        .into_response()
+}
+
+// GET /feeds/calendar/:token.ics - Deadlines of the posts a user owns or helps with,
+// the token from GET /user/me/calendar is the only authentication
+pub async fn get_calendar(
+    State(app): State<AppState>,
+    Path(file): Path<String>,
+) -> impl IntoResponse {
+    let token = file.strip_suffix(".ics").unwrap_or(&file);
+
+    let user_id = match db::users::get_user_by_calendar_token(&app.db, token).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Calendar not found"),
+        Err(e) => {
+            tracing::error!("Failed to look up calendar token: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch calendar");
+        }
+    };
+
+    let since = Utc::now() - Duration::days(CALENDAR_PAST_DAYS);
+    match db::posts::get_calendar_deadlines(&app.db, user_id, since).await {
+        Ok(deadlines) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            render_calendar(&deadlines, &PublicUrls::from_env()),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch deadlines: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch calendar")
+        }
+    }
+}
+
+// GET /user/me/calendar - Address of the current user's deadline calendar, created on first use
+pub async fn get_my_calendar(State(app): State<AppState>, token: AccessToken) -> impl IntoResponse {
+    match db::users::get_or_set_calendar_token(&app.db, token.sub, &generate_calendar_token()).await {
+        Ok(Some(calendar_token)) => (
+            StatusCode::OK,
+            Json(CalendarFeedResponse {
+                url: PublicUrls::from_env().calendar(&calendar_token),
+            }),
+        )
+            .into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, "User not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch calendar token: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch calendar")
+        }
+    }
+}
+
+// POST /user/me/calendar/reset - Replace the calendar address, e.g. after it leaked
+pub async fn reset_my_calendar(State(app): State<AppState>, token: AccessToken) -> impl IntoResponse {
+    let calendar_token = generate_calendar_token();
+
+    match db::users::set_calendar_token(&app.db, token.sub, &calendar_token).await {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(CalendarFeedResponse {
+                url: PublicUrls::from_env().calendar(&calendar_token),
+            }),
+        )
+            .into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, "User not found"),
+        Err(e) => {
+            tracing::error!("Failed to reset calendar token: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to reset calendar")
+        }
+    }
+}
@@ -1,8 +1,18 @@
 // All endpoint defs and router defs
 
+pub mod admin;
 pub mod app;
+pub mod attachment;
 pub mod auth;
+pub mod availability;
 pub mod chat;
+pub mod feed;
+pub mod moderation;
+pub mod notifications;
 pub mod post;
+pub mod proposal;
 pub mod review;
+pub mod saved_search;
+pub mod school_location;
+pub mod subject;
 pub mod user;
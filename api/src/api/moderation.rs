@@ -0,0 +1,197 @@
+// Reporting posts and the moderation queue
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{
+        auth::{AccessToken, ModeratorToken},
+        moderation::{
+            self, validate_report, validate_resolution, ModerationCase, PostReport, ReportOutcome,
+            ReportResolution,
+        },
+        pagination::clamp_per_page,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReportPostRequest {
+    // scam, spam, inappropriate, harassment, academic_dishonesty or other
+    pub reason: String,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportPostResponse {
+    pub report: PostReport,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModerationQueueQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModerationQueueResponse {
+    pub cases: Vec<ModerationCase>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveReportsRequest {
+    // dismiss, hide_post, warn_user or suspend_user
+    pub action: String,
+    // Passed on to the author together with the decision
+    pub note: Option<String>,
+    // Only for suspend_user, defaults to a week
+    pub suspend_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveReportsResponse {
+    pub resolved_reports: usize,
+    pub suspended_until: Option<DateTime<Utc>>,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+// POST /posts/:id/report - Flag a post for the moderators
+pub async fn report_post(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    Json(request): Json<ReportPostRequest>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let details = request
+        .details
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+
+    let reason = match validate_report(&request.reason, details) {
+        Ok(reason) => reason,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+
+    match db::posts::get_post_by_id(&app.db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.owner_id == user_id => {
+            return error(StatusCode::BAD_REQUEST, "You cannot report your own post");
+        }
+        Ok(Some(post)) if post.is_visible_to(Some(user_id)) => {}
+        Ok(_) => return error(StatusCode::NOT_FOUND, "Post not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to report post");
+        }
+    }
+
+    match moderation::report_post(&app.db, post_id, user_id, reason, details).await {
+        Ok(ReportOutcome::Created(report)) => {
+            (StatusCode::CREATED, Json(ReportPostResponse { report })).into_response()
+        }
+        Ok(ReportOutcome::AlreadyReported) => error(
+            StatusCode::CONFLICT,
+            "You already reported this post, a moderator will look at it",
+        ),
+        Ok(ReportOutcome::PostNotFound) => error(StatusCode::NOT_FOUND, "Post not found"),
+        Err(e) => {
+            tracing::error!("Failed to report post: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to report post")
+        }
+    }
+}
+
+// GET /moderation/reports - Posts with open reports, the most reported first
+pub async fn get_moderation_queue(
+    State(app): State<AppState>,
+    ModeratorToken(_moderator): ModeratorToken,
+    Query(query): Query<ModerationQueueQuery>,
+) -> impl IntoResponse {
+    let per_page = clamp_per_page(query.per_page, 20);
+    let offset = query.page.unwrap_or(0).max(0).saturating_mul(per_page);
+
+    match db::moderation::get_moderation_queue(&app.db, per_page, offset).await {
+        Ok(cases) => (StatusCode::OK, Json(ModerationQueueResponse { cases })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch moderation queue: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch moderation queue")
+        }
+    }
+}
+
+// POST /moderation/posts/:id/resolve - Decide on every open report of a post
+pub async fn resolve_reports(
+    State(app): State<AppState>,
+    ModeratorToken(moderator): ModeratorToken,
+    Path(post_id): Path<Uuid>,
+    Json(request): Json<ResolveReportsRequest>,
+) -> impl IntoResponse {
+    let Some(resolution) = ReportResolution::parse(&request.action) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Invalid action '{}', expected one of: {}",
+                request.action,
+                ReportResolution::ALL.map(|r| r.as_str()).join(", ")
+            ),
+        );
+    };
+
+    let note = request
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    if let Err(message) = validate_resolution(resolution, note, request.suspend_days) {
+        return error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    match moderation::resolve_reports(
+        &app.db,
+        post_id,
+        moderator.sub,
+        resolution,
+        note,
+        request.suspend_days,
+    )
+    .await
+    {
+        Ok(Some(resolved)) => {
+            tracing::info!(
+                "Moderator {} resolved {} reports of post {} with {}",
+                moderator.sub,
+                resolved.resolved_reports,
+                post_id,
+                resolution.as_str()
+            );
+            (
+                StatusCode::OK,
+                Json(ResolveReportsResponse {
+                    resolved_reports: resolved.resolved_reports,
+                    suspended_until: resolved.suspended_until,
+                }),
+            )
+                .into_response()
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "No open reports for this post"),
+        Err(e) => {
+            tracing::error!("Failed to resolve reports: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to resolve reports")
+        }
+    }
+}
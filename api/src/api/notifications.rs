@@ -0,0 +1,202 @@
+// Notification preference endpoints
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{
+        auth::AccessToken,
+        notifications::{Notification, NotificationPreferences},
+        pagination::clamp_per_page,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetNotificationPreferencesResponse {
+    pub preferences: NotificationPreferences,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetNotificationsQuery {
+    pub unread: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetNotificationsResponse {
+    pub notifications: Vec<Notification>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkNotificationsReadResponse {
+    pub marked: u64,
+}
+
+// GET /user/me/notifications/preferences - Get the current user's notification preferences
+pub async fn get_my_preferences(
+    State(app): State<AppState>,
+    token: AccessToken,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::notifications::get_preferences(db, token.sub).await {
+        Ok(preferences) => (
+            StatusCode::OK,
+            Json(GetNotificationPreferencesResponse { preferences }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch notification preferences: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch notification preferences".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// PUT /user/me/notifications/preferences - Replace the current user's notification preferences
+pub async fn update_my_preferences(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Json(mut request): Json<NotificationPreferences>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let user_id = token.sub;
+
+    if let Err(message) = request.validate() {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+    }
+
+    if let Some(quiet_hours) = &request.quiet_hours {
+        match db::availability::is_valid_time_zone(db, &quiet_hours.time_zone).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::new(format!(
+                        "Unknown time zone '{}'",
+                        quiet_hours.time_zone
+                    ))),
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to validate time zone: {:?}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to update notification preferences".to_string())),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    request.muted_thread_ids.sort();
+    request.muted_thread_ids.dedup();
+
+    match db::notifications::count_user_threads(db, user_id, &request.muted_thread_ids).await {
+        Ok(count) if count as usize == request.muted_thread_ids.len() => {}
+        Ok(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(
+                    "muted_thread_ids can only contain your own chat threads".to_string(),
+                )),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to validate muted threads: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to update notification preferences".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    if let Err(e) = db::notifications::set_preferences(db, user_id, &request).await {
+        tracing::error!("Failed to update notification preferences: {:?}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("Failed to update notification preferences".to_string())),
+        )
+            .into_response();
+    }
+
+    match db::notifications::get_preferences(db, user_id).await {
+        Ok(preferences) => (
+            StatusCode::OK,
+            Json(GetNotificationPreferencesResponse { preferences }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch notification preferences: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch notification preferences".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// GET /user/me/notifications - Most recent in-app notifications of the current user
+pub async fn get_my_notifications(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Query(query): Query<GetNotificationsQuery>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let limit = clamp_per_page(query.limit, 50);
+
+    match db::notifications::get_notifications(db, token.sub, query.unread.unwrap_or(false), limit).await {
+        Ok(notifications) => (
+            StatusCode::OK,
+            Json(GetNotificationsResponse { notifications }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch notifications: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch notifications".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// POST /user/me/notifications/read - Mark all notifications of the current user as read
+pub async fn mark_my_notifications_read(
+    State(app): State<AppState>,
+    token: AccessToken,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::notifications::mark_notifications_read(db, token.sub).await {
+        Ok(marked) => (
+            StatusCode::OK,
+            Json(MarkNotificationsReadResponse { marked }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to mark notifications as read: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to mark notifications as read".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,26 +1,375 @@
 // Post related endpoints
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{rejection::QueryRejection, ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+};
 
 use crate::{
+    api::chat::send_to_user,
     app::AppState,
     db,
-    server::{auth::AccessToken, post::Post},
+    server::{
+        analytics::{self, PostAnalytics, DEFAULT_ANALYTICS_DAYS, MAX_ANALYTICS_DAYS},
+        attachment::PostAttachment,
+        auth::AccessToken,
+        chat::ChatResponse,
+        money::{format_amount, parse_amount, parse_currency, AmountInput, DEFAULT_CURRENCY},
+        notifications::{self, NotificationChannel, NotificationEvent},
+        pagination::{clamp_per_page, Cursor},
+        saved_search::spawn_saved_search_alerts,
+        post::{
+            validate_publish_at, MeetingMode, Post, PostFilters, PostMeeting, PostPricing, PostRevision, PostSearchMatch, PostSort,
+            PostStatus, PostStatusChange, PricingType, ACADEMIC_LEVELS, DELETED_POST_GRACE_DAYS,
+            DIFFICULTIES, POST_TYPES,
+        },
+        school_location::SchoolLocationTag,
+        subject::{requested_subjects, SubjectTag},
+        views::Viewer,
+    },
 };
 
-#[derive(Debug, Deserialize)]
+// Longest accepted full text search query
+const MAX_SEARCH_LENGTH: usize = 200;
+
+// Most similar posts returned at once
+const MAX_SIMILAR_POSTS: i64 = 20;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct GetPostsQuery {
-    pub page: Option<i32>,
-    pub per_page: Option<i32>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    // Opaque value from a previous response's next_cursor, replaces page
+    pub cursor: Option<String>,
+    // Also count all matching posts
+    pub include_total: Option<bool>,
     pub owner_id: Option<String>,
+    // Only offers whose owner is available at this moment
+    pub available_at: Option<DateTime<Utc>>,
+    // Full text search over title, subject and description, results are ranked by relevance
+    pub q: Option<String>,
+    pub r#type: Option<String>,
+    // Comma separated slugs, names or aliases, matches any of them or their subsubjects
+    pub subject: Option<String>,
+    // Decimal amounts like 19.99, posts without a price never match a bound
+    pub min_price: Option<String>,
+    pub max_price: Option<String>,
+    // Comma separated fixed, hourly, negotiable or free, matches any of them
+    pub pricing_type: Option<String>,
+    pub currency: Option<String>,
+    pub deadline_from: Option<DateTime<Utc>>,
+    pub deadline_to: Option<DateTime<Utc>>,
+    pub urgent: Option<bool>,
+    pub status: Option<String>,
+    pub academic_level: Option<String>,
+    // Comma separated, matches any of them
+    pub difficulty: Option<String>,
+    // Case insensitive substring of the location note
+    pub location: Option<String>,
+    // Comma separated online, in_person or either, matches any of them
+    pub meeting_mode: Option<String>,
+    // Slug of a school location, matches posts meeting there or anywhere inside it
+    pub school_location: Option<String>,
+    // newest, deadline, price_asc, price_desc, rating or relevance
+    pub sort: Option<String>,
+}
+
+impl GetPostsQuery {
+    /// Validates the query and turns it into filters, the error is a message for the client
+    pub fn filters(&self) -> Result<PostFilters, String> {
+        let owner_id = match &self.owner_id {
+            Some(owner_id) => {
+                Some(Uuid::parse_str(owner_id).map_err(|_| "Invalid owner ID format".to_string())?)
+            }
+            None => None,
+        };
+
+        let search = non_empty(&self.q);
+        if let Some(search) = &search
+            && search.chars().count() > MAX_SEARCH_LENGTH
+        {
+            return Err(format!(
+                "Search query cannot be longer than {} characters",
+                MAX_SEARCH_LENGTH
+            ));
+        }
+
+        let r#type = one_of("type", non_empty(&self.r#type), &POST_TYPES)?;
+        let status = one_of("status", non_empty(&self.status), &PostStatus::ALL.map(|s| s.as_str()))?;
+        let academic_level = one_of("academic_level", non_empty(&self.academic_level), &ACADEMIC_LEVELS)?;
+
+        let subjects = split_list(&self.subject);
+
+        let difficulties = split_list(&self.difficulty);
+        for difficulty in &difficulties {
+            one_of("difficulty", Some(difficulty.clone()), &DIFFICULTIES)?;
+        }
+
+        let min_price = price_bound("min_price", &self.min_price)?;
+        let max_price = price_bound("max_price", &self.max_price)?;
+        if let (Some(min), Some(max)) = (min_price, max_price)
+            && min > max
+        {
+            return Err("min_price cannot be greater than max_price".to_string());
+        }
+
+        let pricing_types = split_list(&self.pricing_type);
+        for pricing_type in &pricing_types {
+            one_of(
+                "pricing_type",
+                Some(pricing_type.clone()),
+                &PricingType::ALL.map(|p| p.as_str()),
+            )?;
+        }
+
+        let currency = non_empty(&self.currency).map(|c| parse_currency(&c)).transpose()?;
+
+        let meeting_modes = split_list(&self.meeting_mode);
+        for meeting_mode in &meeting_modes {
+            one_of(
+                "meeting_mode",
+                Some(meeting_mode.clone()),
+                &MeetingMode::ALL.map(|m| m.as_str()),
+            )?;
+        }
+
+        if let (Some(from), Some(to)) = (self.deadline_from, self.deadline_to)
+            && from > to
+        {
+            return Err("deadline_from cannot be after deadline_to".to_string());
+        }
+
+        let sort = match non_empty(&self.sort) {
+            Some(sort) => PostSort::parse(&sort).ok_or_else(|| {
+                format!(
+                    "sort must be one of: {}",
+                    PostSort::ALL.map(|s| s.as_str()).join(", ")
+                )
+            })?,
+            None if search.is_some() => PostSort::Relevance,
+            None => PostSort::Newest,
+        };
+        if sort == PostSort::Relevance && search.is_none() {
+            return Err("sort=relevance requires a search query (q)".to_string());
+        }
+
+        Ok(PostFilters {
+            owner_id,
+            r#type,
+            subjects,
+            min_price,
+            max_price,
+            pricing_types,
+            currency,
+            deadline_from: self.deadline_from,
+            deadline_to: self.deadline_to,
+            urgent: self.urgent,
+            status,
+            academic_level,
+            difficulties,
+            location: non_empty(&self.location),
+            meeting_modes,
+            school_location: non_empty(&self.school_location),
+            available_at: self.available_at,
+            search,
+            include_unpublished: false,
+            deleted: false,
+            sort,
+        })
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn split_list(value: &Option<String>) -> Vec<String> {
+    value
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn one_of(field: &str, value: Option<String>, allowed: &[&str]) -> Result<Option<String>, String> {
+    match value {
+        Some(value) if !allowed.contains(&value.as_str()) => Err(format!(
+            "Invalid {} '{}', expected one of: {}",
+            field,
+            value,
+            allowed.join(", ")
+        )),
+        value => Ok(value),
+    }
+}
+
+fn price_bound(field: &str, value: &Option<String>) -> Result<Option<rust_decimal::Decimal>, String> {
+    non_empty(value).map(|price| parse_amount(field, &price)).transpose()
+}
+
+fn parse_pricing_type(value: &str) -> Result<PricingType, String> {
+    PricingType::parse(value.trim()).ok_or_else(|| {
+        format!(
+            "Invalid pricingType '{}', expected one of: {}",
+            value,
+            PricingType::ALL.map(|p| p.as_str()).join(", ")
+        )
+    })
+}
+
+/// Pricing of a new post. Without a pricing type a price means fixed pricing and a missing
+/// or zero price means negotiable, which is also how existing posts were migrated.
+fn new_post_pricing(
+    price: Option<&AmountInput>,
+    currency: Option<&str>,
+    pricing_type: Option<&str>,
+) -> Result<PostPricing, String> {
+    let price = price.map(|price| price.parse("price")).transpose()?;
+    let currency = parse_currency(currency.unwrap_or(DEFAULT_CURRENCY))?;
+
+    let pricing = match pricing_type {
+        Some(pricing_type) => PostPricing {
+            pricing_type: parse_pricing_type(pricing_type)?,
+            price,
+            currency,
+        },
+        None => match price.filter(|price| !price.is_zero()) {
+            Some(price) => PostPricing {
+                pricing_type: PricingType::Fixed,
+                price: Some(price),
+                currency,
+            },
+            None => PostPricing {
+                pricing_type: PricingType::Negotiable,
+                price: None,
+                currency,
+            },
+        },
+    };
+
+    pricing.validate()?;
+    Ok(pricing)
+}
+
+/// Pricing after an edit. A new pricing type comes with its own price (or none),
+/// a price alone keeps the current pricing type.
+fn edited_post_pricing(post: &Post, request: &UpdatePostRequest) -> Result<PostPricing, String> {
+    let price = request.price.as_ref().map(|price| price.parse("price")).transpose()?;
+    let currency = parse_currency(request.currency.as_deref().unwrap_or(&post.currency))?;
+
+    let pricing = match request.pricing_type.as_deref() {
+        Some(pricing_type) => PostPricing {
+            pricing_type: parse_pricing_type(pricing_type)?,
+            price,
+            currency,
+        },
+        None => PostPricing {
+            pricing_type: PricingType::parse(&post.pricing_type).unwrap_or(PricingType::Fixed),
+            price: price.or(post.price),
+            currency,
+        },
+    };
+
+    pricing.validate()?;
+    Ok(pricing)
+}
+
+fn parse_meeting_mode(value: &str) -> Result<MeetingMode, String> {
+    MeetingMode::parse(value.trim()).ok_or_else(|| {
+        format!(
+            "Invalid meetingMode '{}', expected one of: {}",
+            value,
+            MeetingMode::ALL.map(|m| m.as_str()).join(", ")
+        )
+    })
+}
+
+/// School location given by slug or id, an empty value means none
+async fn resolve_school_location_id(db: &PgPool, input: &str) -> Result<Option<Uuid>, Response> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+
+    match db::school_locations::resolve_school_location(db, input).await {
+        Ok(Some(location_id)) => Ok(Some(location_id)),
+        Ok(None) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "Unknown school location '{}', see GET /school-locations",
+                input
+            ))),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("Failed to resolve school location: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to resolve school location".to_string())),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Meeting details of a new post, either way with no location when nothing is given
+async fn new_post_meeting(
+    db: &PgPool,
+    meeting_mode: Option<&str>,
+    school_location: Option<&str>,
+) -> Result<PostMeeting, Response> {
+    let bad_request =
+        |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+
+    let mode = match meeting_mode {
+        Some(meeting_mode) => parse_meeting_mode(meeting_mode).map_err(bad_request)?,
+        None => MeetingMode::Either,
+    };
+    let school_location_id = match school_location {
+        Some(school_location) => resolve_school_location_id(db, school_location).await?,
+        None => None,
+    };
+
+    let meeting = PostMeeting { mode, school_location_id };
+    meeting.validate().map_err(bad_request)?;
+    Ok(meeting)
+}
+
+/// Meeting details after an edit. Switching to online drops the school location
+/// unless a new one is given, which is then an error.
+async fn edited_post_meeting(db: &PgPool, post: &Post, request: &UpdatePostRequest) -> Result<PostMeeting, Response> {
+    let bad_request =
+        |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+
+    let mode = match request.meeting_mode.as_deref() {
+        Some(meeting_mode) => parse_meeting_mode(meeting_mode).map_err(bad_request)?,
+        None => MeetingMode::parse(&post.meeting_mode).unwrap_or(MeetingMode::Either),
+    };
+    let school_location_id = match request.school_location.as_deref() {
+        Some(school_location) => resolve_school_location_id(db, school_location).await?,
+        None if mode == MeetingMode::Online => None,
+        None => post.school_location.as_ref().map(|location| location.id),
+    };
+
+    let meeting = PostMeeting { mode, school_location_id };
+    meeting.validate().map_err(bad_request)?;
+    Ok(meeting)
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,16 +377,38 @@ pub struct CreatePostRequest {
     pub title: String,
     pub description: String,
     pub r#type: String,
-    pub subject: String,
-    pub price: f64,
+    // Single subject, kept for older clients, `subjects` wins when both are given
+    pub subject: Option<String>,
+    // Slugs, names or aliases of taxonomy subjects, the first one is the primary subject
+    pub subjects: Option<Vec<String>>,
+    // Decimal string like "19.99", numbers are accepted from older clients
+    pub price: Option<AmountInput>,
+    // ISO 4217 code, PLN when missing
+    pub currency: Option<String>,
+    // fixed, hourly, negotiable or free
+    #[serde(rename = "pricingType")]
+    pub pricing_type: Option<String>,
     pub deadline: Option<DateTime<Utc>>,
     pub urgent: bool,
+    // online, in_person or either, either when missing
+    #[serde(rename = "meetingMode")]
+    pub meeting_mode: Option<String>,
+    // Slug or id of a school location, see GET /school-locations
+    #[serde(rename = "schoolLocation")]
+    pub school_location: Option<String>,
+    // Free-text note on where or how to meet
     pub location: Option<String>,
     #[serde(rename = "preferredContactMethod")]
     pub preferred_contact_method: Option<String>,
     #[serde(rename = "academicLevel")]
     pub academic_level: Option<String>,
     pub difficulty: Option<String>,
+    // Save without publishing, only the owner sees it
+    #[serde(default)]
+    pub draft: bool,
+    // Publish automatically at this time instead of right away
+    #[serde(rename = "publishAt")]
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,9 +417,21 @@ pub struct UpdatePostRequest {
     pub description: Option<String>,
     pub r#type: Option<String>,
     pub subject: Option<String>,
-    pub price: Option<f64>,
+    // Replaces all subjects of the post
+    pub subjects: Option<Vec<String>>,
+    pub price: Option<AmountInput>,
+    pub currency: Option<String>,
+    // Replaces the price as well, leave out price for free posts and open negotiable ones
+    #[serde(rename = "pricingType")]
+    pub pricing_type: Option<String>,
     pub deadline: Option<DateTime<Utc>>,
     pub urgent: Option<bool>,
+    // Switching to online drops the school location
+    #[serde(rename = "meetingMode")]
+    pub meeting_mode: Option<String>,
+    // Empty string removes the school location
+    #[serde(rename = "schoolLocation")]
+    pub school_location: Option<String>,
     pub location: Option<String>,
     #[serde(rename = "preferredContactMethod")]
     pub preferred_contact_method: Option<String>,
@@ -57,9 +440,47 @@ pub struct UpdatePostRequest {
     pub difficulty: Option<String>,
 }
 
+impl UpdatePostRequest {
+    fn changes_pricing(&self) -> bool {
+        self.price.is_some() || self.currency.is_some() || self.pricing_type.is_some()
+    }
+
+    fn changes_meeting(&self) -> bool {
+        self.meeting_mode.is_some() || self.school_location.is_some()
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct GetPostsResponse {
     pub posts: Vec<PostResponse>,
+    // Pass back as `cursor` to get the next page, null on the last page
+    // and for orders other than newest (use `page` there)
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetSimilarPostsQuery {
+    // How many posts to return, 6 by default
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetSimilarPostsResponse {
+    // Best match first
+    pub posts: Vec<PostResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetPostAnalyticsQuery {
+    // Length of the views series in days, 30 by default
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetPostAnalyticsResponse {
+    pub analytics: PostAnalytics,
 }
 
 #[derive(Debug, Serialize)]
@@ -82,6 +503,34 @@ pub struct DeletePostResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PublishPostRequest {
+    // Schedule for this time, publish right away when missing
+    #[serde(rename = "publishAt")]
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePostStatusRequest {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetPostStatusHistoryResponse {
+    pub history: Vec<PostStatusChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetPostRevisionsResponse {
+    pub revisions: Vec<PostRevision>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavePostResponse {
+    pub is_saved: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub message: String,
@@ -94,71 +543,159 @@ pub struct PostResponse {
     pub title: String,
     pub description: String,
     pub r#type: String,
+    // Name of the primary subject
     pub subject: String,
-    pub price: f64,
+    pub subjects: Vec<SubjectTag>,
+    // Decimal string with two places, null for free posts and negotiable ones without a price
+    pub price: Option<String>,
+    pub currency: String,
+    #[serde(rename = "pricingType")]
+    pub pricing_type: String,
     pub deadline: Option<String>,
     pub urgent: bool,
     pub status: String,
+    // Only set while the post is scheduled
+    #[serde(rename = "publishAt")]
+    pub publish_at: Option<String>,
+    // Set when moderation hid the post, only its owner still sees it
+    #[serde(rename = "hiddenAt")]
+    pub hidden_at: Option<String>,
+    // Set when the owner deleted the post, only the owner and people with a thread or review on it still see it
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: Option<String>,
+    #[serde(rename = "restorableUntil")]
+    pub restorable_until: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
     pub updated_at: String,
+    #[serde(rename = "publishedAt")]
+    pub published_at: Option<String>,
     
     // Owner information
     pub owner_id: String,
     pub owner_name: String,
     pub owner_username: String,
     pub owner_avatar: Option<String>,
-    pub owner_rating: f64,
+    pub owner_rating: rust_decimal::Decimal,
+    pub owner_review_count: i32,
     
     // Post metadata
     #[serde(rename = "viewCount")]
     pub view_count: i32,
     #[serde(rename = "responseCount")]
     pub response_count: i32,
+
+    // online, in_person or either
+    #[serde(rename = "meetingMode")]
+    pub meeting_mode: String,
+    // Place at school for in-person help
+    #[serde(rename = "schoolLocation")]
+    pub school_location: Option<SchoolLocationTag>,
     
     // Optional fields
+    // Free-text note on where or how to meet
     pub location: Option<String>,
     #[serde(rename = "preferredContactMethod")]
     pub preferred_contact_method: Option<String>,
     #[serde(rename = "academicLevel")]
     pub academic_level: Option<String>,
     pub difficulty: Option<String>,
+    pub assigned_helper_id: Option<String>,
+    pub attachments: Vec<PostAttachment>,
+
+    // Only present for authenticated callers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_saved: Option<bool>,
+
+    // Only present in search results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<PostSearchMatch>,
 }
 
 impl From<Post> for PostResponse {
     fn from(post: Post) -> Self {
+        let restorable_until = post.restorable_until().map(|d| d.to_rfc3339());
         Self {
             id: post.id.to_string(),
             title: post.title,
             description: post.description,
             r#type: post.r#type,
-            subject: post.subject,
-            price: post.price.to_string().parse().unwrap_or(0.0),
+            subject: post.subjects.first().map(|s| s.name.clone()).unwrap_or_default(),
+            subjects: post.subjects.0,
+            price: post.price.map(format_amount),
+            currency: post.currency,
+            pricing_type: post.pricing_type,
             deadline: post.deadline.map(|d| d.to_rfc3339()),
             urgent: post.urgent,
             status: post.status,
+            publish_at: post.publish_at.map(|d| d.to_rfc3339()),
+            hidden_at: post.hidden_at.map(|d| d.to_rfc3339()),
+            deleted_at: post.deleted_at.map(|d| d.to_rfc3339()),
+            restorable_until,
             created_at: post.created_at.to_rfc3339(),
             updated_at: post.updated_at.to_rfc3339(),
+            published_at: post.published_at.map(|d| d.to_rfc3339()),
             owner_id: post.owner_id.to_string(),
             owner_name: post.owner_name,
             owner_username: post.owner_username,
             owner_avatar: None, // TODO: Convert bytea to base64 if needed
-            owner_rating: post.owner_rating.to_string().parse().unwrap_or(4.5),
+            owner_rating: post.owner_rating,
+            owner_review_count: post.owner_review_count,
             view_count: post.view_count,
             response_count: post.response_count,
+            meeting_mode: post.meeting_mode,
+            school_location: post.school_location.map(|location| location.0),
             location: post.location,
             preferred_contact_method: post.preferred_contact_method,
             academic_level: post.academic_level,
             difficulty: post.difficulty,
+            assigned_helper_id: post.assigned_helper_id.map(|id| id.to_string()),
+            attachments: Vec::new(),
+            is_saved: post.is_saved,
+            search: None,
         }
     }
 }
 
+/// Fills in the attachments of posts about to be returned, one query for the whole list
+pub async fn load_attachments(db: &PgPool, posts: &mut [PostResponse]) -> crate::error::Result<()> {
+    let post_ids: Vec<Uuid> = posts.iter().filter_map(|p| p.id.parse().ok()).collect();
+    let mut by_post = db::attachments::get_attachments_for_posts(db, &post_ids).await?;
+
+    for post in posts {
+        if let Ok(post_id) = post.id.parse::<Uuid>() {
+            post.attachments = by_post.remove(&post_id).unwrap_or_default();
+        }
+    }
+
+    Ok(())
+}
+
 impl GetPostsResponse {
     pub fn new(posts: Vec<Post>) -> Self {
         Self { 
-            posts: posts.into_iter().map(PostResponse::from).collect() 
+            posts: posts.into_iter().map(PostResponse::from).collect(),
+            next_cursor: None,
+            total: None,
+        }
+    }
+
+    pub fn with_search_matches(posts: Vec<Post>, mut matches: Vec<PostSearchMatch>) -> Self {
+        let posts = posts
+            .into_iter()
+            .map(|post| {
+                let search = matches
+                    .iter()
+                    .position(|m| m.post_id == post.id)
+                    .map(|i| matches.swap_remove(i));
+                PostResponse { search, ..PostResponse::from(post) }
+            })
+            .collect();
+        Self {
+            posts,
+            next_cursor: None,
+            total: None,
         }
     }
 }
@@ -193,59 +730,319 @@ impl ErrorResponse {
     }
 }
 
-// GET /posts - Get all posts with pagination
+// GET /posts - Get all posts with pagination, filtering and sorting
 pub async fn get_posts(
     State(app): State<AppState>,
-    Query(query): Query<GetPostsQuery>,
+    token: Option<AccessToken>,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
 ) -> impl IntoResponse {
     let db = &app.db;
 
-    // Parse owner_id if provided
-    let owner_uuid = if let Some(ref owner_id) = query.owner_id {
-        match Uuid::parse_str(owner_id) {
-            Ok(uuid) => Some(uuid),
-            Err(_) => {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(rejection) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(rejection.body_text())),
+            )
+                .into_response()
+        }
+    };
+
+    let filters = match query.filters() {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response()
+        }
+    };
+
+    if let Some(status) = filters.status.as_deref().and_then(PostStatus::parse)
+        && !status.is_published()
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "Drafts and scheduled posts are only listed at GET /user/me/posts".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    list_posts(db, &query, filters, token.map(|t| t.sub)).await
+}
+
+/// One page of posts matching the filters, shared by the public listing and the owner's own one
+pub async fn list_posts(
+    db: &PgPool,
+    query: &GetPostsQuery,
+    filters: PostFilters,
+    viewer_id: Option<Uuid>,
+) -> Response {
+    let cursor = match query.cursor.as_deref() {
+        Some(cursor) => match Cursor::decode(cursor) {
+            Some(_) if filters.sort != PostSort::Newest => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::new(
+                        "cursor can only be used with sort=newest, use page instead".to_string(),
+                    )),
+                )
+                    .into_response()
+            }
+            Some(cursor) => Some(cursor),
+            None => {
                 return (
                     StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new("Invalid owner ID format".to_string())),
+                    Json(ErrorResponse::new("Invalid cursor".to_string())),
                 )
+                    .into_response()
+            }
+        },
+        None => None,
+    };
+
+    let per_page = clamp_per_page(query.per_page, 10);
+    let offset = match cursor {
+        Some(_) => 0,
+        None => query.page.unwrap_or(0).max(0).saturating_mul(per_page),
+    };
+
+    // One extra row tells whether there is a next page
+    let mut posts = match db::posts::get_posts_filtered(db, &filters, cursor, offset, per_page + 1, viewer_id).await {
+        Ok(posts) => posts,
+        Err(e) => {
+            tracing::error!("Failed to fetch posts: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch posts".to_string())),
+            )
                 .into_response()
+        }
+    };
+
+    let has_more = posts.len() as i64 > per_page;
+    posts.truncate(per_page as usize);
+    let next_cursor = match posts.last() {
+        Some(last) if has_more && filters.sort == PostSort::Newest => {
+            Some(Cursor::new(last.listed_at(), last.id).encode())
+        }
+        _ => None,
+    };
+
+    let total = if query.include_total.unwrap_or(false) {
+        match db::posts::count_posts_filtered(db, &filters).await {
+            Ok(total) => Some(total),
+            Err(e) => {
+                tracing::error!("Failed to count posts: {:?}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to fetch posts".to_string())),
+                )
+                    .into_response()
             }
         }
     } else {
         None
     };
 
-    match db::posts::get_posts_filtered(db, query.page.unwrap_or(0), query.per_page.unwrap_or(10), owner_uuid).await {
-        Ok(posts) => (StatusCode::OK, Json(GetPostsResponse::new(posts))).into_response(),
-        Err(_) => (
+    let mut response = match filters.search.as_deref() {
+        None => GetPostsResponse::new(posts),
+        Some(search) => {
+            let post_ids: Vec<Uuid> = posts.iter().map(|p| p.id).collect();
+            match db::posts::get_search_matches(db, &post_ids, search).await {
+                Ok(matches) => GetPostsResponse::with_search_matches(posts, matches),
+                Err(e) => {
+                    tracing::error!("Failed to highlight search results: {:?}", e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ErrorResponse::new("Failed to fetch posts".to_string())),
+                    )
+                        .into_response()
+                }
+            }
+        }
+    };
+
+    if let Err(e) = load_attachments(db, &mut response.posts).await {
+        tracing::error!("Failed to fetch attachments: {:?}", e);
+        return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new("Failed to fetch posts".to_string())),
         )
-            .into_response(),
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(GetPostsResponse {
+            next_cursor,
+            total,
+            ..response
+        }),
+    )
+        .into_response()
+}
+
+// Client address passed by nginx in X-Real-IP, whether to believe it is up to the view counter
+fn forwarded_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-real-ip")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Whether the viewer may read the post. A deleted post stays readable for its owner and for
+/// everyone it still matters to through a thread or a review.
+pub async fn is_post_readable(db: &PgPool, post: &Post, viewer_id: Option<Uuid>) -> crate::error::Result<bool> {
+    match (post.deleted_at, viewer_id) {
+        (None, _) => Ok(post.is_visible_to(viewer_id)),
+        (Some(_), Some(user_id)) if user_id == post.owner_id => Ok(true),
+        (Some(_), Some(user_id)) => db::posts::is_post_participant(db, post.id, user_id).await,
+        (Some(_), None) => Ok(false),
+    }
+}
+
+/// Post the viewer may read, a response to return when it is missing or not readable.
+/// `failure` is the message for database errors.
+pub async fn find_readable_post(
+    db: &PgPool,
+    post_id: Uuid,
+    viewer_id: Option<Uuid>,
+    failure: &str,
+) -> Result<Post, Response> {
+    let internal_error = |e: crate::error::AppError| {
+        tracing::error!("Failed to fetch post: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(failure.to_string())),
+        )
+            .into_response()
+    };
+
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post not found".to_string())),
+        )
+            .into_response()
+    };
+
+    let post = db::posts::get_post_by_id(db, post_id, viewer_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+    if is_post_readable(db, &post, viewer_id).await.map_err(internal_error)? {
+        Ok(post)
+    } else {
+        Err(not_found())
     }
 }
 
 // GET /posts/:id - Get a specific post by ID
 pub async fn get_post_by_id(
     State(app): State<AppState>,
+    token: Option<AccessToken>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Path(post_id): Path<Uuid>,
 ) -> impl IntoResponse {
     let db = &app.db;
+    let viewer_id = token.map(|t| t.sub);
 
-    match db::posts::get_post_by_id(db, post_id).await {
-        Ok(Some(post)) => (StatusCode::OK, Json(GetPostResponse::new(post))).into_response(),
-        Ok(None) => (
+    let post = match db::posts::get_post_by_id(db, post_id, viewer_id).await {
+        Ok(Some(post)) => post,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post".to_string())),
+            )
+                .into_response()
+        }
+    };
+
+    let visible = match is_post_readable(db, &post, viewer_id).await {
+        Ok(visible) => visible,
+        Err(e) => {
+            tracing::error!("Failed to check post participants: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    if !visible {
+        return (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse::new("Post not found".to_string())),
         )
-            .into_response(),
-        Err(_) => (
+            .into_response();
+    }
+
+    // Owners looking at their own posts are not views, neither are looks at deleted posts
+    if viewer_id != Some(post.owner_id) && post.deleted_at.is_none() {
+        let viewer = match viewer_id {
+            Some(user_id) => Viewer::User(user_id),
+            None => app.view_counter.anonymous_viewer(peer.ip(), forwarded_ip(&headers)),
+        };
+        app.view_counter.record(post_id, viewer).await;
+    }
+
+    let mut response = GetPostResponse::new(post);
+    if let Err(e) = load_attachments(db, std::slice::from_mut(&mut response.post)).await {
+        tracing::error!("Failed to fetch attachments: {:?}", e);
+        return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new("Failed to fetch post".to_string())),
         )
-            .into_response(),
+            .into_response();
+    }
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Taxonomy ids of the requested subjects in request order, unknown subjects are a client error
+async fn resolve_subject_ids(db: &PgPool, subjects: &[String]) -> Result<Vec<Uuid>, Response> {
+    let resolved = match db::subjects::resolve_subjects(db, subjects).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            tracing::error!("Failed to resolve subjects: {:?}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to resolve subjects".to_string())),
+            )
+                .into_response());
+        }
+    };
+
+    let mut subject_ids = Vec::new();
+    for (subject, subject_id) in subjects.iter().zip(resolved) {
+        let Some(subject_id) = subject_id else {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(format!(
+                    "Unknown subject '{}', see GET /subjects",
+                    subject
+                ))),
+            )
+                .into_response());
+        };
+        // Two spellings of the same subject count once
+        if !subject_ids.contains(&subject_id) {
+            subject_ids.push(subject_id);
+        }
     }
+
+    Ok(subject_ids)
 }
 
 // POST /posts/create - Create a new post
@@ -274,7 +1071,7 @@ pub async fn create_post(
             .into_response();
     }
 
-    if !["request", "offer"].contains(&request.r#type.as_str()) {
+    if !POST_TYPES.contains(&request.r#type.as_str()) {
         return (
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse::new("Type must be 'request' or 'offer'".to_string())),
@@ -282,32 +1079,73 @@ pub async fn create_post(
             .into_response();
     }
 
-    if request.subject.trim().is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Subject cannot be empty".to_string())),
-        )
-            .into_response();
-    }
+    let subject_ids = match requested_subjects(request.subject.as_deref(), request.subjects.as_deref()) {
+        Ok(Some(subjects)) => match resolve_subject_ids(db, &subjects).await {
+            Ok(subject_ids) => subject_ids,
+            Err(response) => return response,
+        },
+        Ok(None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("At least one subject is required".to_string())),
+            )
+                .into_response();
+        }
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+        }
+    };
 
-    if request.price < 0.0 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Price cannot be negative".to_string())),
-        )
-            .into_response();
-    }
+    let pricing = match new_post_pricing(
+        request.price.as_ref(),
+        request.currency.as_deref(),
+        request.pricing_type.as_deref(),
+    ) {
+        Ok(pricing) => pricing,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+        }
+    };
 
-    let price_decimal = rust_decimal::Decimal::from_f64_retain(request.price)
-        .unwrap_or_else(|| rust_decimal::Decimal::new(0, 0));
+    let meeting = match new_post_meeting(
+        db,
+        request.meeting_mode.as_deref(),
+        request.school_location.as_deref(),
+    )
+    .await
+    {
+        Ok(meeting) => meeting,
+        Err(response) => return response,
+    };
+
+    let (status, publish_at) = match (request.draft, request.publish_at) {
+        (true, Some(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(
+                    "A post can be a draft or scheduled, not both".to_string(),
+                )),
+            )
+                .into_response();
+        }
+        (true, None) => (PostStatus::Draft, None),
+        (false, Some(publish_at)) => {
+            if let Err(message) = validate_publish_at(publish_at, request.deadline) {
+                return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+            }
+            (PostStatus::Scheduled, Some(publish_at))
+        }
+        (false, None) => (PostStatus::Active, None),
+    };
 
     match db::posts::create_post(
         db,
         request.title,
         request.description,
         request.r#type,
-        request.subject,
-        price_decimal,
+        &subject_ids,
+        &pricing,
+        &meeting,
         request.deadline,
         request.urgent,
         user_id,
@@ -315,8 +1153,15 @@ pub async fn create_post(
         request.preferred_contact_method,
         request.academic_level,
         request.difficulty,
+        status,
+        publish_at,
     ).await {
-        Ok(post) => (StatusCode::CREATED, Json(CreatePostResponse::new(post))).into_response(),
+        Ok(post) => {
+            if post.status == PostStatus::Active.as_str() {
+                spawn_saved_search_alerts(db.clone(), post.id);
+            }
+            (StatusCode::CREATED, Json(CreatePostResponse::new(post))).into_response()
+        }
         Err(e) => {
             tracing::error!("Failed to create post: {:?}", e);
             (
@@ -343,9 +1188,14 @@ pub async fn update_post(
         && request.description.is_none() 
         && request.r#type.is_none()
         && request.subject.is_none()
+        && request.subjects.is_none()
         && request.price.is_none()
+        && request.currency.is_none()
+        && request.pricing_type.is_none()
         && request.deadline.is_none()
         && request.urgent.is_none()
+        && request.meeting_mode.is_none()
+        && request.school_location.is_none()
         && request.location.is_none()
         && request.preferred_contact_method.is_none()
         && request.academic_level.is_none()
@@ -360,60 +1210,91 @@ pub async fn update_post(
     }
 
     // Validate non-empty strings if provided
-    if let Some(ref title) = request.title {
-        if title.trim().is_empty() {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new("Title cannot be empty".to_string())),
-            )
-                .into_response();
-        }
+    if let Some(ref title) = request.title
+        && title.trim().is_empty()
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("Title cannot be empty".to_string())),
+        )
+            .into_response();
     }
 
-    if let Some(ref description) = request.description {
-        if description.trim().is_empty() {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new("Description cannot be empty".to_string())),
-            )
-                .into_response();
-        }
+    if let Some(ref description) = request.description
+        && description.trim().is_empty()
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("Description cannot be empty".to_string())),
+        )
+            .into_response();
     }
 
-    if let Some(ref r#type) = request.r#type {
-        if !["request", "offer"].contains(&r#type.as_str()) {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new("Type must be 'request' or 'offer'".to_string())),
-            )
-                .into_response();
-        }
+    if let Some(ref r#type) = request.r#type
+        && !POST_TYPES.contains(&r#type.as_str())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("Type must be 'request' or 'offer'".to_string())),
+        )
+            .into_response();
     }
 
-    if let Some(ref subject) = request.subject {
-        if subject.trim().is_empty() {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new("Subject cannot be empty".to_string())),
-            )
-                .into_response();
+    let subject_ids = match requested_subjects(request.subject.as_deref(), request.subjects.as_deref()) {
+        Ok(Some(subjects)) => match resolve_subject_ids(db, &subjects).await {
+            Ok(subject_ids) => Some(subject_ids),
+            Err(response) => return response,
+        },
+        Ok(None) => None,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
         }
-    }
+    };
 
-    if let Some(price) = request.price {
-        if price < 0.0 {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new("Price cannot be negative".to_string())),
-            )
-                .into_response();
+    // The price only makes sense together with the stored pricing type and currency,
+    // the school location together with the stored meeting mode
+    let current = if request.changes_pricing() || request.changes_meeting() {
+        match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+            Ok(Some(post)) if post.owner_id == user_id && post.deleted_at.is_none() => Some(post),
+            Ok(_) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse::new(
+                        "Post not found or you don't have permission to update it".to_string(),
+                    )),
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to fetch post: {:?}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to update post".to_string())),
+                )
+                    .into_response();
+            }
         }
-    }
+    } else {
+        None
+    };
 
-    let price_decimal = request.price.map(|p| {
-        rust_decimal::Decimal::from_f64_retain(p)
-            .unwrap_or_else(|| rust_decimal::Decimal::new(0, 0))
-    });
+    let pricing = match &current {
+        Some(post) if request.changes_pricing() => match edited_post_pricing(post, &request) {
+            Ok(pricing) => Some(pricing),
+            Err(message) => {
+                return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+            }
+        },
+        _ => None,
+    };
+
+    let meeting = match &current {
+        Some(post) if request.changes_meeting() => match edited_post_meeting(db, post, &request).await {
+            Ok(meeting) => Some(meeting),
+            Err(response) => return response,
+        },
+        _ => None,
+    };
 
     match db::posts::update_post(
         db, 
@@ -421,8 +1302,9 @@ pub async fn update_post(
         request.title, 
         request.description,
         request.r#type,
-        request.subject,
-        price_decimal,
+        subject_ids,
+        pricing,
+        meeting,
         request.deadline,
         request.urgent,
         request.location,
@@ -431,7 +1313,17 @@ pub async fn update_post(
         request.difficulty,
         user_id
     ).await {
-        Ok(Some(post)) => (StatusCode::OK, Json(UpdatePostResponse::new(post))).into_response(),
+        Ok(Some((post, revision))) => {
+            if let Some(revision) = revision {
+                notify_post_revised(&app, user_id, revision).await;
+            }
+
+            let mut response = UpdatePostResponse::new(post);
+            if let Err(e) = load_attachments(db, std::slice::from_mut(&mut response.post)).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse::new(
@@ -450,7 +1342,46 @@ pub async fn update_post(
     }
 }
 
-// DELETE /posts/:id - Delete a post
+// Lets everyone chatting about the post know that it changed, the editor already knows.
+// Participants who muted the thread, turned post replies off or are in quiet hours are skipped,
+// their threads still show the post as changed.
+async fn notify_post_revised(app: &AppState, editor_id: Uuid, revision: PostRevision) {
+    let participants = match db::messages::get_post_thread_participants(&app.db, revision.post_id).await {
+        Ok(participants) => participants,
+        Err(e) => {
+            tracing::error!("Failed to fetch thread participants: {:?}", e);
+            return;
+        }
+    };
+
+    let response = ChatResponse::PostRevised { revision };
+    let mut notified = HashSet::new();
+    for (thread_id, user_id) in participants {
+        if user_id == editor_id || notified.contains(&user_id) {
+            continue;
+        }
+
+        match notifications::should_notify(
+            &app.db,
+            user_id,
+            NotificationEvent::PostReply,
+            NotificationChannel::InApp,
+            Some(thread_id),
+        )
+        .await
+        {
+            Ok(delivery) if delivery.is_now() => {}
+            Ok(_) => continue,
+            // Like chat messages, notify when the preferences cannot be read
+            Err(e) => tracing::error!("Failed to read notification preferences for user {}: {:?}", user_id, e),
+        }
+
+        notified.insert(user_id);
+        send_to_user(&app.connection_manager, user_id, response.clone()).await;
+    }
+}
+
+// DELETE /posts/:id - Delete a post, it can be restored during the grace period
 pub async fn delete_post(
     State(app): State<AppState>,
     token: AccessToken,
@@ -462,9 +1393,10 @@ pub async fn delete_post(
     match db::posts::delete_post(db, post_id, user_id).await {
         Ok(true) => (
             StatusCode::OK,
-            Json(DeletePostResponse::new(
-                "Post deleted successfully".to_string(),
-            )),
+            Json(DeletePostResponse::new(format!(
+                "Post deleted, it can be restored within {} days",
+                DELETED_POST_GRACE_DAYS
+            ))),
         )
             .into_response(),
         Ok(false) => (
@@ -480,4 +1412,587 @@ pub async fn delete_post(
         )
             .into_response(),
     }
-}
\ No newline at end of file
+}
+
+// POST /posts/:id/restore - Undo a deletion within the grace period
+pub async fn restore_post(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let user_id = token.sub;
+
+    let post = match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.owner_id == user_id => post,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to restore post".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    if post.deleted_at.is_none() {
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new("Post is not deleted".to_string())),
+        )
+            .into_response();
+    }
+
+    match db::posts::restore_post(db, post_id, user_id, DELETED_POST_GRACE_DAYS).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::GONE,
+                Json(ErrorResponse::new(format!(
+                    "Deleted posts can only be restored within {} days",
+                    DELETED_POST_GRACE_DAYS
+                ))),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to restore post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to restore post".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) => {
+            let mut response = UpdatePostResponse::new(post);
+            if let Err(e) = load_attachments(db, std::slice::from_mut(&mut response.post)).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post not found".to_string())),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// POST /posts/:id/save - Bookmark a post
+pub async fn save_post(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::posts::save_post(db, token.sub, post_id).await {
+        Ok(true) => (StatusCode::OK, Json(SavePostResponse { is_saved: true })).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post not found".to_string())),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to save post: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to save post".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// DELETE /posts/:id/save - Remove a post from bookmarks
+pub async fn unsave_post(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::posts::unsave_post(db, token.sub, post_id).await {
+        Ok(true) => (StatusCode::OK, Json(SavePostResponse { is_saved: false })).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post is not saved".to_string())),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to unsave post: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to unsave post".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// POST /posts/:id/status - Move a post through its lifecycle, only the owner may do this
+pub async fn change_post_status(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    Json(request): Json<ChangePostStatusRequest>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let user_id = token.sub;
+
+    let Some(next) = PostStatus::parse(&request.status) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "Invalid status '{}', expected one of: {}",
+                request.status,
+                PostStatus::ALL.map(|s| s.as_str()).join(", ")
+            ))),
+        )
+            .into_response();
+    };
+
+    let reason = request
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+
+    // Drafts and scheduled posts of others are reported missing rather than forbidden
+    let post = match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.is_visible_to(Some(user_id)) => post,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to change post status".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    if post.owner_id != user_id {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new("Only the owner can change the status of a post".to_string())),
+        )
+            .into_response();
+    }
+
+    let Some(current) = PostStatus::parse(&post.status) else {
+        tracing::error!("Post {} has unknown status '{}'", post_id, post.status);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("Failed to change post status".to_string())),
+        )
+            .into_response();
+    };
+
+    if next.is_automatic() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!(
+                "Posts become '{}' automatically",
+                next.as_str()
+            ))),
+        )
+            .into_response();
+    }
+
+    if next == PostStatus::Scheduled {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "Use POST /posts/:id/publish with publishAt to schedule a post".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    // Publishing also alerts saved searches, it only happens in one place
+    if next == PostStatus::Active && !current.is_published() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "Use POST /posts/:id/publish to publish a post".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    if !current.can_transition_to(next) {
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new(format!(
+                "Cannot change status from '{}' to '{}'",
+                current.as_str(),
+                next.as_str()
+            ))),
+        )
+            .into_response();
+    }
+
+    // It would expire again right away
+    if next == PostStatus::Active
+        && let Some(deadline) = post.deadline
+        && deadline <= Utc::now()
+    {
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new(
+                "The deadline has passed, move it before reopening the post".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    match db::posts::change_post_status(db, post_id, current, next, Some(user_id), reason).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(
+                    "Post status was changed by someone else, reload and try again".to_string(),
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to change post status: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to change post status".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) => {
+            let mut response = UpdatePostResponse::new(post);
+            if let Err(e) = load_attachments(db, std::slice::from_mut(&mut response.post)).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post not found".to_string())),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// POST /posts/:id/publish - Publish a draft now, or schedule it with publishAt. Also moves the time of a scheduled post.
+pub async fn publish_post(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    Json(request): Json<PublishPostRequest>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let user_id = token.sub;
+
+    let post = match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.owner_id == user_id && post.deleted_at.is_none() => post,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to publish post".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    let current = match PostStatus::parse(&post.status) {
+        Some(current) if !current.is_published() => current,
+        Some(_) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new("Post is already published".to_string())),
+            )
+                .into_response()
+        }
+        None => {
+            tracing::error!("Post {} has unknown status '{}'", post_id, post.status);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to publish post".to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    let publish_now = request.publish_at.is_none();
+    let result = match request.publish_at {
+        Some(publish_at) => {
+            if let Err(message) = validate_publish_at(publish_at, post.deadline) {
+                return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+            }
+            db::posts::schedule_post(db, post_id, current, publish_at, user_id).await
+        }
+        None => {
+            // It would expire right away
+            if let Some(deadline) = post.deadline
+                && deadline <= Utc::now()
+            {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse::new(
+                        "The deadline has passed, move it before publishing the post".to_string(),
+                    )),
+                )
+                    .into_response();
+            }
+            db::posts::change_post_status(db, post_id, current, PostStatus::Active, Some(user_id), None).await
+        }
+    };
+
+    match result {
+        Ok(true) if publish_now => spawn_saved_search_alerts(db.clone(), post_id),
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(
+                    "Post status was changed by someone else, reload and try again".to_string(),
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to publish post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to publish post".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) => {
+            let mut response = UpdatePostResponse::new(post);
+            if let Err(e) = load_attachments(db, std::slice::from_mut(&mut response.post)).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Post not found".to_string())),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// GET /posts/:id/similar - Other active posts on similar subjects and topics, best match first
+pub async fn get_similar_posts(
+    State(app): State<AppState>,
+    token: Option<AccessToken>,
+    Path(post_id): Path<Uuid>,
+    Query(query): Query<GetSimilarPostsQuery>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let viewer_id = token.map(|t| t.sub);
+
+    match db::posts::get_post_by_id(db, post_id, viewer_id).await {
+        Ok(Some(post)) if post.is_visible_to(viewer_id) => {}
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch similar posts".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    let limit = query.limit.unwrap_or(6).clamp(1, MAX_SIMILAR_POSTS);
+
+    match db::posts::get_similar_posts(db, post_id, limit, viewer_id).await {
+        Ok(posts) => {
+            let mut posts: Vec<PostResponse> = posts.into_iter().map(PostResponse::from).collect();
+            if let Err(e) = load_attachments(db, &mut posts).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+            }
+            (StatusCode::OK, Json(GetSimilarPostsResponse { posts })).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch similar posts: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch similar posts".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// GET /posts/:id/analytics - Views, threads and proposals of a post, only for its owner
+pub async fn get_post_analytics(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    Query(query): Query<GetPostAnalyticsQuery>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let user_id = token.sub;
+
+    match db::posts::get_post_by_id(db, post_id, Some(user_id)).await {
+        Ok(Some(post)) if post.owner_id == user_id => {}
+        Ok(Some(post)) if post.is_visible_to(Some(user_id)) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::new("Only the owner can see post analytics".to_string())),
+            )
+                .into_response()
+        }
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Post not found".to_string())),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post analytics".to_string())),
+            )
+                .into_response();
+        }
+    }
+
+    let days = query.days.unwrap_or(DEFAULT_ANALYTICS_DAYS).clamp(1, MAX_ANALYTICS_DAYS);
+
+    match analytics::get_post_analytics(db, post_id, days).await {
+        Ok(analytics) => (StatusCode::OK, Json(GetPostAnalyticsResponse { analytics })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch post analytics: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch post analytics".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// GET /posts/:id/status/history - Status changes of a post, oldest first
+pub async fn get_post_status_history(
+    State(app): State<AppState>,
+    token: Option<AccessToken>,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    // Same rules as reading the post itself
+    let viewer_id = token.map(|t| t.sub);
+    if let Err(response) = find_readable_post(db, post_id, viewer_id, "Failed to fetch status history").await {
+        return response;
+    }
+
+    match db::posts::get_post_status_history(db, post_id).await {
+        Ok(history) => (
+            StatusCode::OK,
+            Json(GetPostStatusHistoryResponse { history }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch status history: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch status history".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+// GET /posts/:id/revisions - Edits of a post with old and new values, oldest first
+pub async fn get_post_revisions(
+    State(app): State<AppState>,
+    token: Option<AccessToken>,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    // Same rules as reading the post itself
+    if let Err(response) = find_readable_post(db, post_id, token.map(|t| t.sub), "Failed to fetch revisions").await {
+        return response;
+    }
+
+    match db::posts::get_post_revisions(db, post_id).await {
+        Ok(revisions) => (
+            StatusCode::OK,
+            Json(GetPostRevisionsResponse { revisions }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch revisions: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch revisions".to_string())),
+            )
+                .into_response()
+        }
+    }
+}
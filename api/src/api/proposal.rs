@@ -0,0 +1,258 @@
+// Proposal endpoints: applying to requests, booking offers and picking a helper
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{
+        auth::AccessToken,
+        money::AmountInput,
+        post::{Post, PostStatus},
+        proposal::{validate_proposal, Proposal, ProposalStatus},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProposalRequest {
+    pub message: String,
+    // Decimal string like "19.99", numbers are accepted from older clients
+    pub proposed_price: Option<AmountInput>,
+    pub proposed_deadline: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProposalResponse {
+    pub proposal: Proposal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetProposalsResponse {
+    pub proposals: Vec<Proposal>,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+async fn find_post(app: &AppState, post_id: Uuid, viewer_id: Uuid) -> Result<Post, Response> {
+    match db::posts::get_post_by_id(&app.db, post_id, Some(viewer_id)).await {
+        Ok(Some(post)) if post.is_visible_to(Some(viewer_id)) => Ok(post),
+        Ok(_) => Err(error(StatusCode::NOT_FOUND, "Post not found")),
+        Err(e) => {
+            tracing::error!("Failed to fetch post: {:?}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch post"))
+        }
+    }
+}
+
+/// Loads a proposal of the given post, proposals of other posts are reported as missing
+async fn find_proposal(app: &AppState, post_id: Uuid, proposal_id: Uuid) -> Result<Proposal, Response> {
+    match db::proposals::get_proposal(&app.db, proposal_id).await {
+        Ok(Some(proposal)) if proposal.post_id == post_id => Ok(proposal),
+        Ok(_) => Err(error(StatusCode::NOT_FOUND, "Proposal not found")),
+        Err(e) => {
+            tracing::error!("Failed to fetch proposal: {:?}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch proposal"))
+        }
+    }
+}
+
+// POST /posts/:id/proposals - Apply to a request or book an offer
+pub async fn create_proposal(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+    Json(request): Json<CreateProposalRequest>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let proposed_price = match validate_proposal(
+        &request.message,
+        request.proposed_price.as_ref(),
+        request.proposed_deadline,
+    ) {
+        Ok(proposed_price) => proposed_price,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response();
+        }
+    };
+
+    let post = match find_post(&app, post_id, user_id).await {
+        Ok(post) => post,
+        Err(response) => return response,
+    };
+
+    if post.owner_id == user_id {
+        return error(StatusCode::BAD_REQUEST, "You cannot send a proposal to your own post");
+    }
+
+    if post.status != PostStatus::Active.as_str() {
+        return error(StatusCode::CONFLICT, "Post is not accepting proposals");
+    }
+
+    match db::proposals::create_proposal(
+        &app.db,
+        post_id,
+        user_id,
+        request.message.trim(),
+        proposed_price,
+        request.proposed_deadline,
+    )
+    .await
+    {
+        Ok(Some(proposal)) => {
+            (StatusCode::CREATED, Json(ProposalResponse { proposal })).into_response()
+        }
+        Ok(None) => error(
+            StatusCode::CONFLICT,
+            "You already have a pending proposal on this post",
+        ),
+        Err(e) => {
+            tracing::error!("Failed to create proposal: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create proposal")
+        }
+    }
+}
+
+// GET /posts/:id/proposals - The owner sees all proposals, anyone else only their own
+pub async fn get_proposals(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(post_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let post = match find_post(&app, post_id, user_id).await {
+        Ok(post) => post,
+        Err(response) => return response,
+    };
+
+    let applicant_filter = (post.owner_id != user_id).then_some(user_id);
+
+    match db::proposals::get_post_proposals(&app.db, post_id, applicant_filter).await {
+        Ok(proposals) => (StatusCode::OK, Json(GetProposalsResponse { proposals })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch proposals: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch proposals")
+        }
+    }
+}
+
+// POST /posts/:id/proposals/:proposal_id/accept - Pick the helper, the post moves to in progress
+pub async fn accept_proposal(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path((post_id, proposal_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let post = match find_post(&app, post_id, user_id).await {
+        Ok(post) => post,
+        Err(response) => return response,
+    };
+
+    if post.owner_id != user_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can accept proposals");
+    }
+
+    let proposal = match find_proposal(&app, post_id, proposal_id).await {
+        Ok(proposal) => proposal,
+        Err(response) => return response,
+    };
+
+    if proposal.status != ProposalStatus::Pending.as_str() {
+        return error(StatusCode::CONFLICT, "Only pending proposals can be accepted");
+    }
+
+    if post.status != PostStatus::Active.as_str() {
+        return error(StatusCode::CONFLICT, "Proposals can only be accepted on active posts");
+    }
+
+    match db::proposals::accept_proposal(&app.db, post_id, proposal_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return error(
+                StatusCode::CONFLICT,
+                "Post or proposal was changed by someone else, reload and try again",
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to accept proposal: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to accept proposal");
+        }
+    }
+
+    match find_proposal(&app, post_id, proposal_id).await {
+        Ok(proposal) => (StatusCode::OK, Json(ProposalResponse { proposal })).into_response(),
+        Err(response) => response,
+    }
+}
+
+// POST /posts/:id/proposals/:proposal_id/reject - Decline a pending proposal
+pub async fn reject_proposal(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path((post_id, proposal_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let user_id = token.sub;
+
+    let post = match find_post(&app, post_id, user_id).await {
+        Ok(post) => post,
+        Err(response) => return response,
+    };
+
+    if post.owner_id != user_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can reject proposals");
+    }
+
+    close_proposal(&app, post_id, proposal_id, ProposalStatus::Rejected).await
+}
+
+// DELETE /posts/:id/proposals/:proposal_id - Withdraw your own pending proposal
+pub async fn withdraw_proposal(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path((post_id, proposal_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let proposal = match find_proposal(&app, post_id, proposal_id).await {
+        Ok(proposal) => proposal,
+        Err(response) => return response,
+    };
+
+    if proposal.applicant_id != token.sub {
+        return error(StatusCode::FORBIDDEN, "Only the applicant can withdraw a proposal");
+    }
+
+    close_proposal(&app, post_id, proposal_id, ProposalStatus::Withdrawn).await
+}
+
+async fn close_proposal(
+    app: &AppState,
+    post_id: Uuid,
+    proposal_id: Uuid,
+    status: ProposalStatus,
+) -> Response {
+    match db::proposals::close_proposal(&app.db, proposal_id, status).await {
+        Ok(true) => {}
+        Ok(false) => return error(StatusCode::CONFLICT, "Proposal is not pending anymore"),
+        Err(e) => {
+            tracing::error!("Failed to update proposal: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update proposal");
+        }
+    }
+
+    match find_proposal(app, post_id, proposal_id).await {
+        Ok(proposal) => (StatusCode::OK, Json(ProposalResponse { proposal })).into_response(),
+        Err(response) => response,
+    }
+}
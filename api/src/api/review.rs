@@ -1,6 +1,11 @@
 use crate::{
     app::AppState,
-    server::auth::AccessToken,
+    db,
+    server::{
+        auth::AccessToken,
+        pagination::{clamp_per_page, Cursor},
+        review,
+    },
 };
 use axum::{
     extract::{Path, Query, State},
@@ -44,6 +49,36 @@ pub struct GetReviewsQuery {
     pub profile_id: Option<Uuid>,
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    // Opaque value from a previous response's next_cursor, replaces page
+    pub cursor: Option<String>,
+    // Also count all matching reviews
+    pub include_total: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetReviewsResponse {
+    pub reviews: Vec<Review>,
+    // Pass back as `cursor` to get the next page, null on the last page
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+fn push_review_filters(query_builder: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>, params: &GetReviewsQuery) {
+    if let Some(review_type) = &params.review_type {
+        query_builder.push(" AND r.type = ");
+        query_builder.push_bind(review_type.clone());
+    }
+
+    if let Some(post_id) = params.post_id {
+        query_builder.push(" AND r.post_id = ");
+        query_builder.push_bind(post_id);
+    }
+
+    if let Some(profile_id) = params.profile_id {
+        query_builder.push(" AND r.profile_id = ");
+        query_builder.push_bind(profile_id);
+    }
 }
 
 pub async fn create_review(
@@ -164,22 +199,46 @@ pub async fn create_review(
         review_type: review.r#type,
         post_id: review.post_id,
         profile_id: review.profile_id,
-        created_at: review.created_at.unwrap_or_else(|| chrono::Utc::now()),
-        updated_at: review.updated_at.unwrap_or_else(|| chrono::Utc::now()),
+        created_at: review.created_at.unwrap_or_else(chrono::Utc::now),
+        updated_at: review.updated_at.unwrap_or_else(chrono::Utc::now),
         sender_name: sender_info.as_ref().and_then(|s| s.name.clone()),
         sender_username: sender_info.as_ref().map(|s| s.username.clone()),
     };
 
+    // The review is stored either way, a failed notification is only logged
+    let sender_name = review_response
+        .sender_name
+        .as_deref()
+        .or(review_response.sender_username.as_deref())
+        .unwrap_or("Someone");
+    if let Err(e) = review::notify_review_received(
+        &app_state.db,
+        review_response.review_receiver_id,
+        sender_name,
+        review_response.score,
+        review_response.post_id,
+    )
+    .await
+    {
+        tracing::error!("Failed to notify about review {}: {:?}", review_response.id, e);
+    }
+
     Ok(Json(review_response))
 }
 
 pub async fn get_reviews(
     State(app_state): State<AppState>,
     Query(params): Query<GetReviewsQuery>,
-) -> Result<Json<Vec<Review>>, (StatusCode, String)> {
-    let page = params.page.unwrap_or(0);
-    let limit = params.limit.unwrap_or(20).min(100);
-    let offset = page * limit;
+) -> Result<Json<GetReviewsResponse>, (StatusCode, String)> {
+    let limit = clamp_per_page(params.limit, 20);
+
+    let cursor = match params.cursor.as_deref() {
+        Some(cursor) => Some(
+            Cursor::decode(cursor)
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?,
+        ),
+        None => None,
+    };
 
     let mut query_builder = sqlx::QueryBuilder::new(
         r#"
@@ -192,26 +251,26 @@ pub async fn get_reviews(
         "#
     );
 
-    if let Some(review_type) = &params.review_type {
-        query_builder.push(" AND r.type = ");
-        query_builder.push_bind(review_type);
-    }
-
-    if let Some(post_id) = &params.post_id {
-        query_builder.push(" AND r.post_id = ");
-        query_builder.push_bind(post_id);
-    }
+    push_review_filters(&mut query_builder, &params);
 
-    if let Some(profile_id) = &params.profile_id {
-        query_builder.push(" AND r.profile_id = ");
-        query_builder.push_bind(profile_id);
+    if let Some(cursor) = cursor {
+        query_builder.push(" AND (r.created_at, r.id) < (");
+        query_builder.push_bind(cursor.timestamp);
+        query_builder.push(", ");
+        query_builder.push_bind(cursor.id);
+        query_builder.push(")");
     }
 
-    query_builder.push(" ORDER BY r.created_at DESC");
+    // One extra row tells whether there is a next page
+    query_builder.push(" ORDER BY r.created_at DESC, r.id DESC");
     query_builder.push(" LIMIT ");
-    query_builder.push_bind(limit);
-    query_builder.push(" OFFSET ");
-    query_builder.push_bind(offset);
+    query_builder.push_bind(limit + 1);
+
+    if cursor.is_none() {
+        let page = params.page.unwrap_or(0).max(0);
+        query_builder.push(" OFFSET ");
+        query_builder.push_bind(page.saturating_mul(limit));
+    }
 
     let rows = query_builder
         .build()
@@ -219,7 +278,7 @@ pub async fn get_reviews(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let reviews: Vec<Review> = rows
+    let mut reviews: Vec<Review> = rows
         .into_iter()
         .map(|row| Review {
             id: row.get("id"),
@@ -237,7 +296,33 @@ pub async fn get_reviews(
         })
         .collect();
 
-    Ok(Json(reviews))
+    let has_more = reviews.len() as i64 > limit;
+    reviews.truncate(limit as usize);
+    let next_cursor = match reviews.last() {
+        Some(last) if has_more => Some(Cursor::new(last.created_at, last.id).encode()),
+        _ => None,
+    };
+
+    let total = if params.include_total.unwrap_or(false) {
+        let mut count_builder =
+            sqlx::QueryBuilder::new("SELECT COUNT(*) FROM reviews r WHERE 1=1");
+        push_review_filters(&mut count_builder, &params);
+
+        let total: i64 = count_builder
+            .build_query_scalar()
+            .fetch_one(&app_state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        Some(total)
+    } else {
+        None
+    };
+
+    Ok(Json(GetReviewsResponse {
+        reviews,
+        next_cursor,
+        total,
+    }))
 }
 
 pub async fn delete_review(
@@ -277,7 +362,26 @@ pub async fn get_review_stats(
     Query(params): Query<GetReviewsQuery>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     let review_type = params.review_type.as_deref().unwrap_or("profile");
-    
+
+    // A user's rating is kept in the user_ratings aggregate, only per post stats are computed here
+    if review_type == "profile" {
+        let rating = db::users::get_user_rating(&app_state.db, target_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        return Ok(Json(serde_json::json!({
+            "total_reviews": rating.review_count,
+            "average_score": rating.average_score,
+            "rating_breakdown": {
+                "five_stars": rating.five_stars,
+                "four_stars": rating.four_stars,
+                "three_stars": rating.three_stars,
+                "two_stars": rating.two_stars,
+                "one_star": rating.one_star
+            }
+        })));
+    }
+
     let stats = sqlx::query!(
         r#"
         SELECT 
@@ -289,9 +393,8 @@ pub async fn get_review_stats(
             COUNT(CASE WHEN score = 2 THEN 1 END) as two_stars,
             COUNT(CASE WHEN score = 1 THEN 1 END) as one_star
         FROM reviews 
-        WHERE (($1::text = 'post' AND post_id = $2) OR ($1::text = 'profile' AND profile_id = $2))
+        WHERE type = 'post' AND post_id = $1
         "#,
-        review_type,
         target_id
     )
     .fetch_one(&app_state.db)
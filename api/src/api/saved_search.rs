@@ -0,0 +1,122 @@
+// Saved post searches and their alerts
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::{post::GetPostsQuery, user::ErrorResponse},
+    app::AppState,
+    db,
+    server::{
+        auth::AccessToken,
+        saved_search::{validate_saved_search, SavedSearch, MAX_SAVED_SEARCHES},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSavedSearchRequest {
+    pub name: String,
+    // Same parameters as GET /posts, e.g. {"subject": "fizyka", "type": "request"}
+    pub filters: GetPostsQuery,
+    // Notify about new matching posts, on by default
+    pub alerts: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedSearchResponse {
+    pub saved_search: SavedSearch,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetSavedSearchesResponse {
+    pub saved_searches: Vec<SavedSearch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteSavedSearchResponse {
+    pub message: String,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+// GET /user/me/saved-searches - The current user's saved searches, oldest first
+pub async fn get_my_saved_searches(State(app): State<AppState>, token: AccessToken) -> impl IntoResponse {
+    match db::saved_searches::get_saved_searches(&app.db, token.sub).await {
+        Ok(saved_searches) => {
+            (StatusCode::OK, Json(GetSavedSearchesResponse { saved_searches })).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch saved searches: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch saved searches")
+        }
+    }
+}
+
+// POST /user/me/saved-searches - Save a search, with alerts on new posts matching it
+pub async fn create_saved_search(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Json(request): Json<CreateSavedSearchRequest>,
+) -> impl IntoResponse {
+    let (name, filters, post_filters) = match validate_saved_search(&request.name, &request.filters) {
+        Ok(search) => search,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+
+    match db::saved_searches::create_saved_search(
+        &app.db,
+        token.sub,
+        &name,
+        &filters,
+        &post_filters,
+        request.alerts.unwrap_or(true),
+        MAX_SAVED_SEARCHES,
+    )
+    .await
+    {
+        Ok(Some(saved_search)) => {
+            (StatusCode::CREATED, Json(SavedSearchResponse { saved_search })).into_response()
+        }
+        Ok(None) => error(
+            StatusCode::CONFLICT,
+            &format!(
+                "You can save at most {} searches, delete one first",
+                MAX_SAVED_SEARCHES
+            ),
+        ),
+        Err(e) => {
+            tracing::error!("Failed to save search: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save search")
+        }
+    }
+}
+
+// DELETE /user/me/saved-searches/:id - Remove a saved search and its alerts
+pub async fn delete_saved_search(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(search_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match db::saved_searches::delete_saved_search(&app.db, search_id, token.sub).await {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(DeleteSavedSearchResponse {
+                message: "Saved search deleted".to_string(),
+            }),
+        )
+            .into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, "Saved search not found"),
+        Err(e) => {
+            tracing::error!("Failed to delete saved search: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete saved search")
+        }
+    }
+}
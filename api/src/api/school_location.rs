@@ -0,0 +1,183 @@
+// School location endpoints, reading is public and curating is admin only
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db,
+    server::{
+        auth::AdminToken,
+        school_location::{validate_location_name, SchoolLocation, SchoolLocationKind},
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetSchoolLocationsResponse {
+    pub locations: Vec<SchoolLocation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchoolLocationResponse {
+    pub location: SchoolLocation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSchoolLocationRequest {
+    pub name: String,
+    // campus, building or room
+    pub kind: String,
+    // Campus of a building or building of a room, campuses have none
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameSchoolLocationRequest {
+    pub name: String,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+/// Current state of a location after a change
+async fn location_response(app: &AppState, location_id: Uuid, status: StatusCode) -> Response {
+    match db::school_locations::get_school_location(&app.db, location_id).await {
+        Ok(Some(location)) => (status, Json(SchoolLocationResponse { location })).into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, "Location not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch school location: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch location")
+        }
+    }
+}
+
+// GET /school-locations - All campuses, buildings and rooms with counts of active posts,
+// children reference their parent
+pub async fn get_school_locations(State(app): State<AppState>) -> impl IntoResponse {
+    match db::school_locations::get_school_locations(&app.db, None).await {
+        Ok(locations) => (StatusCode::OK, Json(GetSchoolLocationsResponse { locations })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch school locations: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch locations")
+        }
+    }
+}
+
+// POST /admin/school-locations - Add a campus, a building on a campus or a room in a building
+pub async fn create_school_location(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+    Json(request): Json<CreateSchoolLocationRequest>,
+) -> impl IntoResponse {
+    if let Err(message) = validate_location_name(&request.name) {
+        return error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    let Some(kind) = SchoolLocationKind::parse(request.kind.trim()) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Invalid kind '{}', expected one of: {}",
+                request.kind,
+                SchoolLocationKind::ALL.map(|k| k.as_str()).join(", ")
+            ),
+        );
+    };
+
+    match (kind.parent_kind(), request.parent_id) {
+        (None, None) => {}
+        (None, Some(_)) => return error(StatusCode::BAD_REQUEST, "A campus cannot have a parent"),
+        (Some(parent_kind), None) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("A {} needs a {} as parent_id", kind.as_str(), parent_kind.as_str()),
+            );
+        }
+        (Some(parent_kind), Some(parent_id)) => {
+            match db::school_locations::get_school_location(&app.db, parent_id).await {
+                Ok(Some(parent)) if parent.kind == parent_kind.as_str() => {}
+                Ok(Some(_)) => {
+                    return error(
+                        StatusCode::BAD_REQUEST,
+                        &format!("A {} must be inside a {}", kind.as_str(), parent_kind.as_str()),
+                    );
+                }
+                Ok(None) => return error(StatusCode::BAD_REQUEST, "Location not found"),
+                Err(e) => {
+                    tracing::error!("Failed to fetch school location: {:?}", e);
+                    return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch location");
+                }
+            }
+        }
+    }
+
+    let location_id = match db::school_locations::create_school_location(
+        &app.db,
+        request.name.trim(),
+        kind.as_str(),
+        request.parent_id,
+    )
+    .await
+    {
+        Ok(Some(location_id)) => location_id,
+        Ok(None) => {
+            return error(
+                StatusCode::CONFLICT,
+                "A location with this name already exists at this level",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to create school location: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create location");
+        }
+    };
+
+    tracing::info!("Admin {} created school location {}", admin.sub, location_id);
+    location_response(&app, location_id, StatusCode::CREATED).await
+}
+
+// PUT /admin/school-locations/:id - Rename a location, its slug stays the same
+pub async fn rename_school_location(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+    Path(location_id): Path<Uuid>,
+    Json(request): Json<RenameSchoolLocationRequest>,
+) -> impl IntoResponse {
+    if let Err(message) = validate_location_name(&request.name) {
+        return error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    match db::school_locations::get_school_location(&app.db, location_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Location not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch school location: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch location");
+        }
+    }
+
+    match db::school_locations::rename_school_location(&app.db, location_id, request.name.trim()).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return error(
+                StatusCode::CONFLICT,
+                "A location with this name already exists at this level",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to rename school location: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to rename location");
+        }
+    }
+
+    tracing::info!("Admin {} renamed school location {}", admin.sub, location_id);
+    location_response(&app, location_id, StatusCode::OK).await
+}
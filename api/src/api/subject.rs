@@ -0,0 +1,192 @@
+// Subject taxonomy endpoints, reading is public and curating is admin only
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::user::ErrorResponse,
+    app::AppState,
+    db::{self, subjects::MergeSubjects},
+    server::{
+        auth::AdminToken,
+        subject::{validate_subject_name, Subject},
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetSubjectsResponse {
+    pub subjects: Vec<Subject>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubjectResponse {
+    pub subject: Subject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSubjectRequest {
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameSubjectRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeSubjectRequest {
+    // Subject that takes over the posts, children and aliases
+    pub into: Uuid,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse::new(message.to_string()))).into_response()
+}
+
+/// Current state of a subject after a change
+async fn subject_response(app: &AppState, subject_id: Uuid, status: StatusCode) -> Response {
+    match db::subjects::get_subject(&app.db, subject_id).await {
+        Ok(Some(subject)) => (status, Json(SubjectResponse { subject })).into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, "Subject not found"),
+        Err(e) => {
+            tracing::error!("Failed to fetch subject: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch subject")
+        }
+    }
+}
+
+/// Checks that a subject exists, `missing` is the status used when it does not
+async fn require_subject(app: &AppState, subject_id: Uuid, missing: StatusCode) -> Result<(), Response> {
+    match db::subjects::get_subject(&app.db, subject_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(error(missing, "Subject not found")),
+        Err(e) => {
+            tracing::error!("Failed to fetch subject: {:?}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch subject"))
+        }
+    }
+}
+
+// GET /subjects - The whole taxonomy with counts of active posts, children reference their parent
+pub async fn get_subjects(State(app): State<AppState>) -> impl IntoResponse {
+    match db::subjects::get_subjects(&app.db, None).await {
+        Ok(subjects) => (StatusCode::OK, Json(GetSubjectsResponse { subjects })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch subjects: {:?}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch subjects")
+        }
+    }
+}
+
+// POST /admin/subjects - Add a subject, at the top level or below `parent_id`
+pub async fn create_subject(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+    Json(request): Json<CreateSubjectRequest>,
+) -> impl IntoResponse {
+    if let Err(message) = validate_subject_name(&request.name) {
+        return error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    if let Some(parent_id) = request.parent_id
+        && let Err(response) = require_subject(&app, parent_id, StatusCode::BAD_REQUEST).await
+    {
+        return response;
+    }
+
+    let subject_id = match db::subjects::create_subject(&app.db, request.name.trim(), request.parent_id).await {
+        Ok(Some(subject_id)) => subject_id,
+        Ok(None) => {
+            return error(
+                StatusCode::CONFLICT,
+                "A subject with this name already exists at this level",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to create subject: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create subject");
+        }
+    };
+
+    tracing::info!("Admin {} created subject {}", admin.sub, subject_id);
+    subject_response(&app, subject_id, StatusCode::CREATED).await
+}
+
+// PUT /admin/subjects/:id - Rename a subject, the old name keeps resolving as an alias
+pub async fn rename_subject(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+    Path(subject_id): Path<Uuid>,
+    Json(request): Json<RenameSubjectRequest>,
+) -> impl IntoResponse {
+    if let Err(message) = validate_subject_name(&request.name) {
+        return error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    if let Err(response) = require_subject(&app, subject_id, StatusCode::NOT_FOUND).await {
+        return response;
+    }
+
+    match db::subjects::rename_subject(&app.db, subject_id, request.name.trim()).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return error(
+                StatusCode::CONFLICT,
+                "A subject with this name already exists at this level",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to rename subject: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to rename subject");
+        }
+    }
+
+    tracing::info!("Admin {} renamed subject {}", admin.sub, subject_id);
+    subject_response(&app, subject_id, StatusCode::OK).await
+}
+
+// POST /admin/subjects/:id/merge - Fold a subject into another one and remove it
+pub async fn merge_subject(
+    State(app): State<AppState>,
+    AdminToken(admin): AdminToken,
+    Path(subject_id): Path<Uuid>,
+    Json(request): Json<MergeSubjectRequest>,
+) -> impl IntoResponse {
+    if let Err(response) = require_subject(&app, subject_id, StatusCode::NOT_FOUND).await {
+        return response;
+    }
+
+    if let Err(response) = require_subject(&app, request.into, StatusCode::BAD_REQUEST).await {
+        return response;
+    }
+
+    match db::subjects::merge_subjects(&app.db, subject_id, request.into).await {
+        Ok(MergeSubjects::Merged) => {}
+        Ok(MergeSubjects::IntoOwnSubtree) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "A subject cannot be merged into itself or one of its subsubjects",
+            );
+        }
+        Ok(MergeSubjects::ChildNameConflict(name)) => {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("Both subjects have a subsubject named '{}', merge those first", name),
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to merge subjects: {:?}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to merge subjects");
+        }
+    }
+
+    tracing::info!("Admin {} merged subject {} into {}", admin.sub, subject_id, request.into);
+    subject_response(&app, request.into, StatusCode::OK).await
+}
@@ -1,7 +1,7 @@
 // User related endpoints
 
 use axum::{
-    extract::{Path, State},
+    extract::{rejection::QueryRejection, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -10,9 +10,10 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
+    api::post::{list_posts, load_attachments, GetPostsQuery, GetPostsResponse},
     app::AppState,
     db,
-    server::auth::AccessToken,
+    server::{auth::AccessToken, pagination::clamp_per_page, user::UserStats},
 };
 
 #[derive(Debug, Serialize)]
@@ -32,6 +33,27 @@ pub struct UserInfo {
     pub name: Option<String>,
     pub email: String,
     pub subjects: Option<Vec<String>>,
+    pub rating: rust_decimal::Decimal,
+    pub review_count: i32,
+    // Only present for authenticated callers looking at someone else
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_following: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetUserStatsResponse {
+    pub stats: UserStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FollowUserResponse {
+    pub is_following: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaginationQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -71,7 +93,7 @@ pub async fn get_current_user(
     let db = &app.db;
     let user_id = token.sub;
 
-    match db::users::get_user_by_id_public(db, user_id).await {
+    match db::users::get_user_by_id_public(db, user_id, None).await {
         Ok(Some(user)) => (StatusCode::OK, Json(GetUserResponse::new(user))).into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
@@ -89,6 +111,7 @@ pub async fn get_current_user(
 // GET /users/{id} - Get user information by ID (public information only)
 pub async fn get_user_by_id(
     State(app): State<AppState>,
+    token: Option<AccessToken>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
     let db = &app.db;
@@ -105,7 +128,7 @@ pub async fn get_user_by_id(
         }
     };
 
-    match db::users::get_user_by_id_public(db, user_uuid).await {
+    match db::users::get_user_by_id_public(db, user_uuid, token.map(|t| t.sub)).await {
         Ok(Some(user)) => (StatusCode::OK, Json(GetUserResponse::new(user))).into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
@@ -139,7 +162,7 @@ pub async fn update_user(
     }
 
     // Update user in database
-    if let Err(_) = db::users::update_user(db, user_id, request.name, request.subjects).await {
+    if db::users::update_user(db, user_id, request.name, request.subjects).await.is_err() {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new("Failed to update user".to_string())),
@@ -148,7 +171,7 @@ pub async fn update_user(
     }
 
     // Fetch updated user information
-    match db::users::get_user_by_id_public(db, user_id).await {
+    match db::users::get_user_by_id_public(db, user_id, None).await {
         Ok(Some(user)) => (StatusCode::OK, Json(GetUserResponse::new(user))).into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
@@ -166,10 +189,11 @@ pub async fn update_user(
 // GET /users - Get all users (public information only)
 pub async fn get_all_users(
     State(app): State<AppState>,
+    token: Option<AccessToken>,
 ) -> impl IntoResponse {
     let db = &app.db;
 
-    match db::users::get_all_users_public(db).await {
+    match db::users::get_all_users_public(db, token.map(|t| t.sub)).await {
         Ok(users) => (StatusCode::OK, Json(GetUsersResponse::new(users))).into_response(),
         Err(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -179,4 +203,223 @@ pub async fn get_all_users(
     }
 }
 
+// POST /users/{id}/follow - Follow a user
+pub async fn follow_user(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    if token.sub == user_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("You cannot follow yourself".to_string())),
+        )
+        .into_response();
+    }
+
+    match db::users::follow_user(db, token.sub, user_id).await {
+        Ok(true) => (StatusCode::OK, Json(FollowUserResponse { is_following: true })).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("User not found".to_string())),
+        )
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to follow user: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to follow user".to_string())),
+            )
+            .into_response()
+        }
+    }
+}
+
+// DELETE /users/{id}/follow - Unfollow a user
+pub async fn unfollow_user(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    match db::users::unfollow_user(db, token.sub, user_id).await {
+        Ok(true) => (StatusCode::OK, Json(FollowUserResponse { is_following: false })).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("You are not following this user".to_string())),
+        )
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to unfollow user: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to unfollow user".to_string())),
+            )
+            .into_response()
+        }
+    }
+}
+
+// GET /user/me/saved - Posts bookmarked by the current user
+pub async fn get_saved_posts(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Query(query): Query<PaginationQuery>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let per_page = clamp_per_page(query.per_page, 10);
+    let offset = query.page.unwrap_or(0).max(0).saturating_mul(per_page);
+
+    match db::posts::get_saved_posts(db, token.sub, offset, per_page).await {
+        Ok(posts) => {
+            let mut response = GetPostsResponse::new(posts);
+            if let Err(e) = load_attachments(db, &mut response.posts).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to fetch saved posts".to_string())),
+                )
+                .into_response();
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch saved posts: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch saved posts".to_string())),
+            )
+            .into_response()
+        }
+    }
+}
+
+// GET /user/me/posts - Posts of the current user including drafts and scheduled ones,
+// takes the same filters as GET /posts, e.g. ?status=draft
+pub async fn get_my_posts(
+    State(app): State<AppState>,
+    token: AccessToken,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(rejection) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(rejection.body_text())),
+            )
+                .into_response()
+        }
+    };
 
+    let mut filters = match query.filters() {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response()
+        }
+    };
+    filters.owner_id = Some(token.sub);
+    filters.include_unpublished = true;
+
+    list_posts(&app.db, &query, filters, Some(token.sub)).await
+}
+
+// GET /user/me/posts/deleted - Deleted posts of the current user, restorableUntil tells how long
+// each can still be restored. Takes the same filters as GET /posts.
+pub async fn get_my_deleted_posts(
+    State(app): State<AppState>,
+    token: AccessToken,
+    query: Result<Query<GetPostsQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(rejection) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(rejection.body_text())),
+            )
+                .into_response()
+        }
+    };
+
+    let mut filters = match query.filters() {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message))).into_response()
+        }
+    };
+    filters.owner_id = Some(token.sub);
+    filters.include_unpublished = true;
+    filters.deleted = true;
+
+    list_posts(&app.db, &query, filters, Some(token.sub)).await
+}
+
+// GET /user/me/following/posts - Feed of posts by users the current user follows
+pub async fn get_following_posts(
+    State(app): State<AppState>,
+    token: AccessToken,
+    Query(query): Query<PaginationQuery>,
+) -> impl IntoResponse {
+    let db = &app.db;
+    let per_page = clamp_per_page(query.per_page, 10);
+    let offset = query.page.unwrap_or(0).max(0).saturating_mul(per_page);
+
+    match db::posts::get_followed_posts(db, token.sub, offset, per_page).await {
+        Ok(posts) => {
+            let mut response = GetPostsResponse::new(posts);
+            if let Err(e) = load_attachments(db, &mut response.posts).await {
+                tracing::error!("Failed to fetch attachments: {:?}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to fetch following feed".to_string())),
+                )
+                .into_response();
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch following feed: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch following feed".to_string())),
+            )
+            .into_response()
+        }
+    }
+}
+
+// GET /users/{id}/stats - Activity and reliability statistics for a user
+pub async fn get_user_stats(
+    State(app): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db = &app.db;
+
+    if let Some(stats) = app.user_stats_cache.get(&user_id).await {
+        return (StatusCode::OK, Json(GetUserStatsResponse { stats })).into_response();
+    }
+
+    match db::users::get_user_stats(db, user_id).await {
+        Ok(Some(stats)) => {
+            app.user_stats_cache.insert(stats.clone()).await;
+            (StatusCode::OK, Json(GetUserStatsResponse { stats })).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("User not found".to_string())),
+        )
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch user stats: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Failed to fetch user stats".to_string())),
+            )
+            .into_response()
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use crate::common::env;
 use crate::error::Result;
 use crate::api::chat::ConnectionManager;
+use crate::server::blob_store::{blob_store_from_env, BlobStore};
+use crate::server::scheduler::Scheduler;
+use crate::server::user::UserStatsCache;
+use crate::server::views::ViewCounter;
 use sqlx::migrate::Migrator;
 use sqlx::PgPool;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
     pub connection_manager: ConnectionManager,
+    pub user_stats_cache: UserStatsCache,
+    pub view_counter: ViewCounter,
+    pub blob_store: Arc<dyn BlobStore>,
 }
 
 static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
@@ -22,10 +30,20 @@ impl AppState {
         
         // Initialize connection manager
         let connection_manager = crate::api::chat::create_connection_manager();
-        
+
+        let view_counter = ViewCounter::from_env()?;
+        view_counter.spawn_flusher(db.clone());
+
+        let blob_store = blob_store_from_env()?;
+
+        Scheduler::from_env(db.clone(), blob_store.clone()).spawn();
+
         Ok(Self { 
             db,
             connection_manager,
+            user_stats_cache: UserStatsCache::default(),
+            view_counter,
+            blob_store,
         })
     }
 }
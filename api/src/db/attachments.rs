@@ -0,0 +1,138 @@
+// Database functions for post attachments
+
+use std::collections::HashMap;
+
+use crate::{error::Result, server::attachment::PostAttachment};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Outcome of trying to record a new attachment
+pub enum CreateAttachment {
+    Created(PostAttachment),
+    /// The post already has the maximum number of attachments
+    PostFull,
+    /// The upload would take the uploader over their quota, carries the current usage
+    OverQuota(i64),
+}
+
+/// Records an attachment while holding a per user lock, so concurrent uploads
+/// cannot both squeeze under the quota
+#[allow(clippy::too_many_arguments)]
+pub async fn create_attachment(
+    db: &PgPool,
+    attachment_id: Uuid,
+    post_id: Uuid,
+    uploader_id: Uuid,
+    file_name: &str,
+    content_type: &str,
+    size_bytes: i64,
+    storage_key: &str,
+    quota: i64,
+    max_per_post: i64,
+) -> Result<CreateAttachment> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtext('attachments:' || $1::text))",
+        uploader_id.to_string()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let usage = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT COALESCE(SUM(size_bytes), 0) FROM post_attachments WHERE uploader_id = $1)::BIGINT AS "used!",
+            (SELECT COUNT(*) FROM post_attachments WHERE post_id = $2) AS "on_post!"
+        "#,
+        uploader_id,
+        post_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if usage.on_post >= max_per_post {
+        return Ok(CreateAttachment::PostFull);
+    }
+
+    if usage.used + size_bytes > quota {
+        return Ok(CreateAttachment::OverQuota(usage.used));
+    }
+
+    let attachment = sqlx::query_as!(
+        PostAttachment,
+        r#"
+        INSERT INTO post_attachments (id, post_id, uploader_id, file_name, content_type, size_bytes, storage_key)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, post_id, uploader_id, file_name, content_type, size_bytes, storage_key, created_at
+        "#,
+        attachment_id,
+        post_id,
+        uploader_id,
+        file_name,
+        content_type,
+        size_bytes,
+        storage_key
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(CreateAttachment::Created(attachment))
+}
+
+pub async fn get_attachment(
+    db: &PgPool,
+    post_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<Option<PostAttachment>> {
+    let attachment = sqlx::query_as!(
+        PostAttachment,
+        r#"
+        SELECT id, post_id, uploader_id, file_name, content_type, size_bytes, storage_key, created_at
+        FROM post_attachments
+        WHERE id = $1 AND post_id = $2
+        "#,
+        attachment_id,
+        post_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(attachment)
+}
+
+/// Attachments of the given posts grouped by post, oldest first
+pub async fn get_attachments_for_posts(
+    db: &PgPool,
+    post_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<PostAttachment>>> {
+    let attachments = sqlx::query_as!(
+        PostAttachment,
+        r#"
+        SELECT id, post_id, uploader_id, file_name, content_type, size_bytes, storage_key, created_at
+        FROM post_attachments
+        WHERE post_id = ANY($1)
+        ORDER BY created_at, id
+        "#,
+        post_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut by_post: HashMap<Uuid, Vec<PostAttachment>> = HashMap::new();
+    for attachment in attachments {
+        by_post.entry(attachment.post_id).or_default().push(attachment);
+    }
+
+    Ok(by_post)
+}
+
+pub async fn delete_attachment(db: &PgPool, attachment_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!("DELETE FROM post_attachments WHERE id = $1", attachment_id)
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
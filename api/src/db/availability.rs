@@ -0,0 +1,127 @@
+// Database functions for user availability schedules
+
+use crate::{
+    error::Result,
+    server::availability::{Availability, AvailabilityException, WeeklySlot},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Returns the user's schedule, or an empty one in the default time zone if none is set
+pub async fn get_availability(db: &PgPool, user_id: Uuid) -> Result<Availability> {
+    let time_zone = sqlx::query_scalar!(
+        "SELECT time_zone FROM user_availability WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    let Some(time_zone) = time_zone else {
+        return Ok(Availability::default());
+    };
+
+    let weekly = sqlx::query_as!(
+        WeeklySlot,
+        r#"
+        SELECT weekday, start_time, end_time
+        FROM availability_slots
+        WHERE user_id = $1
+        ORDER BY weekday, start_time
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let exceptions = sqlx::query_as!(
+        AvailabilityException,
+        r#"
+        SELECT date, is_available, start_time, end_time
+        FROM availability_exceptions
+        WHERE user_id = $1
+        ORDER BY date, start_time NULLS FIRST
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(Availability {
+        time_zone,
+        weekly,
+        exceptions,
+    })
+}
+
+/// Replaces the user's whole schedule in a single transaction
+pub async fn set_availability(db: &PgPool, user_id: Uuid, availability: &Availability) -> Result<()> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_availability (user_id, time_zone)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET
+            time_zone = EXCLUDED.time_zone,
+            updated_at = NOW()
+        "#,
+        user_id,
+        availability.time_zone
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM availability_slots WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM availability_exceptions WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for slot in &availability.weekly {
+        sqlx::query!(
+            r#"
+            INSERT INTO availability_slots (user_id, weekday, start_time, end_time)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            user_id,
+            slot.weekday,
+            slot.start_time,
+            slot.end_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for exception in &availability.exceptions {
+        sqlx::query!(
+            r#"
+            INSERT INTO availability_exceptions (user_id, date, is_available, start_time, end_time)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            user_id,
+            exception.date,
+            exception.is_available,
+            exception.start_time,
+            exception.end_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Checks the name against the time zones known to PostgreSQL
+pub async fn is_valid_time_zone(db: &PgPool, time_zone: &str) -> Result<bool> {
+    Ok(sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)",
+        time_zone
+    )
+    .fetch_one(db)
+    .await?
+    .unwrap_or(false))
+}
@@ -0,0 +1,238 @@
+// Database functions for background jobs
+
+use crate::{error::Result, server::scheduler::JobStatus};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Post whose owner should be reminded about the deadline
+pub struct DueReminder {
+    pub post_id: Uuid,
+    pub owner_id: Uuid,
+    pub title: String,
+    pub deadline: DateTime<Utc>,
+}
+
+/// Deleted posts removed by the purge job
+pub struct PurgedPosts {
+    pub count: u64,
+    /// Attachment files that have to be removed from the blob store
+    pub storage_keys: Vec<String>,
+}
+
+/// Takes the job's lock for the rest of the transaction, false if another instance holds it
+pub async fn try_lock_job(conn: &mut PgConnection, name: &str) -> Result<bool> {
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_xact_lock(hashtext('job:' || $1)) AS "locked!""#,
+        name
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(locked)
+}
+
+pub async fn record_job_start(db: &PgPool, name: &str, instance: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO scheduled_jobs (name, last_started_at, last_instance, run_count)
+        VALUES ($1, NOW(), $2, 1)
+        ON CONFLICT (name) DO UPDATE SET
+            last_started_at = NOW(),
+            last_instance = EXCLUDED.last_instance,
+            run_count = scheduled_jobs.run_count + 1
+        "#,
+        name,
+        instance
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn record_job_finish(
+    db: &PgPool,
+    name: &str,
+    result: std::result::Result<u64, String>,
+) -> Result<()> {
+    let (succeeded, affected, error) = match result {
+        Ok(affected) => (true, Some(affected as i64), None),
+        Err(error) => (false, None, Some(error)),
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs SET
+            last_finished_at = NOW(),
+            last_succeeded = $2,
+            last_affected = $3,
+            last_error = $4
+        WHERE name = $1
+        "#,
+        name,
+        succeeded,
+        affected,
+        error
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_job_statuses(db: &PgPool) -> Result<Vec<JobStatus>> {
+    let jobs = sqlx::query_as!(
+        JobStatus,
+        r#"
+        SELECT name, last_started_at, last_finished_at, last_succeeded, last_error,
+               last_affected, last_instance, run_count
+        FROM scheduled_jobs
+        ORDER BY name
+        "#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(jobs)
+}
+
+/// Moves active posts past their deadline to expired, returns how many expired
+pub async fn expire_overdue_posts(conn: &mut PgConnection) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+        WITH overdue AS (
+            SELECT id FROM posts
+            WHERE status = 'active' AND deadline < NOW() AND deleted_at IS NULL
+            FOR UPDATE SKIP LOCKED
+        ), expired AS (
+            UPDATE posts p SET status = 'expired'
+            FROM overdue o
+            WHERE p.id = o.id
+            RETURNING p.id
+        )
+        INSERT INTO post_status_history (post_id, from_status, to_status, changed_by, reason)
+        SELECT id, 'active', 'expired', NULL, 'Deadline passed' FROM expired
+        "#
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Publishes scheduled posts whose time has come, returns the ones that went live.
+/// They are listed as new from now on, like posts published by hand.
+pub async fn publish_scheduled_posts(conn: &mut PgConnection) -> Result<Vec<Uuid>> {
+    let published = sqlx::query_scalar!(
+        r#"
+        WITH due AS (
+            SELECT id FROM posts
+            WHERE status = 'scheduled' AND publish_at <= NOW() AND deleted_at IS NULL
+            FOR UPDATE SKIP LOCKED
+        ), published AS (
+            UPDATE posts p SET status = 'active', publish_at = NULL, published_at = NOW()
+            FROM due d
+            WHERE p.id = d.id
+            RETURNING p.id
+        )
+        INSERT INTO post_status_history (post_id, from_status, to_status, changed_by, reason)
+        SELECT id, 'scheduled', 'active', NULL, 'Publish time reached' FROM published
+        RETURNING post_id
+        "#
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(published)
+}
+
+/// Active posts with a deadline within `lead_seconds` whose owner was not reminded about it yet.
+/// The rows stay locked until the transaction ends.
+pub async fn get_due_deadline_reminders(
+    conn: &mut PgConnection,
+    lead_seconds: f64,
+) -> Result<Vec<DueReminder>> {
+    let reminders = sqlx::query_as!(
+        DueReminder,
+        r#"
+        SELECT id AS post_id, owner_id, title, deadline AS "deadline!"
+        FROM posts
+        WHERE status = 'active'
+          AND deleted_at IS NULL
+          AND deadline > NOW()
+          AND deadline <= NOW() + make_interval(secs => $1)
+          AND deadline_reminded_for IS DISTINCT FROM deadline
+        ORDER BY deadline
+        FOR UPDATE SKIP LOCKED
+        "#,
+        lead_seconds
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(reminders)
+}
+
+pub async fn mark_deadline_reminded(conn: &mut PgConnection, post_id: Uuid) -> Result<()> {
+    sqlx::query!(
+        "UPDATE posts SET deadline_reminded_for = deadline WHERE id = $1",
+        post_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Pushes notifications whose quiet hours ended, returns how many were released
+pub async fn release_deferred_notifications(conn: &mut PgConnection) -> Result<u64> {
+    let result = sqlx::query!(
+        "UPDATE notifications SET deliver_after = NULL WHERE deliver_after <= NOW()"
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Removes posts that were deleted more than `grace_days` ago for good, together with their
+/// attachments, revisions and history. Posts a chat thread or a review still refers to are kept,
+/// so both sides can keep resolving them. Returns the storage keys of the removed attachments.
+pub async fn purge_deleted_posts(conn: &mut PgConnection, grace_days: i64) -> Result<PurgedPosts> {
+    let post_ids = sqlx::query_scalar!(
+        r#"
+        SELECT p.id FROM posts p
+        WHERE p.deleted_at < NOW() - make_interval(days => $1::int)
+          AND NOT EXISTS (SELECT 1 FROM msg_threads t WHERE t.post_id = p.id)
+          AND NOT EXISTS (SELECT 1 FROM reviews r WHERE r.post_id = p.id)
+        FOR UPDATE SKIP LOCKED
+        "#,
+        grace_days as i32
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if post_ids.is_empty() {
+        return Ok(PurgedPosts {
+            count: 0,
+            storage_keys: Vec::new(),
+        });
+    }
+
+    let storage_keys = sqlx::query_scalar!(
+        "SELECT storage_key FROM post_attachments WHERE post_id = ANY($1)",
+        &post_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let result = sqlx::query!("DELETE FROM posts WHERE id = ANY($1)", &post_ids)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(PurgedPosts {
+        count: result.rows_affected(),
+        storage_keys,
+    })
+}
@@ -22,11 +22,13 @@ pub async fn create_thread(
         (user2, user1)
     };
 
+    // Deleted posts keep their threads but do not get new ones
     let thread = sqlx::query_as!(
         MessageThread,
         r#"
         INSERT INTO msg_threads (post_id, user_a, user_b)
-        VALUES ($1, $2, $3)
+        SELECT $1, $2, $3
+        WHERE EXISTS (SELECT 1 FROM posts WHERE id = $1 AND deleted_at IS NULL)
         ON CONFLICT (post_id, user_a, user_b) DO UPDATE SET
             updated_at = NOW()
         RETURNING id, post_id, user_a, user_b, created_at, updated_at
@@ -35,10 +37,10 @@ pub async fn create_thread(
         user_a,
         user_b
     )
-    .fetch_one(db)
+    .fetch_optional(db)
     .await?;
 
-    Ok(thread)
+    thread.ok_or_else(|| crate::error::AppError::BadRequest("Post not found".to_string()))
 }
 
 /// Get all threads for a user with post and other user info
@@ -51,6 +53,7 @@ pub async fn get_user_threads(db: &PgPool, user_id: Uuid) -> Result<Vec<ThreadIn
         other_user_name: Option<String>,
         last_message: Option<String>,
         last_message_at: Option<DateTime<Utc>>,
+        post_revised_at: Option<DateTime<Utc>>,
         created_at: DateTime<Utc>,
         updated_at: DateTime<Utc>,
     }
@@ -72,6 +75,7 @@ pub async fn get_user_threads(db: &PgPool, user_id: Uuid) -> Result<Vec<ThreadIn
             END as other_user_name,
             last_msg.content as last_message,
             last_msg.sent_at as last_message_at,
+            (SELECT MAX(r.created_at) FROM post_revisions r WHERE r.post_id = t.post_id) as post_revised_at,
             t.created_at,
             t.updated_at
         FROM msg_threads t
@@ -108,6 +112,8 @@ pub async fn get_user_threads(db: &PgPool, user_id: Uuid) -> Result<Vec<ThreadIn
                 other_user_name,
                 last_message: t.last_message,
                 last_message_at: t.last_message_at,
+                post_revised_at: t.post_revised_at,
+                post_changed: t.post_revised_at.is_some_and(|revised| revised > t.created_at),
                 created_at: t.created_at,
                 updated_at: t.updated_at,
             })
@@ -117,6 +123,22 @@ pub async fn get_user_threads(db: &PgPool, user_id: Uuid) -> Result<Vec<ThreadIn
     Ok(thread_infos)
 }
 
+/// Users taking part in any chat thread about the post, with the thread, as (thread_id, user_id)
+pub async fn get_post_thread_participants(db: &PgPool, post_id: Uuid) -> Result<Vec<(Uuid, Uuid)>> {
+    let participants = sqlx::query!(
+        r#"
+        SELECT id AS "thread_id!", user_a AS "user_id!" FROM msg_threads WHERE post_id = $1
+        UNION
+        SELECT id, user_b FROM msg_threads WHERE post_id = $1
+        "#,
+        post_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(participants.into_iter().map(|p| (p.thread_id, p.user_id)).collect())
+}
+
 /// Get a specific thread by ID if user has access
 pub async fn get_thread_by_id(db: &PgPool, thread_id: Uuid, user_id: Uuid) -> Result<Option<MessageThread>> {
     let thread = sqlx::query_as!(
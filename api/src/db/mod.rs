@@ -1,6 +1,16 @@
 // Functions for db queries
 
+pub mod attachments;
+pub mod availability;
+pub mod jobs;
+pub mod moderation;
 pub mod messages;
+pub mod notifications;
+pub mod post_events;
 pub mod posts;
 pub mod profile;
+pub mod proposals;
+pub mod saved_searches;
+pub mod school_locations;
+pub mod subjects;
 pub mod users;
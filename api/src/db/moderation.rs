@@ -0,0 +1,214 @@
+// Database functions for post reports and moderation
+
+use crate::{
+    error::Result,
+    server::moderation::{CaseReport, ModerationCase, PostReport, ReportReason, ReportResolution},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Post being reported or moderated
+pub struct ModeratedPost {
+    pub owner_id: Uuid,
+    pub title: String,
+    pub hidden_at: Option<DateTime<Utc>>,
+}
+
+/// Locks the post for the rest of the transaction
+pub async fn lock_post(conn: &mut PgConnection, post_id: Uuid) -> Result<Option<ModeratedPost>> {
+    let post = sqlx::query_as!(
+        ModeratedPost,
+        "SELECT owner_id, title, hidden_at FROM posts WHERE id = $1 FOR UPDATE",
+        post_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(post)
+}
+
+/// Files a report, returns None when the user already has an open report on the post
+pub async fn create_report(
+    conn: &mut PgConnection,
+    post_id: Uuid,
+    reporter_id: Uuid,
+    reason: ReportReason,
+    details: Option<&str>,
+) -> Result<Option<PostReport>> {
+    let report = sqlx::query_as!(
+        PostReport,
+        r#"
+        INSERT INTO post_reports (post_id, reporter_id, reason, details)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (post_id, reporter_id) WHERE resolved_at IS NULL DO NOTHING
+        RETURNING id, post_id, reporter_id, reason, details, created_at
+        "#,
+        post_id,
+        reporter_id,
+        reason.as_str(),
+        details
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(report)
+}
+
+pub async fn count_open_reports(conn: &mut PgConnection, post_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM post_reports WHERE post_id = $1 AND resolved_at IS NULL"#,
+        post_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn set_post_hidden(conn: &mut PgConnection, post_id: Uuid, hidden: bool) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE posts SET hidden_at = CASE WHEN $2 THEN COALESCE(hidden_at, NOW()) END
+        WHERE id = $1
+        "#,
+        post_id,
+        hidden
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether the post is hidden only because of the automatic threshold, not by a moderator
+pub async fn was_auto_hidden(conn: &mut PgConnection, post_id: Uuid) -> Result<bool> {
+    let last_action = sqlx::query_scalar!(
+        r#"
+        SELECT action FROM moderation_actions
+        WHERE post_id = $1 AND action IN ('auto_hide', 'hide_post', 'suspend_user')
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        post_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(last_action.as_deref() == Some("auto_hide"))
+}
+
+/// Closes every open report of the post, returns who filed them
+pub async fn resolve_open_reports(
+    conn: &mut PgConnection,
+    post_id: Uuid,
+    moderator_id: Uuid,
+    resolution: ReportResolution,
+) -> Result<Vec<Uuid>> {
+    let reporter_ids = sqlx::query_scalar!(
+        r#"
+        UPDATE post_reports SET resolved_at = NOW(), resolved_by = $2, resolution = $3
+        WHERE post_id = $1 AND resolved_at IS NULL
+        RETURNING reporter_id
+        "#,
+        post_id,
+        moderator_id,
+        resolution.as_str()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(reporter_ids)
+}
+
+/// Suspends the user and ends their sessions, an existing longer suspension is kept.
+/// Returns when the suspension ends.
+pub async fn suspend_user(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    until: DateTime<Utc>,
+) -> Result<DateTime<Utc>> {
+    let suspended_until = sqlx::query_scalar!(
+        r#"
+        UPDATE users SET
+            suspended_until = GREATEST(suspended_until, $2),
+            token_ver = gen_random_uuid()
+        WHERE id = $1
+        RETURNING suspended_until AS "suspended_until!"
+        "#,
+        user_id,
+        until
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(suspended_until)
+}
+
+/// Adds an entry to the moderation log, `moderator_id` is None for automatic actions
+#[allow(clippy::too_many_arguments)]
+pub async fn record_action(
+    conn: &mut PgConnection,
+    post_id: Uuid,
+    target_user_id: Uuid,
+    moderator_id: Option<Uuid>,
+    action: &str,
+    note: Option<&str>,
+    suspended_until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO moderation_actions (post_id, target_user_id, moderator_id, action, note, suspended_until)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        post_id,
+        target_user_id,
+        moderator_id,
+        action,
+        note,
+        suspended_until
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Posts with open reports, the most reported first and then the longest waiting
+pub async fn get_moderation_queue(db: &PgPool, limit: i64, offset: i64) -> Result<Vec<ModerationCase>> {
+    let cases = sqlx::query_as!(
+        ModerationCase,
+        r#"
+        SELECT p.id AS post_id, p.title AS post_title, p.owner_id, u.username AS owner_username,
+               p.hidden_at,
+               COUNT(*) AS "report_count!",
+               MIN(r.created_at) AS "first_reported_at!",
+               MAX(r.created_at) AS "last_reported_at!",
+               jsonb_agg(
+                   jsonb_build_object(
+                       'id', r.id,
+                       'reporter_id', r.reporter_id,
+                       'reporter_username', reporter.username,
+                       'reason', r.reason,
+                       'details', r.details,
+                       'created_at', r.created_at
+                   )
+                   ORDER BY r.created_at
+               ) AS "reports!: sqlx::types::Json<Vec<CaseReport>>"
+        FROM post_reports r
+        JOIN posts p ON p.id = r.post_id
+        JOIN users u ON u.id = p.owner_id
+        LEFT JOIN users reporter ON reporter.id = r.reporter_id
+        WHERE r.resolved_at IS NULL
+        GROUP BY p.id, u.username
+        ORDER BY COUNT(*) DESC, MIN(r.created_at), p.id
+        LIMIT $1 OFFSET $2
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(cases)
+}
@@ -0,0 +1,265 @@
+// Database functions for notification preferences
+
+use crate::{
+    error::Result,
+    server::notifications::{
+        Notification, NotificationChannel, NotificationEvent, NotificationPreferences, QuietHours,
+    },
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Everything [`crate::server::notifications::should_notify`] needs, read in one query
+pub struct DeliveryState {
+    /// Stored preference, `None` when the user kept the default
+    pub enabled: Option<bool>,
+    pub thread_muted: bool,
+    /// End of the quiet hours the user is in right now
+    pub quiet_until: Option<DateTime<Utc>>,
+}
+
+pub async fn get_delivery_state(
+    db: &PgPool,
+    user_id: Uuid,
+    event: NotificationEvent,
+    channel: NotificationChannel,
+    thread_id: Option<Uuid>,
+) -> Result<DeliveryState> {
+    let state = sqlx::query_as!(
+        DeliveryState,
+        r#"
+        SELECT
+            (
+                SELECT enabled FROM notification_preferences
+                WHERE user_id = $1 AND event_type = $2 AND channel = $3
+            ) AS enabled,
+            EXISTS (
+                SELECT 1 FROM muted_threads WHERE user_id = $1 AND thread_id = $4
+            ) AS "thread_muted!",
+            (
+                -- The next time the end comes around in the user's time zone
+                SELECT (t.local_now::date + s.quiet_hours_end
+                        + CASE WHEN t.local_now::time < s.quiet_hours_end
+                               THEN INTERVAL '0 days' ELSE INTERVAL '1 day' END) AT TIME ZONE s.time_zone
+                FROM notification_settings s
+                CROSS JOIN LATERAL (SELECT NOW() AT TIME ZONE s.time_zone AS local_now) t
+                WHERE s.user_id = $1
+                  AND s.quiet_hours_start IS NOT NULL
+                  AND CASE
+                      WHEN s.quiet_hours_start < s.quiet_hours_end
+                          THEN t.local_now::time >= s.quiet_hours_start AND t.local_now::time < s.quiet_hours_end
+                      ELSE t.local_now::time >= s.quiet_hours_start OR t.local_now::time < s.quiet_hours_end
+                  END
+            ) AS quiet_until
+        "#,
+        user_id,
+        event.as_str(),
+        channel.as_str(),
+        thread_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(state)
+}
+
+/// Returns the user's preferences with defaults filled in for anything not stored
+pub async fn get_preferences(db: &PgPool, user_id: Uuid) -> Result<NotificationPreferences> {
+    let mut preferences = NotificationPreferences::default();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT event_type, channel, enabled
+        FROM notification_preferences
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    for row in rows {
+        if let (Some(event), Some(channel)) = (
+            NotificationEvent::parse(&row.event_type),
+            NotificationChannel::parse(&row.channel),
+        ) {
+            preferences.event_mut(event).set(channel, row.enabled);
+        }
+    }
+
+    let settings = sqlx::query!(
+        r#"
+        SELECT quiet_hours_start, quiet_hours_end, time_zone
+        FROM notification_settings
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    if let Some(settings) = settings
+        && let (Some(start), Some(end)) = (settings.quiet_hours_start, settings.quiet_hours_end)
+    {
+        preferences.quiet_hours = Some(QuietHours {
+            start,
+            end,
+            time_zone: settings.time_zone,
+        });
+    }
+
+    preferences.muted_thread_ids = sqlx::query_scalar!(
+        "SELECT thread_id FROM muted_threads WHERE user_id = $1 ORDER BY created_at",
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(preferences)
+}
+
+/// Replaces all of the user's preferences in a single transaction
+pub async fn set_preferences(
+    db: &PgPool,
+    user_id: Uuid,
+    preferences: &NotificationPreferences,
+) -> Result<()> {
+    let mut tx = db.begin().await?;
+
+    for event in NotificationEvent::ALL {
+        for channel in NotificationChannel::ALL {
+            sqlx::query!(
+                r#"
+                INSERT INTO notification_preferences (user_id, event_type, channel, enabled)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, event_type, channel) DO UPDATE SET
+                    enabled = EXCLUDED.enabled,
+                    updated_at = NOW()
+                "#,
+                user_id,
+                event.as_str(),
+                channel.as_str(),
+                preferences.event(event).get(channel)
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let quiet_hours = preferences.quiet_hours.as_ref();
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_settings (user_id, quiet_hours_start, quiet_hours_end, time_zone)
+        VALUES ($1, $2, $3, COALESCE($4, 'Europe/Warsaw'))
+        ON CONFLICT (user_id) DO UPDATE SET
+            quiet_hours_start = EXCLUDED.quiet_hours_start,
+            quiet_hours_end = EXCLUDED.quiet_hours_end,
+            time_zone = EXCLUDED.time_zone,
+            updated_at = NOW()
+        "#,
+        user_id,
+        quiet_hours.map(|q| q.start),
+        quiet_hours.map(|q| q.end),
+        quiet_hours.map(|q| q.time_zone.clone())
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM muted_threads WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO muted_threads (user_id, thread_id)
+        SELECT $1, t.id FROM msg_threads t
+        WHERE t.id = ANY($2) AND (t.user_a = $1 OR t.user_b = $1)
+        "#,
+        user_id,
+        &preferences.muted_thread_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Counts how many of the given threads the user takes part in
+pub async fn count_user_threads(db: &PgPool, user_id: Uuid, thread_ids: &[Uuid]) -> Result<i64> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM msg_threads
+        WHERE id = ANY($2) AND (user_a = $1 OR user_b = $1)
+        "#,
+        user_id,
+        thread_ids
+    )
+    .fetch_one(db)
+    .await?)
+}
+
+/// Stores a notification, it is pushed to the user right away unless `deliver_after` is set
+pub async fn create_notification(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    event: NotificationEvent,
+    post_id: Option<Uuid>,
+    message: &str,
+    deliver_after: Option<DateTime<Utc>>,
+) -> Result<Uuid> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        INSERT INTO notifications (user_id, event_type, post_id, message, deliver_after)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        user_id,
+        event.as_str(),
+        post_id,
+        message,
+        deliver_after
+    )
+    .fetch_one(conn)
+    .await?)
+}
+
+/// Most recent notifications of the user, optionally only unread ones
+pub async fn get_notifications(
+    db: &PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+) -> Result<Vec<Notification>> {
+    let notifications = sqlx::query_as!(
+        Notification,
+        r#"
+        SELECT id, user_id, event_type, post_id, message, created_at, read_at
+        FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+        "#,
+        user_id,
+        unread_only,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(notifications)
+}
+
+/// Marks all of the user's notifications as read, returns how many were unread
+pub async fn mark_notifications_read(db: &PgPool, user_id: Uuid) -> Result<u64> {
+    let result = sqlx::query!(
+        "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,134 @@
+// Database functions for post analytics events
+
+use crate::{
+    error::Result,
+    server::analytics::{DailyViews, PostAnalyticsTotals, PostEvent},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// Records counted views, entry i of every slice describes one view.
+/// Views of posts deleted for good in the meantime are skipped.
+pub async fn add_view_events(
+    db: impl PgExecutor<'_>,
+    post_ids: &[Uuid],
+    user_ids: &[Option<Uuid>],
+    visitor_hashes: &[Option<i64>],
+    viewed_at: &[DateTime<Utc>],
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO post_events (post_id, event, user_id, visitor_hash, created_at)
+        SELECT v.post_id, $1, v.user_id, v.visitor_hash, v.viewed_at
+        FROM UNNEST($2::uuid[], $3::uuid[], $4::bigint[], $5::timestamptz[])
+            AS v(post_id, user_id, visitor_hash, viewed_at)
+        WHERE EXISTS (SELECT 1 FROM posts p WHERE p.id = v.post_id)
+        "#,
+        PostEvent::View.as_str(),
+        post_ids,
+        user_ids as &[Option<Uuid>],
+        visitor_hashes as &[Option<i64>],
+        viewed_at
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Records that a user opened a thread about a post, reopening the same thread records nothing
+pub async fn add_thread_opened(db: &PgPool, post_id: Uuid, thread_id: Uuid, user_id: Uuid) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO post_events (post_id, event, user_id, thread_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (thread_id) WHERE event = 'thread_opened' DO NOTHING
+        "#,
+        post_id,
+        PostEvent::ThreadOpened.as_str(),
+        user_id,
+        thread_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// All time totals of a post, activity of its owner is left out
+pub async fn get_post_analytics_totals(db: &PgPool, post_id: Uuid) -> Result<PostAnalyticsTotals> {
+    let totals = sqlx::query_as!(
+        PostAnalyticsTotals,
+        r#"
+        WITH post AS (
+            SELECT id, owner_id FROM posts WHERE id = $1
+        ),
+        views AS (
+            SELECT e.user_id, e.visitor_hash FROM post_events e
+            WHERE e.post_id = $1 AND e.event = 'view'
+        ),
+        engaged AS (
+            SELECT e.user_id FROM post_events e
+            WHERE e.post_id = $1 AND e.event = 'thread_opened'
+            UNION
+            SELECT pp.applicant_id FROM post_proposals pp WHERE pp.post_id = $1
+        )
+        SELECT
+            (SELECT COUNT(*) FROM views) AS "views!",
+            (
+                SELECT COUNT(DISTINCT COALESCE(user_id::text, 'anonymous:' || visitor_hash::text))
+                FROM views
+            ) AS "unique_viewers!",
+            (
+                SELECT COUNT(*) FROM post_events e
+                WHERE e.post_id = $1 AND e.event = 'thread_opened'
+            ) AS "threads_opened!",
+            (SELECT COUNT(*) FROM post_proposals pp WHERE pp.post_id = $1) AS "proposals_received!",
+            (
+                SELECT COUNT(*) FROM engaged
+                WHERE engaged.user_id IS DISTINCT FROM (SELECT owner_id FROM post)
+                  AND engaged.user_id IS NOT NULL
+            ) AS "engaged_users!"
+        "#,
+        post_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(totals)
+}
+
+/// Views per day (UTC) over the last `days` days including today, days without views are zero
+pub async fn get_daily_views(db: &PgPool, post_id: Uuid, days: i64) -> Result<Vec<DailyViews>> {
+    let views = sqlx::query_as!(
+        DailyViews,
+        r#"
+        WITH days AS (
+            SELECT day::date AS date
+            FROM generate_series(
+                (NOW() AT TIME ZONE 'UTC')::date - ($2::int - 1),
+                (NOW() AT TIME ZONE 'UTC')::date,
+                INTERVAL '1 day'
+            ) AS day
+        )
+        SELECT d.date AS "date!",
+               COUNT(e.id) AS "views!",
+               COUNT(DISTINCT COALESCE(e.user_id::text, 'anonymous:' || e.visitor_hash::text)) AS "unique_viewers!"
+        FROM days d
+        LEFT JOIN post_events e
+            ON e.post_id = $1
+           AND e.event = 'view'
+           AND e.created_at >= d.date::timestamp AT TIME ZONE 'UTC'
+           AND e.created_at < (d.date + 1)::timestamp AT TIME ZONE 'UTC'
+        GROUP BY d.date
+        ORDER BY d.date
+        "#,
+        post_id,
+        days as i32
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(views)
+}
@@ -1,135 +1,54 @@
 // Functions for interacting with the posts table
 
-use crate::{error::Result, server::post::Post};
-use sqlx::PgPool;
+use crate::{
+    db,
+    error::{AppError, Result},
+    server::{
+        feed::CalendarDeadline,
+        pagination::Cursor,
+        post::{
+            Post, PostContent, PostFieldChange, PostFilters, PostMeeting, PostPricing, PostRevision,
+            PostSearchMatch, PostStatus, PostStatusChange,
+        },
+    },
+};
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use uuid::Uuid;
 
-pub async fn get_posts(
+pub async fn get_post_by_id(
     db: &PgPool,
-    page: Option<i32>,
-    per_page: Option<i32>,
-) -> Result<Vec<Post>> {
-    let page = page.unwrap_or(0);
-    let per_page = per_page.unwrap_or(10).min(100); // Cap at 100 posts per page
-    let offset = page * per_page;
-
-    // First get posts
-    let posts = sqlx::query!(
-        r#"
-        SELECT p.id, p.title, p.description, p.type, p.subject, p.price, p.deadline, p.urgent,
-               p.status, p.created_at, p.updated_at, p.owner_id, p.view_count, p.response_count,
-               p.location, p.preferred_contact_method, p.academic_level, p.difficulty
-        FROM posts p
-        ORDER BY p.created_at DESC
-        LIMIT $1 OFFSET $2
-        "#,
-        per_page as i64,
-        offset as i64
-    )
-        .fetch_all(db)
-        .await?;
-
-    // Get owner information for each post
-    let mut full_posts = Vec::new();
-    for post in posts {
-        let owner_info = sqlx::query!(
-            "SELECT username, email, avatar FROM users WHERE id = $1",
-            post.owner_id
-        )
-        .fetch_one(db)
-        .await?;
-
-        let full_post = Post {
-            id: post.id,
-            title: post.title,
-            description: post.description,
-            r#type: post.r#type,
-            subject: post.subject,
-            price: post.price,
-            deadline: post.deadline,
-            urgent: post.urgent,
-            status: post.status,
-            created_at: post.created_at,
-            updated_at: post.updated_at,
-            owner_id: post.owner_id,
-            owner_name: owner_info.username.clone(),
-            owner_username: owner_info.username,
-            owner_email: owner_info.email,
-            owner_avatar: owner_info.avatar,
-            owner_rating: rust_decimal::Decimal::new(0, 0), // Default rating
-            view_count: post.view_count,
-            response_count: post.response_count,
-            location: post.location,
-            preferred_contact_method: post.preferred_contact_method,
-            academic_level: post.academic_level,
-            difficulty: post.difficulty,
-        };
-        full_posts.push(full_post);
-    }
-
-    Ok(full_posts)
-}
-
-pub async fn get_post_by_id(db: &PgPool, post_id: Uuid) -> Result<Option<Post>> {
-    let post = sqlx::query!(
+    post_id: Uuid,
+    viewer_id: Option<Uuid>,
+) -> Result<Option<Post>> {
+    // Columns come from the post_details view, whose nullability the query macros cannot see
+    let post = sqlx::query_as::<_, Post>(
         r#"
-        SELECT p.id, p.title, p.description, p.type, p.subject, p.price, p.deadline, p.urgent,
-               p.status, p.created_at, p.updated_at, p.owner_id, p.view_count, p.response_count,
-               p.location, p.preferred_contact_method, p.academic_level, p.difficulty
-        FROM posts p
-        WHERE p.id = $1
+        SELECT d.*,
+               CASE WHEN $2::uuid IS NULL THEN NULL ELSE EXISTS (
+                   SELECT 1 FROM saved_posts s WHERE s.post_id = d.id AND s.user_id = $2
+               ) END AS is_saved
+        FROM post_details d
+        WHERE d.id = $1
         "#,
-        post_id
     )
-        .fetch_optional(db)
-        .await?;
-
-    if let Some(post) = post {
-        let owner_info = sqlx::query!(
-            "SELECT username, email, avatar FROM users WHERE id = $1",
-            post.owner_id
-        )
-        .fetch_one(db)
-        .await?;
+    .bind(post_id)
+    .bind(viewer_id)
+    .fetch_optional(db)
+    .await?;
 
-        let full_post = Post {
-            id: post.id,
-            title: post.title,
-            description: post.description,
-            r#type: post.r#type,
-            subject: post.subject,
-            price: post.price,
-            deadline: post.deadline,
-            urgent: post.urgent,
-            status: post.status,
-            created_at: post.created_at,
-            updated_at: post.updated_at,
-            owner_id: post.owner_id,
-            owner_name: owner_info.username.clone(),
-            owner_username: owner_info.username,
-            owner_email: owner_info.email,
-            owner_avatar: owner_info.avatar,
-            owner_rating: rust_decimal::Decimal::new(0, 0), // Default rating
-            view_count: post.view_count,
-            response_count: post.response_count,
-            location: post.location,
-            preferred_contact_method: post.preferred_contact_method,
-            academic_level: post.academic_level,
-            difficulty: post.difficulty,
-        };
-        Ok(Some(full_post))
-    } else {
-        Ok(None)
-    }
+    Ok(post)
 }
 
+/// Inserts a post tagged with the given subjects, the first one is its primary subject
+#[allow(clippy::too_many_arguments)]
 pub async fn create_post(
     db: &PgPool,
     title: String,
     description: String,
     r#type: String,
-    subject: String,
-    price: rust_decimal::Decimal,
+    subject_ids: &[Uuid],
+    pricing: &PostPricing,
+    meeting: &PostMeeting,
     deadline: Option<chrono::DateTime<chrono::Utc>>,
     urgent: bool,
     owner_id: Uuid,
@@ -137,88 +56,64 @@ pub async fn create_post(
     preferred_contact_method: Option<String>,
     academic_level: Option<String>,
     difficulty: Option<String>,
+    status: PostStatus,
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
 ) -> Result<Post> {
-    let post_id = Uuid::new_v4();
-
-    // First, get owner information
-    let owner_info = sqlx::query!(
-        "SELECT username, email, avatar FROM users WHERE id = $1",
-        owner_id
-    )
-    .fetch_one(db)
-    .await?;
+    let mut tx = db.begin().await?;
 
-    let post = sqlx::query!(
+    let post_id = sqlx::query_scalar!(
         r#"
         INSERT INTO posts (
-            id, title, description, type, subject, price, deadline, urgent, 
-            owner_id, owner_name, owner_username, owner_email, owner_avatar,
-            location, preferred_contact_method, academic_level, difficulty
+            title, description, type, price, currency, pricing_type, deadline, urgent, owner_id,
+            location, preferred_contact_method, academic_level, difficulty, status, publish_at,
+            meeting_mode, school_location_id, published_at
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
-        RETURNING id, title, description, type, subject, price, deadline, urgent, 
-                  status, created_at, updated_at, owner_id, view_count, response_count,
-                  location, preferred_contact_method, academic_level, difficulty
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14::text, $15, $16, $17,
+            CASE WHEN $14::text IN ('draft', 'scheduled') THEN NULL ELSE NOW() END
+        )
+        RETURNING id
         "#,
-        post_id,
         title,
         description,
         r#type,
-        subject,
-        price,
+        pricing.price,
+        pricing.currency,
+        pricing.pricing_type.as_str(),
         deadline,
         urgent,
         owner_id,
-        owner_info.username, // owner_name
-        owner_info.username, // owner_username
-        owner_info.email,    // owner_email
-        owner_info.avatar,   // owner_avatar
         location,
         preferred_contact_method,
         academic_level,
-        difficulty
+        difficulty,
+        status.as_str(),
+        publish_at,
+        meeting.mode.as_str(),
+        meeting.school_location_id
     )
-        .fetch_one(db)
-        .await?;
+    .fetch_one(&mut *tx)
+    .await?;
 
-    // Create the full Post struct with owner information
-    let full_post = Post {
-        id: post.id,
-        title: post.title,
-        description: post.description,
-        r#type: post.r#type,
-        subject: post.subject,
-        price: post.price,
-        deadline: post.deadline,
-        urgent: post.urgent,
-        status: post.status,
-        created_at: post.created_at,
-        updated_at: post.updated_at,
-        owner_id: post.owner_id,
-        owner_name: owner_info.username.clone(),
-        owner_username: owner_info.username,
-        owner_email: owner_info.email,
-        owner_avatar: owner_info.avatar,
-        owner_rating: rust_decimal::Decimal::new(0, 0), // Default rating
-        view_count: post.view_count,
-        response_count: post.response_count,
-        location: post.location,
-        preferred_contact_method: post.preferred_contact_method,
-        academic_level: post.academic_level,
-        difficulty: post.difficulty,
-    };
+    db::subjects::set_post_subjects(&mut tx, post_id, subject_ids).await?;
 
-    Ok(full_post)
+    tx.commit().await?;
+
+    get_post_by_id(db, post_id, Some(owner_id))
+        .await?
+        .ok_or_else(|| AppError::InternalServerError("Post not found after creation".to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn update_post(
     db: &PgPool,
     post_id: Uuid,
     title: Option<String>,
     description: Option<String>,
     r#type: Option<String>,
-    subject: Option<String>,
-    price: Option<rust_decimal::Decimal>,
+    subject_ids: Option<Vec<Uuid>>,
+    pricing: Option<PostPricing>,
+    meeting: Option<PostMeeting>,
     deadline: Option<chrono::DateTime<chrono::Utc>>,
     urgent: Option<bool>,
     location: Option<String>,
@@ -226,19 +121,36 @@ pub async fn update_post(
     academic_level: Option<String>,
     difficulty: Option<String>,
     owner_id: Uuid,
-) -> Result<Option<Post>> {
-    // Check if the post exists and belongs to the user
-    let existing_post = sqlx::query!(
-        "SELECT id FROM posts WHERE id = $1 AND owner_id = $2",
+) -> Result<Option<(Post, Option<PostRevision>)>> {
+    let mut tx = db.begin().await?;
+
+    // Lock the post so concurrent edits get consecutive revision numbers
+    let old = sqlx::query_as!(
+        PostContent,
+        r#"
+        SELECT title, description, type AS "type",
+               ARRAY(
+                   SELECT s.name FROM post_subjects ps
+                   JOIN subjects s ON s.id = ps.subject_id
+                   WHERE ps.post_id = posts.id
+                   ORDER BY ps.position
+               ) AS "subjects!",
+               price, currency, pricing_type, deadline, urgent,
+               meeting_mode, school_location_tag(school_location_id)->>'path' AS school_location,
+               location, preferred_contact_method, academic_level, difficulty
+        FROM posts
+        WHERE id = $1 AND owner_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
         post_id,
         owner_id
     )
-        .fetch_optional(db)
-        .await?;
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    if existing_post.is_none() {
+    let Some(old) = old else {
         return Ok(None);
-    }
+    };
 
     // Update the post with provided fields
     sqlx::query!(
@@ -247,22 +159,24 @@ pub async fn update_post(
             title = COALESCE($1, title),
             description = COALESCE($2, description),
             type = COALESCE($3, type),
-            subject = COALESCE($4, subject),
-            price = COALESCE($5, price),
-            deadline = COALESCE($6, deadline),
-            urgent = COALESCE($7, urgent),
-            location = COALESCE($8, location),
-            preferred_contact_method = COALESCE($9, preferred_contact_method),
-            academic_level = COALESCE($10, academic_level),
-            difficulty = COALESCE($11, difficulty),
+            price = CASE WHEN $13 THEN $4 ELSE price END,
+            currency = COALESCE($14, currency),
+            pricing_type = COALESCE($15, pricing_type),
+            meeting_mode = COALESCE($16, meeting_mode),
+            school_location_id = CASE WHEN $16::text IS NULL THEN school_location_id ELSE $17 END,
+            deadline = COALESCE($5, deadline),
+            urgent = COALESCE($6, urgent),
+            location = COALESCE($7, location),
+            preferred_contact_method = COALESCE($8, preferred_contact_method),
+            academic_level = COALESCE($9, academic_level),
+            difficulty = COALESCE($10, difficulty),
             updated_at = NOW()
-        WHERE id = $12 AND owner_id = $13
+        WHERE id = $11 AND owner_id = $12
         "#,
         title,
         description,
         r#type,
-        subject,
-        price,
+        pricing.as_ref().and_then(|pricing| pricing.price),
         deadline,
         urgent,
         location,
@@ -270,18 +184,121 @@ pub async fn update_post(
         academic_level,
         difficulty,
         post_id,
+        owner_id,
+        pricing.is_some(),
+        pricing.as_ref().map(|pricing| pricing.currency.as_str()),
+        pricing.as_ref().map(|pricing| pricing.pricing_type.as_str()),
+        meeting.as_ref().map(|meeting| meeting.mode.as_str()),
+        meeting.as_ref().and_then(|meeting| meeting.school_location_id)
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if let Some(subject_ids) = subject_ids {
+        db::subjects::set_post_subjects(&mut tx, post_id, &subject_ids).await?;
+    }
+
+    let new = sqlx::query_as!(
+        PostContent,
+        r#"
+        SELECT title, description, type AS "type",
+               ARRAY(
+                   SELECT s.name FROM post_subjects ps
+                   JOIN subjects s ON s.id = ps.subject_id
+                   WHERE ps.post_id = posts.id
+                   ORDER BY ps.position
+               ) AS "subjects!",
+               price, currency, pricing_type, deadline, urgent,
+               meeting_mode, school_location_tag(school_location_id)->>'path' AS school_location,
+               location, preferred_contact_method, academic_level, difficulty
+        FROM posts
+        WHERE id = $1 AND owner_id = $2
+        "#,
+        post_id,
         owner_id
     )
-        .execute(db)
-        .await?;
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let changes = old.diff(&new);
+    let revision_id = if changes.is_empty() {
+        None
+    } else {
+        Some(
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO post_revisions (post_id, revision, editor_id, changes)
+                SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3
+                FROM post_revisions
+                WHERE post_id = $1
+                RETURNING id
+                "#,
+                post_id,
+                owner_id,
+                sqlx::types::Json(&changes) as _
+            )
+            .fetch_one(&mut *tx)
+            .await?,
+        )
+    };
 
-    // Return the updated post
-    Ok(get_post_by_id(db, post_id).await?)
+    tx.commit().await?;
+
+    let Some(post) = get_post_by_id(db, post_id, Some(owner_id)).await? else {
+        return Ok(None);
+    };
+
+    let revision = match revision_id {
+        Some(revision_id) => get_post_revision(db, revision_id).await?,
+        None => None,
+    };
+
+    Ok(Some((post, revision)))
+}
+
+pub async fn get_post_revision(db: &PgPool, revision_id: Uuid) -> Result<Option<PostRevision>> {
+    let revision = sqlx::query_as!(
+        PostRevision,
+        r#"
+        SELECT r.id, r.post_id, r.revision, r.editor_id, u.username AS "editor_username?",
+               r.changes AS "changes: sqlx::types::Json<Vec<PostFieldChange>>", r.created_at
+        FROM post_revisions r
+        LEFT JOIN users u ON u.id = r.editor_id
+        WHERE r.id = $1
+        "#,
+        revision_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(revision)
 }
 
+/// Every revision of a post, oldest first
+pub async fn get_post_revisions(db: &PgPool, post_id: Uuid) -> Result<Vec<PostRevision>> {
+    let revisions = sqlx::query_as!(
+        PostRevision,
+        r#"
+        SELECT r.id, r.post_id, r.revision, r.editor_id, u.username AS "editor_username?",
+               r.changes AS "changes: sqlx::types::Json<Vec<PostFieldChange>>", r.created_at
+        FROM post_revisions r
+        LEFT JOIN users u ON u.id = r.editor_id
+        WHERE r.post_id = $1
+        ORDER BY r.revision
+        "#,
+        post_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(revisions)
+}
+
+/// Moves a post to the bin. Its threads, reviews and attachments stay until the purge job removes it.
+/// Returns false when the post is not the owner's or already deleted.
 pub async fn delete_post(db: &PgPool, post_id: Uuid, owner_id: Uuid) -> Result<bool> {
     let result = sqlx::query!(
-        "DELETE FROM posts WHERE id = $1 AND owner_id = $2",
+        "UPDATE posts SET deleted_at = NOW() WHERE id = $1 AND owner_id = $2 AND deleted_at IS NULL",
         post_id,
         owner_id
     )
@@ -291,68 +308,435 @@ pub async fn delete_post(db: &PgPool, post_id: Uuid, owner_id: Uuid) -> Result<b
     Ok(result.rows_affected() > 0)
 }
 
+/// Takes a post out of the bin, returns false when it is not deleted or was deleted
+/// more than `grace_days` ago
+pub async fn restore_post(db: &PgPool, post_id: Uuid, owner_id: Uuid, grace_days: i64) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE posts SET deleted_at = NULL
+        WHERE id = $1 AND owner_id = $2
+          AND deleted_at > NOW() - make_interval(days => $3::int)
+        "#,
+        post_id,
+        owner_id,
+        grace_days as i32
+    )
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether the user took part in a post without owning it: chatted about it, reviewed it,
+/// was reviewed for it or was assigned to it
+pub async fn is_post_participant(db: &PgPool, post_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let participant = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM msg_threads t
+            WHERE t.post_id = $1 AND (t.user_a = $2 OR t.user_b = $2)
+        ) OR EXISTS (
+            SELECT 1 FROM reviews r
+            WHERE r.post_id = $1 AND (r.review_sender_id = $2 OR r.review_receiver_id = $2)
+        ) OR EXISTS (
+            SELECT 1 FROM posts p WHERE p.id = $1 AND p.assigned_helper_id = $2
+        ) AS "participant!"
+        "#,
+        post_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(participant)
+}
+
+/// Posts matching the filters. With a cursor only posts listed before it are returned,
+/// which is only meaningful for the newest first order.
 pub async fn get_posts_filtered(
     db: &PgPool,
-    page: i32,
-    per_page: i32,
-    owner_id: Option<Uuid>,
+    filters: &PostFilters,
+    cursor: Option<Cursor>,
+    offset: i64,
+    limit: i64,
+    viewer_id: Option<Uuid>,
 ) -> Result<Vec<Post>> {
-    let offset = page * per_page;
-
-    let posts = sqlx::query!(
+    let posts = sqlx::query_as::<_, Post>(
         r#"
-        SELECT p.id, p.title, p.description, p.type, p.subject, p.price, p.deadline, p.urgent,
-               p.status, p.created_at, p.updated_at, p.owner_id, p.view_count, p.response_count,
-               p.location, p.preferred_contact_method, p.academic_level, p.difficulty
+        SELECT d.*,
+               CASE WHEN $2::uuid IS NULL THEN NULL ELSE EXISTS (
+                   SELECT 1 FROM saved_posts s WHERE s.post_id = d.id AND s.user_id = $2
+               ) END AS is_saved
         FROM posts p
-        WHERE ($1::uuid IS NULL OR p.owner_id = $1)
-        ORDER BY p.created_at DESC
-        LIMIT $2 OFFSET $3
+        JOIN post_details d ON d.id = p.id
+        WHERE post_matches_filters(p, $1)
+          AND ($4::timestamptz IS NULL OR (COALESCE(p.published_at, p.created_at), p.id) < ($4, $5::uuid))
+        ORDER BY
+            CASE WHEN $3 = 'relevance' THEN ts_rank_cd(p.search_vector, post_search_query($1->>'search')) END DESC,
+            CASE WHEN $3 = 'deadline' THEN p.deadline END ASC NULLS LAST,
+            CASE WHEN $3 = 'price_asc' THEN p.price END ASC,
+            CASE WHEN $3 = 'price_desc' THEN p.price END DESC NULLS LAST,
+            CASE WHEN $3 = 'rating' THEN d.owner_rating END DESC,
+            COALESCE(p.published_at, p.created_at) DESC,
+            p.id DESC
+        LIMIT $6 OFFSET $7
         "#,
-        owner_id,
-        per_page as i64,
-        offset as i64
     )
+    .bind(sqlx::types::Json(filters))
+    .bind(viewer_id)
+    .bind(filters.sort.as_str())
+    .bind(cursor.map(|c| c.timestamp))
+    .bind(cursor.map(|c| c.id))
+    .bind(limit)
+    .bind(offset)
     .fetch_all(db)
     .await?;
 
-    // Get owner information for each post and convert to full Post structs
-    let mut full_posts = Vec::new();
-    for post in posts {
-        let owner_info = sqlx::query!(
-            "SELECT username, email, avatar FROM users WHERE id = $1",
-            post.owner_id
+    Ok(posts)
+}
+
+/// Moves a post between statuses and records the change in its history.
+/// Returns false when the post is not in `from` anymore, e.g. after a concurrent change.
+pub async fn change_post_status(
+    db: &PgPool,
+    post_id: Uuid,
+    from: PostStatus,
+    to: PostStatus,
+    changed_by: Option<Uuid>,
+    reason: Option<&str>,
+) -> Result<bool> {
+    let mut tx = db.begin().await?;
+
+    let changed = change_post_status_in(&mut tx, post_id, from, to, changed_by, reason).await?;
+    if changed {
+        tx.commit().await?;
+    }
+
+    Ok(changed)
+}
+
+/// Same as [`change_post_status`] as part of a larger transaction
+pub async fn change_post_status_in(
+    conn: &mut PgConnection,
+    post_id: Uuid,
+    from: PostStatus,
+    to: PostStatus,
+    changed_by: Option<Uuid>,
+    reason: Option<&str>,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE posts SET
+            status = $3::text,
+            -- Reopening drops the assigned helper
+            assigned_helper_id = CASE WHEN $3::text = 'active' THEN NULL ELSE assigned_helper_id END,
+            published_at = CASE
+                WHEN $2 IN ('draft', 'scheduled') AND $3::text = 'active' THEN NOW()
+                ELSE published_at
+            END,
+            publish_at = NULL,
+            updated_at = NOW()
+        WHERE id = $1 AND status = $2 AND deleted_at IS NULL
+        "#,
+        post_id,
+        from.as_str(),
+        to.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO post_status_history (post_id, from_status, to_status, changed_by, reason)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        post_id,
+        from.as_str(),
+        to.as_str(),
+        changed_by,
+        reason
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+/// Schedules a draft, or moves the publish time of a post that is already scheduled.
+/// Returns false when the post is not in `from` anymore.
+pub async fn schedule_post(
+    db: &PgPool,
+    post_id: Uuid,
+    from: PostStatus,
+    publish_at: chrono::DateTime<chrono::Utc>,
+    changed_by: Uuid,
+) -> Result<bool> {
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query!(
+        "UPDATE posts SET status = 'scheduled', publish_at = $3 WHERE id = $1 AND status = $2 AND deleted_at IS NULL",
+        post_id,
+        from.as_str(),
+        publish_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    // Moving the publish time is not a status change
+    if from != PostStatus::Scheduled {
+        sqlx::query!(
+            r#"
+            INSERT INTO post_status_history (post_id, from_status, to_status, changed_by)
+            VALUES ($1, $2, 'scheduled', $3)
+            "#,
+            post_id,
+            from.as_str(),
+            changed_by
         )
-        .fetch_one(db)
+        .execute(&mut *tx)
         .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Status changes of a post, oldest first
+pub async fn get_post_status_history(db: &PgPool, post_id: Uuid) -> Result<Vec<PostStatusChange>> {
+    let history = sqlx::query_as!(
+        PostStatusChange,
+        r#"
+        SELECT id, from_status, to_status, changed_by, reason, created_at
+        FROM post_status_history
+        WHERE post_id = $1
+        ORDER BY created_at, id
+        "#,
+        post_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(history)
+}
+
+/// Number of posts matching the filters, ignoring pagination
+pub async fn count_posts_filtered(db: impl PgExecutor<'_>, filters: &PostFilters) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM posts p WHERE post_matches_filters(p, $1)"#,
+        sqlx::types::Json(filters) as _
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(count)
+}
 
-        let full_post = Post {
-            id: post.id,
-            title: post.title,
-            description: post.description,
-            r#type: post.r#type,
-            subject: post.subject,
-            price: post.price,
-            deadline: post.deadline,
-            urgent: post.urgent,
-            status: post.status,
-            created_at: post.created_at,
-            updated_at: post.updated_at,
-            owner_id: post.owner_id,
-            owner_name: owner_info.username.clone(),
-            owner_username: owner_info.username,
-            owner_email: owner_info.email,
-            owner_avatar: owner_info.avatar,
-            owner_rating: rust_decimal::Decimal::new(0, 0), // Default rating
-            view_count: post.view_count,
-            response_count: post.response_count,
-            location: post.location,
-            preferred_contact_method: post.preferred_contact_method,
-            academic_level: post.academic_level,
-            difficulty: post.difficulty,
-        };
-        full_posts.push(full_post);
+/// Rank and highlighted title and description snippet for posts matching a search
+pub async fn get_search_matches(
+    db: &PgPool,
+    post_ids: &[Uuid],
+    search: &str,
+) -> Result<Vec<PostSearchMatch>> {
+    let matches = sqlx::query_as!(
+        PostSearchMatch,
+        r#"
+        SELECT p.id AS post_id,
+               ts_rank_cd(p.search_vector, post_search_query($2)) AS "rank!",
+               post_search_headline(p.title, $2, true) AS "title!",
+               post_search_headline(p.description, $2, false) AS "snippet!"
+        FROM posts p
+        WHERE p.id = ANY($1)
+        "#,
+        post_ids,
+        search
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(matches)
+}
+
+/// Adds counts[i] views to post_ids[i], posts deleted in the meantime are skipped
+pub async fn add_view_counts(db: impl PgExecutor<'_>, post_ids: &[Uuid], counts: &[i32]) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE posts p SET view_count = p.view_count + v.views
+        FROM UNNEST($1::uuid[], $2::int[]) AS v(post_id, views)
+        WHERE p.id = v.post_id
+        "#,
+        post_ids,
+        counts
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Bookmarks a post for the user, returns false if the post does not exist or is not public
+pub async fn save_post(db: &PgPool, user_id: Uuid, post_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO saved_posts (user_id, post_id)
+        SELECT $1, id FROM posts WHERE id = $2 AND is_post_public(posts)
+        ON CONFLICT (user_id, post_id) DO NOTHING
+        "#,
+        user_id,
+        post_id
+    )
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        return Ok(true);
     }
 
-    Ok(full_posts)
+    // Nothing inserted, either the post is missing or it was already saved
+    let exists = sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM posts WHERE id = $1 AND is_post_public(posts))",
+        post_id
+    )
+    .fetch_one(db)
+    .await?
+    .unwrap_or(false);
+
+    Ok(exists)
+}
+
+/// Removes a bookmark, returns false if the post was not saved
+pub async fn unsave_post(db: &PgPool, user_id: Uuid, post_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM saved_posts WHERE user_id = $1 AND post_id = $2",
+        user_id,
+        post_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Other active posts most similar to the given one, best match first.
+/// The ranking lives in the similar_posts database function.
+pub async fn get_similar_posts(
+    db: &PgPool,
+    post_id: Uuid,
+    limit: i64,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<Post>> {
+    let posts = sqlx::query_as::<_, Post>(
+        r#"
+        SELECT d.*,
+               CASE WHEN $3::uuid IS NULL THEN NULL ELSE EXISTS (
+                   SELECT 1 FROM saved_posts s WHERE s.post_id = d.id AND s.user_id = $3
+               ) END AS is_saved
+        FROM similar_posts($1, $2::int) sp
+        JOIN post_details d ON d.id = sp.post_id
+        ORDER BY sp.score DESC, d.id
+        "#,
+    )
+    .bind(post_id)
+    .bind(limit as i32)
+    .bind(viewer_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(posts)
+}
+
+/// Posts bookmarked by the user, most recently saved first.
+/// Posts that were unpublished, hidden or deleted since stay saved but are not listed.
+pub async fn get_saved_posts(
+    db: &PgPool,
+    user_id: Uuid,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<Post>> {
+    let posts = sqlx::query_as::<_, Post>(
+        r#"
+        SELECT d.*, true AS is_saved
+        FROM saved_posts s
+        JOIN posts p ON p.id = s.post_id
+        JOIN post_details d ON d.id = p.id
+        WHERE s.user_id = $1 AND is_post_public(p)
+        ORDER BY s.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok(posts)
+}
+
+/// Feed of posts created by users that the given user follows
+pub async fn get_followed_posts(
+    db: &PgPool,
+    user_id: Uuid,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<Post>> {
+    let posts = sqlx::query_as::<_, Post>(
+        r#"
+        SELECT d.*,
+               EXISTS (
+                   SELECT 1 FROM saved_posts s WHERE s.post_id = d.id AND s.user_id = $1
+               ) AS is_saved
+        FROM user_follows f
+        JOIN posts p ON p.owner_id = f.followee_id
+        JOIN post_details d ON d.id = p.id
+        WHERE f.follower_id = $1 AND is_post_public(p)
+        ORDER BY p.published_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok(posts)
+}
+
+/// Deadlines of posts the user owns or was assigned to, from `since` on, soonest first.
+/// Cancelled and deleted posts are left out.
+pub async fn get_calendar_deadlines(
+    db: &PgPool,
+    user_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<CalendarDeadline>> {
+    let deadlines = sqlx::query_as!(
+        CalendarDeadline,
+        r#"
+        SELECT p.id AS post_id, p.title, p.description, p.status,
+               p.deadline AS "deadline!",
+               p.owner_id = $1 AS "is_owner!",
+               p.updated_at
+        FROM posts p
+        WHERE (p.owner_id = $1 OR p.assigned_helper_id = $1)
+          AND p.deadline >= $2
+          AND p.status <> 'cancelled'
+          AND p.deleted_at IS NULL
+        ORDER BY p.deadline, p.id
+        "#,
+        user_id,
+        since
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(deadlines)
 }
@@ -0,0 +1,171 @@
+// Database functions for proposals on posts
+
+use crate::{
+    db,
+    error::Result,
+    server::{
+        post::PostStatus,
+        proposal::{Proposal, ProposalStatus},
+    },
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Creates a pending proposal, returns None when the user already has one on this post
+pub async fn create_proposal(
+    db: &PgPool,
+    post_id: Uuid,
+    applicant_id: Uuid,
+    message: &str,
+    proposed_price: Option<Decimal>,
+    proposed_deadline: Option<DateTime<Utc>>,
+) -> Result<Option<Proposal>> {
+    let proposal_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO post_proposals (post_id, applicant_id, message, proposed_price, proposed_deadline)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (post_id, applicant_id) WHERE status = 'pending' DO NOTHING
+        RETURNING id
+        "#,
+        post_id,
+        applicant_id,
+        message,
+        proposed_price,
+        proposed_deadline
+    )
+    .fetch_optional(db)
+    .await?;
+
+    match proposal_id {
+        Some(proposal_id) => get_proposal(db, proposal_id).await,
+        None => Ok(None),
+    }
+}
+
+pub async fn get_proposal(db: &PgPool, proposal_id: Uuid) -> Result<Option<Proposal>> {
+    let proposal = sqlx::query_as!(
+        Proposal,
+        r#"
+        SELECT pp.id, pp.post_id, pp.applicant_id, u.username AS applicant_username,
+               pp.message, pp.proposed_price, pp.proposed_deadline, pp.status,
+               pp.created_at, pp.updated_at
+        FROM post_proposals pp
+        JOIN users u ON u.id = pp.applicant_id
+        WHERE pp.id = $1
+        "#,
+        proposal_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(proposal)
+}
+
+/// Proposals on a post, oldest first. With an applicant only their own proposals are returned.
+pub async fn get_post_proposals(
+    db: &PgPool,
+    post_id: Uuid,
+    applicant_id: Option<Uuid>,
+) -> Result<Vec<Proposal>> {
+    let proposals = sqlx::query_as!(
+        Proposal,
+        r#"
+        SELECT pp.id, pp.post_id, pp.applicant_id, u.username AS applicant_username,
+               pp.message, pp.proposed_price, pp.proposed_deadline, pp.status,
+               pp.created_at, pp.updated_at
+        FROM post_proposals pp
+        JOIN users u ON u.id = pp.applicant_id
+        WHERE pp.post_id = $1
+          AND ($2::uuid IS NULL OR pp.applicant_id = $2)
+        ORDER BY pp.created_at, pp.id
+        "#,
+        post_id,
+        applicant_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(proposals)
+}
+
+/// Moves a pending proposal to a final status, returns false when it is not pending anymore
+pub async fn close_proposal(db: &PgPool, proposal_id: Uuid, status: ProposalStatus) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE post_proposals SET status = $2, updated_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        "#,
+        proposal_id,
+        status.as_str()
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Accepts a pending proposal in one transaction: the post moves to in progress,
+/// the applicant becomes its helper and all other pending proposals are rejected.
+/// Returns false when the proposal is not pending or the post is not active anymore.
+pub async fn accept_proposal(
+    db: &PgPool,
+    post_id: Uuid,
+    proposal_id: Uuid,
+    owner_id: Uuid,
+) -> Result<bool> {
+    let mut tx = db.begin().await?;
+
+    let applicant_id = sqlx::query_scalar!(
+        r#"
+        UPDATE post_proposals SET status = 'accepted', updated_at = NOW()
+        WHERE id = $1 AND post_id = $2 AND status = 'pending'
+        RETURNING applicant_id
+        "#,
+        proposal_id,
+        post_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(applicant_id) = applicant_id else {
+        return Ok(false);
+    };
+
+    let moved = db::posts::change_post_status_in(
+        &mut tx,
+        post_id,
+        PostStatus::Active,
+        PostStatus::InProgress,
+        Some(owner_id),
+        Some("Proposal accepted"),
+    )
+    .await?;
+
+    if !moved {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE posts SET assigned_helper_id = $2 WHERE id = $1",
+        post_id,
+        applicant_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE post_proposals SET status = 'rejected', updated_at = NOW()
+        WHERE post_id = $1 AND status = 'pending'
+        "#,
+        post_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
@@ -0,0 +1,126 @@
+// Database functions for saved post searches
+
+use crate::{
+    error::Result,
+    server::{post::PostFilters, saved_search::SavedSearch},
+};
+use serde_json::{Map, Value};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Saved search a new post matched, with the post's title for the alert
+pub struct MatchingSearch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub post_title: String,
+}
+
+/// Stores a search unless the user already has `max` of them. The user's row is locked
+/// while counting, so concurrent requests cannot both pass the limit.
+pub async fn create_saved_search(
+    db: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    filters: &Map<String, Value>,
+    post_filters: &PostFilters,
+    alerts: bool,
+    max: i64,
+) -> Result<Option<SavedSearch>> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let search = sqlx::query_as!(
+        SavedSearch,
+        r#"
+        INSERT INTO saved_searches (user_id, name, filters, post_filters, alerts)
+        SELECT $1, $2, $3, $4, $5
+        WHERE (SELECT COUNT(*) FROM saved_searches WHERE user_id = $1) < $6
+        RETURNING id, user_id, name,
+                  filters AS "filters: sqlx::types::Json<Map<String, Value>>",
+                  alerts, last_alerted_at, created_at
+        "#,
+        user_id,
+        name,
+        sqlx::types::Json(filters) as _,
+        sqlx::types::Json(post_filters) as _,
+        alerts,
+        max
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(search)
+}
+
+/// The user's saved searches, oldest first
+pub async fn get_saved_searches(db: &PgPool, user_id: Uuid) -> Result<Vec<SavedSearch>> {
+    let searches = sqlx::query_as!(
+        SavedSearch,
+        r#"
+        SELECT id, user_id, name,
+               filters AS "filters: sqlx::types::Json<Map<String, Value>>",
+               alerts, last_alerted_at, created_at
+        FROM saved_searches
+        WHERE user_id = $1
+        ORDER BY created_at, id
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(searches)
+}
+
+/// Returns false when the user has no such search
+pub async fn delete_saved_search(db: &PgPool, search_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM saved_searches WHERE id = $1 AND user_id = $2",
+        search_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// First saved search, by creation, of every user with alerts on that the post matches.
+/// The author's own searches are left out.
+pub async fn get_matching_searches(conn: &mut PgConnection, post_id: Uuid) -> Result<Vec<MatchingSearch>> {
+    let searches = sqlx::query_as!(
+        MatchingSearch,
+        r#"
+        SELECT DISTINCT ON (s.user_id) s.id, s.user_id, s.name, p.title AS post_title
+        FROM posts p
+        JOIN saved_searches s
+            ON s.alerts
+           AND s.user_id <> p.owner_id
+           AND post_matches_filters(p, s.post_filters)
+        WHERE p.id = $1
+        ORDER BY s.user_id, s.created_at, s.id
+        "#,
+        post_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(searches)
+}
+
+pub async fn mark_alerted(conn: &mut PgConnection, search_ids: &[Uuid]) -> Result<()> {
+    sqlx::query!(
+        "UPDATE saved_searches SET last_alerted_at = NOW() WHERE id = ANY($1)",
+        search_ids
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Database functions for school defined locations
+
+use crate::{error::Result, server::school_location::SchoolLocation};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// All locations, or a single one, with counts of active posts
+pub async fn get_school_locations(db: &PgPool, location_id: Option<Uuid>) -> Result<Vec<SchoolLocation>> {
+    let locations = sqlx::query_as!(
+        SchoolLocation,
+        r#"
+        SELECT l.id, l.slug, l.name, l.kind, l.parent_id,
+               (
+                   SELECT COUNT(*) FROM posts p
+                   WHERE p.school_location_id IN (SELECT school_location_descendants(l.id))
+                     AND p.status = 'active' AND is_post_public(p)
+               ) AS "post_count!",
+               l.created_at
+        FROM school_locations l
+        WHERE $1::uuid IS NULL OR l.id = $1
+        ORDER BY l.name
+        "#,
+        location_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(locations)
+}
+
+pub async fn get_school_location(db: &PgPool, location_id: Uuid) -> Result<Option<SchoolLocation>> {
+    Ok(get_school_locations(db, Some(location_id)).await?.into_iter().next())
+}
+
+/// Location with the given slug or id
+pub async fn resolve_school_location(db: &PgPool, input: &str) -> Result<Option<Uuid>> {
+    let location_id = sqlx::query_scalar!(
+        "SELECT id FROM school_locations WHERE slug = lower(trim($1)) OR id::text = lower(trim($1))",
+        input
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(location_id)
+}
+
+/// Adds a location, returns None when its parent already has a location with that name
+pub async fn create_school_location(
+    db: &PgPool,
+    name: &str,
+    kind: &str,
+    parent_id: Option<Uuid>,
+) -> Result<Option<Uuid>> {
+    let location_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO school_locations (slug, name, kind, parent_id)
+        VALUES (next_school_location_slug($1, $3), $1, $2, $3)
+        ON CONFLICT DO NOTHING
+        RETURNING id
+        "#,
+        name,
+        kind,
+        parent_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(location_id)
+}
+
+/// Renames a location, the slug does not change so saved filters keep working.
+/// Returns false when a sibling already has the new name.
+pub async fn rename_school_location(db: &PgPool, location_id: Uuid, name: &str) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE school_locations SET name = $2
+        WHERE id = $1 AND NOT EXISTS (
+            SELECT 1 FROM school_locations s
+            JOIN school_locations renamed ON renamed.id = $1
+            WHERE s.id <> renamed.id
+              AND s.parent_id IS NOT DISTINCT FROM renamed.parent_id
+              AND lower(s.name) = lower($2)
+        )
+        "#,
+        location_id,
+        name
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,252 @@
+// Database functions for the subject taxonomy
+
+use crate::{error::Result, server::subject::Subject};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Outcome of merging one subject into another
+pub enum MergeSubjects {
+    Merged,
+    /// The target is the source itself or lies below it
+    IntoOwnSubtree,
+    /// A child of the source has the same name as a child of the target, carries its name
+    ChildNameConflict(String),
+}
+
+/// The whole taxonomy, or a single subject, with counts of active posts
+pub async fn get_subjects(db: &PgPool, subject_id: Option<Uuid>) -> Result<Vec<Subject>> {
+    let subjects = sqlx::query_as!(
+        Subject,
+        r#"
+        WITH RECURSIVE tree AS (
+            SELECT id AS root, id FROM subjects
+            UNION ALL
+            SELECT t.root, s.id FROM subjects s JOIN tree t ON s.parent_id = t.id
+        ),
+        active_tags AS (
+            SELECT ps.subject_id, ps.post_id
+            FROM post_subjects ps
+            JOIN posts p ON p.id = ps.post_id
+            WHERE p.status = 'active' AND is_post_public(p)
+        )
+        SELECT s.id, s.slug, s.name, s.parent_id,
+               ARRAY(SELECT a.alias FROM subject_aliases a WHERE a.subject_id = s.id ORDER BY a.alias) AS "aliases!",
+               (SELECT COUNT(*) FROM active_tags t WHERE t.subject_id = s.id) AS "post_count!",
+               (
+                   SELECT COUNT(DISTINCT t.post_id)
+                   FROM tree
+                   JOIN active_tags t ON t.subject_id = tree.id
+                   WHERE tree.root = s.id
+               ) AS "total_post_count!",
+               s.created_at
+        FROM subjects s
+        WHERE $1::uuid IS NULL OR s.id = $1
+        ORDER BY s.name
+        "#,
+        subject_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(subjects)
+}
+
+pub async fn get_subject(db: &PgPool, subject_id: Uuid) -> Result<Option<Subject>> {
+    Ok(get_subjects(db, Some(subject_id)).await?.into_iter().next())
+}
+
+/// Taxonomy ids for free-text input (slugs, names or aliases), None where nothing matches
+pub async fn resolve_subjects(db: &PgPool, inputs: &[String]) -> Result<Vec<Option<Uuid>>> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT resolve_subject(input) AS id
+        FROM unnest($1::text[]) WITH ORDINALITY AS t(input, n)
+        ORDER BY n
+        "#,
+        inputs
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Replaces the tags of a post, the first subject becomes its primary one
+pub async fn set_post_subjects(conn: &mut PgConnection, post_id: Uuid, subject_ids: &[Uuid]) -> Result<()> {
+    sqlx::query!("DELETE FROM post_subjects WHERE post_id = $1", post_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO post_subjects (post_id, subject_id, position)
+        SELECT $1, subject_id, (n - 1)::smallint
+        FROM unnest($2::uuid[]) WITH ORDINALITY AS t(subject_id, n)
+        "#,
+        post_id,
+        subject_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Adds a subject, returns None when its parent already has a child with that name
+pub async fn create_subject(db: &PgPool, name: &str, parent_id: Option<Uuid>) -> Result<Option<Uuid>> {
+    let subject_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO subjects (slug, name, parent_id)
+        VALUES (next_subject_slug($1), $1, $2)
+        ON CONFLICT DO NOTHING
+        RETURNING id
+        "#,
+        name,
+        parent_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(subject_id)
+}
+
+/// Renames a subject keeping the old name as an alias, the slug does not change.
+/// Returns false when a sibling already has the new name.
+pub async fn rename_subject(db: &PgPool, subject_id: Uuid, name: &str) -> Result<bool> {
+    let mut tx = db.begin().await?;
+
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM subjects s
+            JOIN subjects renamed ON renamed.id = $1
+            WHERE s.id <> renamed.id
+              AND s.parent_id IS NOT DISTINCT FROM renamed.parent_id
+              AND lower(s.name) = lower($2)
+        ) AS "taken!"
+        "#,
+        subject_id,
+        name
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if taken {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO subject_aliases (alias, subject_id)
+        SELECT normalize_subject(name), id FROM subjects
+        WHERE id = $1 AND normalize_subject(name) <> normalize_subject($2)
+        ON CONFLICT DO NOTHING
+        "#,
+        subject_id,
+        name
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("UPDATE subjects SET name = $2 WHERE id = $1", subject_id, name)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Moves everything tagged with `source` to `target` and removes `source`.
+/// Children of the source move under the target, its name, slug and aliases become aliases of the target.
+pub async fn merge_subjects(db: &PgPool, source_id: Uuid, target_id: Uuid) -> Result<MergeSubjects> {
+    let mut tx = db.begin().await?;
+
+    // Both rows are locked so concurrent merges or renames of either wait for this one
+    sqlx::query!(
+        "SELECT id FROM subjects WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+        &[source_id, target_id]
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let into_own_subtree = sqlx::query_scalar!(
+        r#"SELECT $2::uuid IN (SELECT subject_descendants($1)) AS "inside!""#,
+        source_id,
+        target_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if into_own_subtree {
+        return Ok(MergeSubjects::IntoOwnSubtree);
+    }
+
+    let conflict = sqlx::query_scalar!(
+        r#"
+        SELECT moved.name
+        FROM subjects moved
+        JOIN subjects existing ON existing.parent_id = $2 AND lower(existing.name) = lower(moved.name)
+        WHERE moved.parent_id = $1
+        LIMIT 1
+        "#,
+        source_id,
+        target_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(name) = conflict {
+        return Ok(MergeSubjects::ChildNameConflict(name));
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO subject_aliases (alias, subject_id)
+        SELECT alias, $2 FROM (
+            SELECT normalize_subject(name) AS alias FROM subjects WHERE id = $1
+            UNION
+            SELECT slug FROM subjects WHERE id = $1
+            UNION
+            SELECT alias FROM subject_aliases WHERE subject_id = $1
+        ) merged
+        ON CONFLICT (alias) DO UPDATE SET subject_id = EXCLUDED.subject_id
+        "#,
+        source_id,
+        target_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO post_subjects (post_id, subject_id, position)
+        SELECT post_id, $2, position FROM post_subjects WHERE subject_id = $1
+        ON CONFLICT DO NOTHING
+        "#,
+        source_id,
+        target_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM post_subjects WHERE subject_id = $1", source_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        "UPDATE subjects SET parent_id = $2 WHERE parent_id = $1",
+        source_id,
+        target_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM subjects WHERE id = $1", source_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(MergeSubjects::Merged)
+}
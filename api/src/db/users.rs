@@ -3,6 +3,7 @@ use crate::{
     server::{
         auth::{PasswordHash, Salt},
         credentials::{CredentialError, Credentials, StoredCredentials, Valid},
+        user::{UserRating, UserStats},
     },
 };
 
@@ -11,7 +12,6 @@ use sqlx::PgPool;
 use uuid::Uuid;
 pub async fn get_stored_credentials(db: &PgPool, user_id: Uuid) -> Result<StoredCredentials> {
     struct Query {
-        id: Uuid,
         password_hash: PasswordHash,
         salt: Salt,
     }
@@ -19,18 +19,14 @@ pub async fn get_stored_credentials(db: &PgPool, user_id: Uuid) -> Result<Stored
     let query = sqlx::query_as!(
         Query,
         r#"
-        select id, password_hash, salt from users where id = $1 limit 1;
+        select password_hash, salt from users where id = $1 limit 1;
         "#,
         user_id
     )
     .fetch_one(db)
     .await?;
 
-    Ok(StoredCredentials::new(
-        query.id,
-        query.password_hash,
-        query.salt,
-    ))
+    Ok(StoredCredentials::new(query.password_hash, query.salt))
 }
 
 /// Updates the token version and returns it for the given user_id
@@ -105,23 +101,13 @@ pub async fn add_user(
     credentials: Credentials<Valid>,
     username: String,
 ) -> Result<()> {
-    #[derive(Debug)]
-    struct Query {
-        id: Uuid,
-        username: String,
-        email: String,
-        token_ver: Uuid,
-        created_at: DateTime<Utc>,
-    }
-
     let (email, password, salt) = credentials.prepare();
 
-    let query = sqlx::query_as!(
-        Query,
+    let user_id = sqlx::query_scalar!(
         r#"
         insert into users (email, username, salt, password_hash, avatar)
         values ($1, $2, $3, $4, null)
-        RETURNING id, username, email, token_ver, created_at
+        RETURNING id
         "#,
         email,
         username,
@@ -131,7 +117,7 @@ pub async fn add_user(
     .fetch_one(db)
     .await?;
 
-    tracing::info!("Created a user: {:?}", &query);
+    tracing::info!("Created user {}", user_id);
 
     Ok(())
 }
@@ -151,23 +137,37 @@ pub async fn get_user_id_by_email(db: &PgPool, email: String) -> Result<Uuid> {
 use crate::api::user::UserInfo;
 
 // Get user by ID for API responses (public information)
-pub async fn get_user_by_id_public(db: &PgPool, user_id: Uuid) -> Result<Option<UserInfo>> {
+// `viewer_id` is the authenticated caller, used to resolve `is_following`
+pub async fn get_user_by_id_public(
+    db: &PgPool,
+    user_id: Uuid,
+    viewer_id: Option<Uuid>,
+) -> Result<Option<UserInfo>> {
     #[derive(Debug)]
     struct UserQuery {
         id: Uuid,
         username: String,
         email: String,
-        created_at: DateTime<Utc>,
+        rating: rust_decimal::Decimal,
+        review_count: i32,
+        is_following: Option<bool>,
     }
 
     let user_query = sqlx::query_as!(
         UserQuery,
         r#"
-        SELECT id, username, email, created_at
-        FROM users
-        WHERE id = $1
+        SELECT u.id, u.username, u.email,
+               COALESCE(r.average_score, 0) AS "rating!",
+               COALESCE(r.review_count, 0) AS "review_count!",
+               CASE WHEN $2::uuid IS NULL OR $2 = u.id THEN NULL ELSE EXISTS (
+                   SELECT 1 FROM user_follows f WHERE f.follower_id = $2 AND f.followee_id = u.id
+               ) END AS is_following
+        FROM users u
+        LEFT JOIN user_ratings r ON r.user_id = u.id
+        WHERE u.id = $1
         "#,
-        user_id
+        user_id,
+        viewer_id
     )
     .fetch_optional(db)
     .await?;
@@ -179,6 +179,9 @@ pub async fn get_user_by_id_public(db: &PgPool, user_id: Uuid) -> Result<Option<
             name: Some(user.username.clone()), // Use username as name
             email: user.email,
             subjects: Some(vec![]), // Default empty subjects
+            rating: user.rating,
+            review_count: user.review_count,
+            is_following: user.is_following,
         }))
     } else {
         Ok(None)
@@ -216,21 +219,33 @@ pub async fn update_user(
 }
 
 // Get all users (public information only)
-pub async fn get_all_users_public(db: &PgPool) -> Result<Vec<crate::api::user::UserInfo>> {
+pub async fn get_all_users_public(
+    db: &PgPool,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<crate::api::user::UserInfo>> {
     struct UserQuery {
         id: Uuid,
         username: String,
         email: String,
-        created_at: DateTime<Utc>,
+        rating: rust_decimal::Decimal,
+        review_count: i32,
+        is_following: Option<bool>,
     }
 
     let users = sqlx::query_as!(
         UserQuery,
         r#"
-        SELECT id, username, email, created_at
-        FROM users
-        ORDER BY created_at DESC
-        "#
+        SELECT u.id, u.username, u.email,
+               COALESCE(r.average_score, 0) AS "rating!",
+               COALESCE(r.review_count, 0) AS "review_count!",
+               CASE WHEN $1::uuid IS NULL OR $1 = u.id THEN NULL ELSE EXISTS (
+                   SELECT 1 FROM user_follows f WHERE f.follower_id = $1 AND f.followee_id = u.id
+               ) END AS is_following
+        FROM users u
+        LEFT JOIN user_ratings r ON r.user_id = u.id
+        ORDER BY u.created_at DESC
+        "#,
+        viewer_id
     )
     .fetch_all(db)
     .await?;
@@ -243,8 +258,194 @@ pub async fn get_all_users_public(db: &PgPool) -> Result<Vec<crate::api::user::U
             name: Some(user.username.clone()), // Use username as name
             email: user.email,
             subjects: Some(vec![]), // Default empty subjects
+            rating: user.rating,
+            review_count: user.review_count,
+            is_following: user.is_following,
         })
         .collect();
 
     Ok(user_infos)
 }
+
+/// Follows another user, returns false if the followee does not exist
+pub async fn follow_user(db: &PgPool, follower_id: Uuid, followee_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO user_follows (follower_id, followee_id)
+        SELECT $1, id FROM users WHERE id = $2
+        ON CONFLICT (follower_id, followee_id) DO NOTHING
+        "#,
+        follower_id,
+        followee_id
+    )
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+
+    // Nothing inserted, either the user is missing or already followed
+    let exists = sqlx::query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)",
+        followee_id
+    )
+    .fetch_one(db)
+    .await?
+    .unwrap_or(false);
+
+    Ok(exists)
+}
+
+/// Unfollows a user, returns false if the user was not followed
+pub async fn unfollow_user(db: &PgPool, follower_id: Uuid, followee_id: Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM user_follows WHERE follower_id = $1 AND followee_id = $2",
+        follower_id,
+        followee_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rating aggregate for a user, zeroed if they have not received any reviews yet
+pub async fn get_user_rating(db: &PgPool, user_id: Uuid) -> Result<UserRating> {
+    let rating = sqlx::query_as!(
+        UserRating,
+        r#"
+        SELECT review_count, average_score AS "average_score!",
+               one_star, two_stars, three_stars, four_stars, five_stars
+        FROM user_ratings
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(rating.unwrap_or_default())
+}
+
+/// Computes profile statistics for a user in a single round trip, `None` if the user does not exist
+pub async fn get_user_stats(db: &PgPool, user_id: Uuid) -> Result<Option<UserStats>> {
+    let stats = sqlx::query_as!(
+        UserStats,
+        r#"
+        WITH thread_messages AS (
+            SELECT m.sender_id, m.sent_at,
+                   LAG(m.sender_id) OVER w AS prev_sender_id,
+                   LAG(m.sent_at) OVER w AS prev_sent_at
+            FROM messages m
+            JOIN msg_threads t ON t.id = m.thread_id
+            WHERE t.user_a = $1 OR t.user_b = $1
+            WINDOW w AS (PARTITION BY m.thread_id ORDER BY m.sent_at)
+        ),
+        post_counts AS (
+            SELECT COUNT(*) FILTER (WHERE type = 'request') AS requests_created,
+                   COUNT(*) FILTER (WHERE type = 'offer') AS offers_created,
+                   COUNT(*) FILTER (WHERE status = 'completed') AS completed_jobs,
+                   MAX(updated_at) AS last_post_at
+            FROM posts
+            WHERE owner_id = $1 AND is_post_public(posts)
+        )
+        SELECT u.id AS user_id,
+               pc.requests_created AS "requests_created!",
+               pc.offers_created AS "offers_created!",
+               pc.completed_jobs AS "completed_jobs!",
+               (
+                   SELECT percentile_cont(0.5) WITHIN GROUP (
+                       ORDER BY EXTRACT(EPOCH FROM tm.sent_at - tm.prev_sent_at)::float8
+                   )
+                   FROM thread_messages tm
+                   WHERE tm.sender_id = $1 AND tm.prev_sender_id <> $1
+               ) AS median_response_time_seconds,
+               u.created_at AS member_since,
+               GREATEST(
+                   (SELECT MAX(sent_at) FROM messages WHERE sender_id = $1),
+                   pc.last_post_at,
+                   (SELECT MAX(created_at) FROM reviews WHERE review_sender_id = $1)
+               ) AS last_active
+        FROM users u
+        CROSS JOIN post_counts pc
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(stats)
+}
+
+/// False for unknown users
+pub async fn is_admin(db: &PgPool, user_id: Uuid) -> Result<bool> {
+    let is_admin = sqlx::query_scalar!("SELECT is_admin FROM users WHERE id = $1", user_id)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(is_admin.unwrap_or(false))
+}
+
+/// Moderators and admins can both moderate
+pub async fn is_moderator(db: &PgPool, user_id: Uuid) -> Result<bool> {
+    let is_moderator = sqlx::query_scalar!(
+        r#"SELECT is_moderator OR is_admin AS "is_moderator!" FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(is_moderator.unwrap_or(false))
+}
+
+/// End of the user's suspension, None when they are not suspended
+pub async fn get_suspended_until(db: &PgPool, user_id: Uuid) -> Result<Option<DateTime<Utc>>> {
+    let suspended_until = sqlx::query_scalar!(
+        "SELECT suspended_until FROM users WHERE id = $1 AND suspended_until > NOW()",
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(suspended_until.flatten())
+}
+
+/// Secret of the user's calendar feed, `token` becomes the secret when there is none yet
+pub async fn get_or_set_calendar_token(db: &PgPool, user_id: Uuid, token: &str) -> Result<Option<String>> {
+    let calendar_token = sqlx::query_scalar!(
+        r#"
+        UPDATE users SET calendar_token = COALESCE(calendar_token, $2)
+        WHERE id = $1
+        RETURNING calendar_token AS "calendar_token!"
+        "#,
+        user_id,
+        token
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(calendar_token)
+}
+
+/// Replaces the secret of the user's calendar feed, the old address stops working
+pub async fn set_calendar_token(db: &PgPool, user_id: Uuid, token: &str) -> Result<bool> {
+    let result = sqlx::query!(
+        "UPDATE users SET calendar_token = $2 WHERE id = $1",
+        user_id,
+        token
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_by_calendar_token(db: &PgPool, token: &str) -> Result<Option<Uuid>> {
+    let user_id = sqlx::query_scalar!("SELECT id FROM users WHERE calendar_token = $1", token)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(user_id)
+}
@@ -18,6 +18,10 @@ pub enum AppError {
     JsonError(#[from] serde_json::Error),
     #[error("UUID parsing error: {0}")]
     UuidError(#[from] uuid::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Blob store error: {0}")]
+    BlobStoreError(#[from] object_store::Error),
     #[error("Bad request: {0}")]
     BadRequest(String),
     #[error("Internal server error: {0}")]
@@ -1,4 +1,5 @@
 // Removed unused imports
+use std::net::SocketAddr;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::{api::app::init_router, app::AppState};
@@ -31,10 +32,43 @@ async fn main() {
         .map_err(|e| tracing::error!("App state initalization failed: {:?}", e))
         .unwrap();
 
-    let app = init_router().with_state(app_state);
+    let app = init_router().with_state(app_state.clone());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
     tracing::info!("Server up on 0.0.0.0:8080");
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    // Don't lose views buffered since the last periodic flush
+    if let Err(e) = app_state.view_counter.flush(&app_state.db).await {
+        tracing::error!("Failed to flush post views on shutdown: {:?}", e);
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        if let Ok(mut signal) =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        {
+            signal.recv().await;
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutting down");
 }
@@ -0,0 +1,85 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{db, error::Result};
+
+/// Days of the views series when the owner does not ask for a range
+pub const DEFAULT_ANALYTICS_DAYS: i64 = 30;
+
+/// Longest views series returned at once
+pub const MAX_ANALYTICS_DAYS: i64 = 365;
+
+/// Something that happened to a post, recorded for its owner's analytics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostEvent {
+    /// A counted view, repeated views by the same viewer within an hour count once
+    View,
+    /// Someone started a chat thread about the post
+    ThreadOpened,
+}
+
+impl PostEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostEvent::View => "view",
+            PostEvent::ThreadOpened => "thread_opened",
+        }
+    }
+}
+
+/// Views of a post on one day (UTC)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyViews {
+    pub date: NaiveDate,
+    pub views: i64,
+    pub unique_viewers: i64,
+}
+
+/// Totals since events were first recorded for the post
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostAnalyticsTotals {
+    pub views: i64,
+    /// Signed in users count once each, anonymous visitors once per address
+    pub unique_viewers: i64,
+    pub threads_opened: i64,
+    pub proposals_received: i64,
+    /// Users other than the owner who opened a thread or sent a proposal
+    pub engaged_users: i64,
+}
+
+/// How a post is doing, only its owner sees this
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostAnalytics {
+    pub post_id: Uuid,
+    #[serde(flatten)]
+    pub totals: PostAnalyticsTotals,
+    /// Share of unique viewers that became engaged users, null before the first view
+    pub conversion_rate: Option<f64>,
+    /// One entry per day, oldest first, days without views included
+    pub views_over_time: Vec<DailyViews>,
+}
+
+impl PostAnalytics {
+    pub fn new(post_id: Uuid, totals: PostAnalyticsTotals, views_over_time: Vec<DailyViews>) -> Self {
+        // Users can reach a post without a counted view, e.g. through a link in the chat
+        let conversion_rate = (totals.unique_viewers > 0)
+            .then(|| (totals.engaged_users as f64 / totals.unique_viewers as f64).min(1.0));
+
+        Self {
+            post_id,
+            totals,
+            conversion_rate,
+            views_over_time,
+        }
+    }
+}
+
+/// Analytics of a post with views per day over the last `days` days
+pub async fn get_post_analytics(db: &PgPool, post_id: Uuid, days: i64) -> Result<PostAnalytics> {
+    let totals = db::post_events::get_post_analytics_totals(db, post_id).await?;
+    let views_over_time = db::post_events::get_daily_views(db, post_id, days).await?;
+
+    Ok(PostAnalytics::new(post_id, totals, views_over_time))
+}
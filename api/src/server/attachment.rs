@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Largest single upload
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Total size of the files a single user may keep
+pub const USER_ATTACHMENT_QUOTA: i64 = 100 * 1024 * 1024;
+
+pub const MAX_ATTACHMENTS_PER_POST: i64 = 10;
+
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// Types accepted for upload, detected from the content and never taken from the client
+const ALLOWED_CONTENT_TYPES: [&str; 12] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "application/pdf",
+    "application/zip",
+    "application/msword",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.oasis.opendocument.text",
+    "text/plain",
+];
+
+/// File attached to a post
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostAttachment {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub uploader_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where the content of an attachment is kept in the blob store
+pub fn storage_key(post_id: Uuid, attachment_id: Uuid) -> String {
+    format!("posts/{}/{}", post_id, attachment_id)
+}
+
+/// Detects the type of an upload from its magic bytes, None when it is not allowed.
+/// Anything without a signature that is valid UTF-8 counts as plain text.
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    let detected = match infer::get(data) {
+        Some(kind) => kind.mime_type(),
+        None if !data.contains(&0) && std::str::from_utf8(data).is_ok() => "text/plain",
+        None => return None,
+    };
+
+    ALLOWED_CONTENT_TYPES.into_iter().find(|allowed| *allowed == detected)
+}
+
+/// Keeps only the last path component of a client supplied name, without control characters
+pub fn sanitize_file_name(name: Option<&str>) -> String {
+    let name = name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default();
+
+    let cleaned: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+
+    match cleaned.trim() {
+        "" | "." | ".." => "attachment".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Content-Disposition for a download, with an ASCII fallback and the UTF-8 name per RFC 6266
+pub fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+
+    let mut encoded = String::new();
+    for byte in file_name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_allowed_types_from_content() {
+        assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff_content_type(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff_content_type("Zadanie 1: całka".as_bytes()), Some("text/plain"));
+    }
+
+    #[test]
+    fn rejects_other_content() {
+        assert_eq!(sniff_content_type(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0"), None);
+        assert_eq!(sniff_content_type(b"MZ\x90\0\x03\0\0\0"), None);
+        assert_eq!(sniff_content_type(b"text\0with nul"), None);
+        assert_eq!(sniff_content_type(&[0xff, 0xfe, 0x41]), None);
+    }
+
+    #[test]
+    fn keeps_the_last_path_component() {
+        assert_eq!(sanitize_file_name(Some("notes.pdf")), "notes.pdf");
+        assert_eq!(sanitize_file_name(Some("../../etc/passwd")), "passwd");
+        assert_eq!(sanitize_file_name(Some("C:\\Users\\me\\zadanie.docx")), "zadanie.docx");
+        assert_eq!(sanitize_file_name(Some("  spaced.txt  ")), "spaced.txt");
+    }
+
+    #[test]
+    fn falls_back_for_empty_names() {
+        assert_eq!(sanitize_file_name(None), "attachment");
+        assert_eq!(sanitize_file_name(Some("")), "attachment");
+        assert_eq!(sanitize_file_name(Some("dir/")), "attachment");
+        assert_eq!(sanitize_file_name(Some("..")), "attachment");
+        assert_eq!(sanitize_file_name(Some("\n\t")), "attachment");
+    }
+
+    #[test]
+    fn strips_control_characters_and_limits_length() {
+        assert_eq!(sanitize_file_name(Some("a\u{0}b\r\nc.txt")), "abc.txt");
+        let long = "ą".repeat(MAX_FILE_NAME_LENGTH + 10);
+        assert_eq!(sanitize_file_name(Some(&long)).chars().count(), MAX_FILE_NAME_LENGTH);
+    }
+
+    #[test]
+    fn content_disposition_has_ascii_and_utf8_names() {
+        assert_eq!(
+            content_disposition("notes.pdf"),
+            "attachment; filename=\"notes.pdf\"; filename*=UTF-8''notes.pdf"
+        );
+        assert_eq!(
+            content_disposition("całki \"1\".pdf"),
+            "attachment; filename=\"ca_ki _1_.pdf\"; filename*=UTF-8''ca%C5%82ki%20%221%22.pdf"
+        );
+    }
+}
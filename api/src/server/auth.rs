@@ -1,3 +1,4 @@
+use crate::app::AppState;
 use crate::db;
 use crate::server::credentials::CredentialError;
 use crate::{
@@ -10,7 +11,7 @@ use argon2::{
     password_hash::rand_core::{OsRng, RngCore},
 };
 use axum::{
-    extract::FromRequestParts,
+    extract::{FromRequestParts, OptionalFromRequestParts},
     http::{StatusCode, request::Parts},
 };
 use jsonwebtoken::{DecodingKey, Validation};
@@ -67,7 +68,7 @@ impl From<Vec<u8>> for Salt {
     }
 }
 
-pub static HASH: LazyLock<Argon2> = LazyLock::new(|| Argon2::default());
+pub static HASH: LazyLock<Argon2> = LazyLock::new(Argon2::default);
 
 // Helper trait for JWT's
 pub trait JwtToken<'a>: Serialize + DeserializeOwned {
@@ -132,15 +133,15 @@ where
             .get(axum::http::header::AUTHORIZATION)
             .and_then(|hv| hv.to_str().ok());
 
-        if let Some(header) = auth_header {
-            if let Some(token) = header.strip_prefix("Bearer ") {
-                let secret = env("ACCESS_TOKEN_SECRET");
-                if let Some(token) = AccessToken::try_decode(token, secret) {
-                    if token.is_valid() {
-                        return Ok(token);
-                    } else {
-                        return Err((StatusCode::UNAUTHORIZED, "Token invalid"));
-                    };
+        if let Some(header) = auth_header
+            && let Some(token) = header.strip_prefix("Bearer ")
+        {
+            let secret = env("ACCESS_TOKEN_SECRET");
+            if let Some(token) = AccessToken::try_decode(token, secret) {
+                if token.is_valid() {
+                    return Ok(token);
+                } else {
+                    return Err((StatusCode::UNAUTHORIZED, "Token invalid"));
                 };
             };
         };
@@ -149,6 +150,74 @@ where
     }
 }
 
+// Lets public endpoints take `Option<AccessToken>`, a missing header means anonymous access
+// while a header with a bad token is still rejected
+impl<S> OptionalFromRequestParts<S> for AccessToken
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> std::result::Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(axum::http::header::AUTHORIZATION) {
+            return Ok(None);
+        }
+
+        <AccessToken as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// Access token of a user with the admin flag, checked against the database on every request
+pub struct AdminToken(pub AccessToken);
+
+impl FromRequestParts<AppState> for AdminToken {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let token = <AccessToken as FromRequestParts<AppState>>::from_request_parts(parts, state).await?;
+
+        match db::users::is_admin(&state.db, token.sub).await {
+            Ok(true) => Ok(AdminToken(token)),
+            Ok(false) => Err((StatusCode::FORBIDDEN, "Admin access required")),
+            Err(e) => {
+                tracing::error!("Failed to check admin flag: {:?}", e);
+                Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to check permissions"))
+            }
+        }
+    }
+}
+
+/// Access token of a moderator or admin, checked against the database on every request
+pub struct ModeratorToken(pub AccessToken);
+
+impl FromRequestParts<AppState> for ModeratorToken {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let token = <AccessToken as FromRequestParts<AppState>>::from_request_parts(parts, state).await?;
+
+        match db::users::is_moderator(&state.db, token.sub).await {
+            Ok(true) => Ok(ModeratorToken(token)),
+            Ok(false) => Err((StatusCode::FORBIDDEN, "Moderator access required")),
+            Err(e) => {
+                tracing::error!("Failed to check moderator flag: {:?}", e);
+                Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to check permissions"))
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RefreshToken {
     pub exp: usize, // Epoch expiration
@@ -183,7 +252,7 @@ impl Auth {
             let pepper_env = env("SERVER_PEPPER");
             let pepper = pepper_env.into_bytes();
             let mut password = password.clone();
-            password.extend(pepper.into_iter());
+            password.extend(pepper);
 
             password
         };
@@ -200,7 +269,8 @@ impl Auth {
 
         let password_hash = {
             let mut buf = [0u8; HASH_LEN];
-            HASH.hash_password_into(&prepared_password, &*salt, &mut buf);
+            HASH.hash_password_into(&prepared_password, &salt, &mut buf)
+                .expect("argon2 output length is valid");
             PasswordHash(buf.to_vec())
         };
 
@@ -215,8 +285,12 @@ impl Auth {
         db: &PgPool,
     ) -> Result<AccessToken> {
         let token_ver = crate::db::users::get_token_version(db, refresh_token.sub).await?;
+        // Suspending also rotates the token version, this catches refreshes racing with it
+        if db::users::get_suspended_until(db, refresh_token.sub).await?.is_some() {
+            return Err(CredentialError::AccountSuspended.into());
+        }
         if token_ver == refresh_token.ver && refresh_token.is_valid() {
-            Ok(AccessToken::new(refresh_token.sub.clone()))
+            Ok(AccessToken::new(refresh_token.sub))
         } else {
             Err(AppError::GenericError(
                 "Invalid token version or refresh token is expired".into(),
@@ -241,6 +315,10 @@ impl Auth {
 
         stored_credentials.check_credentials(credentials)?;
 
+        if db::users::get_suspended_until(db, user_id).await?.is_some() {
+            return Err(CredentialError::AccountSuspended.into());
+        }
+
         Ok(RefreshToken::new(token_ver, user_id))
     }
 }
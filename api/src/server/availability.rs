@@ -0,0 +1,96 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// Time zone used for users that have not configured their availability yet
+pub const DEFAULT_TIME_ZONE: &str = "Europe/Warsaw";
+
+const MAX_WEEKLY_SLOTS: usize = 50;
+const MAX_EXCEPTIONS: usize = 200;
+
+/// Recurring time range on a weekday, times are local to the user's time zone
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeeklySlot {
+    /// ISO weekday, 1 = Monday ... 7 = Sunday
+    pub weekday: i16,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+/// Change to the weekly schedule on a single date.
+/// Without a time range the exception applies to the whole day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AvailabilityException {
+    pub date: NaiveDate,
+    pub is_available: bool,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+}
+
+/// Full availability schedule of a user
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Availability {
+    pub time_zone: String,
+    pub weekly: Vec<WeeklySlot>,
+    pub exceptions: Vec<AvailabilityException>,
+}
+
+impl Default for Availability {
+    fn default() -> Self {
+        Self {
+            time_zone: DEFAULT_TIME_ZONE.to_string(),
+            weekly: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+impl Availability {
+    /// Checks ranges and limits, the time zone itself is verified against the database.
+    /// Returns a message describing the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.time_zone.trim().is_empty() {
+            return Err("time_zone cannot be empty".to_string());
+        }
+
+        if self.weekly.len() > MAX_WEEKLY_SLOTS {
+            return Err(format!("At most {} weekly slots are allowed", MAX_WEEKLY_SLOTS));
+        }
+
+        if self.exceptions.len() > MAX_EXCEPTIONS {
+            return Err(format!("At most {} exceptions are allowed", MAX_EXCEPTIONS));
+        }
+
+        for slot in &self.weekly {
+            if !(1..=7).contains(&slot.weekday) {
+                return Err("weekday must be between 1 (Monday) and 7 (Sunday)".to_string());
+            }
+            if slot.start_time >= slot.end_time {
+                return Err("start_time must be before end_time".to_string());
+            }
+        }
+
+        // Overlapping ranges on the same day are almost always a client mistake
+        let mut slots: Vec<&WeeklySlot> = self.weekly.iter().collect();
+        slots.sort_by_key(|s| (s.weekday, s.start_time));
+        for pair in slots.windows(2) {
+            if pair[0].weekday == pair[1].weekday && pair[1].start_time < pair[0].end_time {
+                return Err(format!("Weekly slots overlap on weekday {}", pair[0].weekday));
+            }
+        }
+
+        for exception in &self.exceptions {
+            match (exception.start_time, exception.end_time) {
+                (None, None) => {}
+                (Some(start), Some(end)) if start < end => {}
+                (Some(_), Some(_)) => {
+                    return Err("start_time must be before end_time".to_string());
+                }
+                _ => {
+                    return Err("Exceptions need both start_time and end_time or neither".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
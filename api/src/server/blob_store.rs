@@ -0,0 +1,166 @@
+use std::{
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path as ObjectPath,
+    Attribute, Attributes, ObjectStore, PutOptions, PutPayload,
+};
+
+use crate::{
+    common::env_var,
+    error::{AppError, Result},
+};
+
+/// Directory used by the local store when BLOB_LOCAL_DIR is not set
+const DEFAULT_LOCAL_DIR: &str = "./uploads";
+
+/// Storage for uploaded files. Keys are generated by the server and use `/` as separator.
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<()>;
+
+    /// Returns None when nothing is stored under the key
+    async fn get(&self, key: &str) -> Result<Option<Bytes>>;
+
+    /// Deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Picks the store from the environment.
+///
+/// BLOB_STORE=local (default) keeps files under BLOB_LOCAL_DIR.
+/// BLOB_STORE=s3 uses S3_BUCKET, S3_REGION, S3_ENDPOINT, S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY,
+/// set S3_ENDPOINT to an http URL for MinIO.
+pub fn blob_store_from_env() -> Result<Arc<dyn BlobStore>> {
+    let kind = env_var("BLOB_STORE").unwrap_or_else(|_| "local".to_string());
+
+    match kind.as_str() {
+        "local" => {
+            let root = env_var("BLOB_LOCAL_DIR").unwrap_or_else(|_| DEFAULT_LOCAL_DIR.to_string());
+            tracing::info!("Storing attachments in {}", root);
+            Ok(Arc::new(LocalBlobStore::new(root)))
+        }
+        "s3" => {
+            let store = S3BlobStore::from_env()?;
+            tracing::info!("Storing attachments in S3 bucket {}", env_var("S3_BUCKET")?);
+            Ok(Arc::new(store))
+        }
+        other => Err(AppError::InternalServerError(format!(
+            "Unknown BLOB_STORE '{}', expected 'local' or 's3'",
+            other
+        ))),
+    }
+}
+
+/// Files in a directory on the local disk, one file per key
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> Result<()> {
+        let path = self.path_of(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write next to the target and rename so readers never see a partial file
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, &data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>> {
+        match tokio::fs::read(self.path_of(key)).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path_of(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Bucket in S3 or any S3 compatible service such as MinIO
+pub struct S3BlobStore {
+    store: AmazonS3,
+}
+
+impl S3BlobStore {
+    pub fn from_env() -> Result<Self> {
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(env_var("S3_BUCKET")?)
+            .with_region(env_var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()))
+            .with_access_key_id(env_var("S3_ACCESS_KEY_ID")?)
+            .with_secret_access_key(env_var("S3_SECRET_ACCESS_KEY")?);
+
+        if let Ok(endpoint) = env_var("S3_ENDPOINT") {
+            // Self hosted services are usually addressed by path and often without TLS
+            builder = builder
+                .with_allow_http(endpoint.starts_with("http://"))
+                .with_virtual_hosted_style_request(false)
+                .with_endpoint(endpoint);
+        }
+
+        Ok(Self { store: builder.build()? })
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<()> {
+        let mut attributes = Attributes::new();
+        attributes.insert(Attribute::ContentType, content_type.to_string().into());
+
+        let options = PutOptions {
+            attributes,
+            ..Default::default()
+        };
+
+        self.store
+            .put_opts(&ObjectPath::from(key), PutPayload::from(data), options)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>> {
+        match self.store.get(&ObjectPath::from(key)).await {
+            Ok(result) => Ok(Some(result.bytes().await?)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match self.store.delete(&ObjectPath::from(key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -3,6 +3,8 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::server::{notifications::Notification, post::PostRevision};
+
 /// Represents a chat thread between two users about a specific post
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct MessageThread {
@@ -15,30 +17,6 @@ pub struct MessageThread {
 }
 
 impl MessageThread {
-    /// Create a new thread ensuring user_a < user_b for consistent ordering
-    pub fn new(post_id: Uuid, user1: Uuid, user2: Uuid) -> Self {
-        let (user_a, user_b) = if user1 < user2 {
-            (user1, user2)
-        } else {
-            (user2, user1)
-        };
-        
-        let now = Utc::now();
-        Self {
-            id: Uuid::new_v4(),
-            post_id,
-            user_a,
-            user_b,
-            created_at: now,
-            updated_at: now,
-        }
-    }
-    
-    /// Check if a user is part of this thread
-    pub fn contains_user(&self, user_id: &Uuid) -> bool {
-        &self.user_a == user_id || &self.user_b == user_id
-    }
-    
     /// Get the other user in the thread (not the provided user)
     pub fn get_other_user(&self, user_id: &Uuid) -> Option<Uuid> {
         if &self.user_a == user_id {
@@ -61,19 +39,6 @@ pub struct Message {
     pub sent_at: DateTime<Utc>,
 }
 
-impl Message {
-    /// Create a new message
-    pub fn new(thread_id: Uuid, sender_id: Uuid, content: String) -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            thread_id,
-            sender_id,
-            content,
-            sent_at: Utc::now(),
-        }
-    }
-}
-
 /// Enhanced thread info with post and user details for API responses
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ThreadInfo {
@@ -84,6 +49,10 @@ pub struct ThreadInfo {
     pub other_user_name: String,
     pub last_message: Option<String>,
     pub last_message_at: Option<DateTime<Utc>>,
+    /// When the post was last edited, see GET /posts/{id}/revisions for what changed
+    pub post_revised_at: Option<DateTime<Utc>>,
+    /// Whether the post was edited after this thread was opened
+    pub post_changed: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -146,6 +115,17 @@ pub enum ChatResponse {
     #[serde(rename = "new_message")]
     NewMessage {
         message: MessageInfo,
+        /// Whether the client should surface a notification, false when muted or in quiet hours
+        notify: bool,
+    },
+    #[serde(rename = "notification")]
+    Notification {
+        notification: Notification,
+    },
+    /// The post a thread is about was edited by its owner
+    #[serde(rename = "post_revised")]
+    PostRevised {
+        revision: PostRevision,
     },
     #[serde(rename = "error")]
     Error {
@@ -2,7 +2,6 @@ use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use uuid::Uuid;
 
 use crate::server::auth::{Auth, PasswordHash, Salt};
 
@@ -16,6 +15,8 @@ pub enum CredentialError {
     EmailTaken,
     #[error("Username taken")]
     UsernameTaken,
+    #[error("Account suspended")]
+    AccountSuspended,
 }
 
 /// Valid credentials means that they have the right form, to see if credentials are matching get [`StoredCredentials`]
@@ -67,15 +68,13 @@ impl Credentials<Valid> {
 }
 
 pub struct StoredCredentials {
-    user_id: Uuid,
     password_hash: PasswordHash,
     salt: Salt,
 }
 
 impl StoredCredentials {
-    pub fn new(user_id: Uuid, password_hash: PasswordHash, salt: Salt) -> Self {
+    pub fn new(password_hash: PasswordHash, salt: Salt) -> Self {
         Self {
-            user_id,
             password_hash,
             salt,
         }
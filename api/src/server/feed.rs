@@ -0,0 +1,288 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::{common::env_var, server::post::Post};
+
+/// Web app address used when PUBLIC_URL is not set
+const DEFAULT_PUBLIC_URL: &str = "http://localhost:3000";
+
+const SITE_NAME: &str = "Techni Zlecenia";
+
+/// Past deadlines stay in the calendar feed this long
+pub const CALENDAR_PAST_DAYS: i64 = 30;
+
+const CALENDAR_TOKEN_BYTES: usize = 32;
+
+/// Longest content line of an iCalendar file in octets, longer ones are folded
+const ICAL_LINE_OCTETS: usize = 75;
+
+/// Absolute addresses put into feeds.
+///
+/// PUBLIC_URL is the web app, post links point there. PUBLIC_API_URL is where clients reach
+/// the API, it defaults to the web app's /api proxy.
+#[derive(Debug, Clone)]
+pub struct PublicUrls {
+    pub web: String,
+    pub api: String,
+}
+
+impl PublicUrls {
+    pub fn from_env() -> Self {
+        let web = env_var("PUBLIC_URL")
+            .unwrap_or_else(|_| DEFAULT_PUBLIC_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let api = env_var("PUBLIC_API_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| format!("{}/api", web));
+
+        Self { web, api }
+    }
+
+    pub fn post(&self, post_id: Uuid) -> String {
+        format!("{}/posts/{}", self.web, post_id)
+    }
+
+    pub fn calendar(&self, token: &str) -> String {
+        format!("{}/feeds/calendar/{}.ics", self.api, token)
+    }
+}
+
+/// Syndication format of the posts feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+
+    /// Feed document for the posts, `self_url` is the address the feed was requested at
+    pub fn render(&self, posts: &[Post], self_url: &str, urls: &PublicUrls) -> String {
+        match self {
+            FeedFormat::Atom => render_atom(posts, self_url, urls),
+            FeedFormat::Rss => render_rss(posts, self_url, urls),
+        }
+    }
+}
+
+/// Deadline of a post the user owns or helps with, as listed in their calendar
+#[derive(Debug, Clone)]
+pub struct CalendarDeadline {
+    pub post_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub status: String,
+    pub deadline: DateTime<Utc>,
+    /// False when the user is the assigned helper
+    pub is_owner: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Secret part of a calendar feed address
+pub fn generate_calendar_token() -> String {
+    let mut buf = [0u8; CALENDAR_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut buf);
+    buf.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Escapes text for XML content and attributes, dropping characters XML cannot carry
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn atom_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn render_atom(posts: &[Post], self_url: &str, urls: &PublicUrls) -> String {
+    let updated = posts
+        .iter()
+        .map(|post| post.updated_at)
+        .max()
+        .unwrap_or_else(Utc::now);
+
+    let mut feed = String::new();
+    feed.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str(&format!("  <title>{}: posts</title>\n", SITE_NAME));
+    feed.push_str(&format!("  <id>{}</id>\n", xml_escape(self_url)));
+    feed.push_str(&format!(
+        "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n",
+        xml_escape(self_url)
+    ));
+    feed.push_str(&format!("  <link rel=\"alternate\" href=\"{}\"/>\n", xml_escape(&urls.web)));
+    feed.push_str(&format!("  <updated>{}</updated>\n", atom_date(updated)));
+
+    for post in posts {
+        feed.push_str("  <entry>\n");
+        feed.push_str(&format!("    <id>urn:uuid:{}</id>\n", post.id));
+        feed.push_str(&format!("    <title>{}</title>\n", xml_escape(&post.title)));
+        feed.push_str(&format!(
+            "    <link rel=\"alternate\" href=\"{}\"/>\n",
+            xml_escape(&urls.post(post.id))
+        ));
+        feed.push_str(&format!("    <published>{}</published>\n", atom_date(post.listed_at())));
+        feed.push_str(&format!("    <updated>{}</updated>\n", atom_date(post.updated_at)));
+        feed.push_str(&format!(
+            "    <author><name>{}</name></author>\n",
+            xml_escape(&post.owner_username)
+        ));
+        for subject in post.subjects.iter() {
+            feed.push_str(&format!(
+                "    <category term=\"{}\" label=\"{}\"/>\n",
+                xml_escape(&subject.slug),
+                xml_escape(&subject.name)
+            ));
+        }
+        feed.push_str(&format!(
+            "    <content type=\"text\">{}</content>\n",
+            xml_escape(&post.description)
+        ));
+        feed.push_str("  </entry>\n");
+    }
+
+    feed.push_str("</feed>\n");
+    feed
+}
+
+fn render_rss(posts: &[Post], self_url: &str, urls: &PublicUrls) -> String {
+    let updated = posts
+        .iter()
+        .map(|post| post.updated_at)
+        .max()
+        .unwrap_or_else(Utc::now);
+
+    let mut feed = String::new();
+    feed.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str("  <channel>\n");
+    feed.push_str(&format!("    <title>{}: posts</title>\n", SITE_NAME));
+    feed.push_str(&format!("    <link>{}</link>\n", xml_escape(&urls.web)));
+    feed.push_str("    <description>Newest requests and offers</description>\n");
+    feed.push_str(&format!(
+        "    <atom:link rel=\"self\" type=\"application/rss+xml\" href=\"{}\"/>\n",
+        xml_escape(self_url)
+    ));
+    feed.push_str(&format!("    <lastBuildDate>{}</lastBuildDate>\n", updated.to_rfc2822()));
+
+    for post in posts {
+        feed.push_str("    <item>\n");
+        feed.push_str(&format!("      <title>{}</title>\n", xml_escape(&post.title)));
+        feed.push_str(&format!("      <link>{}</link>\n", xml_escape(&urls.post(post.id))));
+        feed.push_str(&format!("      <guid isPermaLink=\"false\">{}</guid>\n", post.id));
+        feed.push_str(&format!("      <pubDate>{}</pubDate>\n", post.listed_at().to_rfc2822()));
+        for subject in post.subjects.iter() {
+            feed.push_str(&format!("      <category>{}</category>\n", xml_escape(&subject.name)));
+        }
+        feed.push_str(&format!(
+            "      <description>{}</description>\n",
+            xml_escape(&post.description)
+        ));
+        feed.push_str("    </item>\n");
+    }
+
+    feed.push_str("  </channel>\n");
+    feed.push_str("</rss>\n");
+    feed
+}
+
+/// Escapes a TEXT value of an iCalendar property
+fn ical_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits a content line into lines of at most 75 octets, continuation lines start with a space
+fn ical_fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > ICAL_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+fn ical_date(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// iCalendar document with one event per deadline
+pub fn render_calendar(deadlines: &[CalendarDeadline], urls: &PublicUrls) -> String {
+    let now = Utc::now();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//{}//Deadlines//PL", SITE_NAME),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", ical_escape(&format!("{} deadlines", SITE_NAME))),
+    ];
+
+    for deadline in deadlines {
+        let role = if deadline.is_owner {
+            "Your post"
+        } else {
+            "You are helping with this post"
+        };
+        let description = format!(
+            "{}, status: {}\n\n{}",
+            role, deadline.status, deadline.description
+        );
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:deadline-{}@techni-zlecenia", deadline.post_id));
+        lines.push(format!("DTSTAMP:{}", ical_date(now)));
+        lines.push(format!("LAST-MODIFIED:{}", ical_date(deadline.updated_at)));
+        lines.push(format!("DTSTART:{}", ical_date(deadline.deadline)));
+        lines.push(format!("SUMMARY:{}", ical_escape(&format!("Deadline: {}", deadline.title))));
+        lines.push(format!("DESCRIPTION:{}", ical_escape(&description)));
+        lines.push(format!("URL:{}", urls.post(deadline.post_id)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut calendar = String::new();
+    for line in lines {
+        calendar.push_str(&ical_fold(&line));
+        calendar.push_str("\r\n");
+    }
+    calendar
+}
@@ -1,8 +1,24 @@
 // Buisness logic and type's
 // Anything that is going to exclusively happen on the server lives here
 
+pub mod analytics;
+pub mod attachment;
 pub mod auth;
+pub mod availability;
+pub mod blob_store;
 pub mod chat;
 pub mod credentials;
+pub mod feed;
+pub mod moderation;
+pub mod money;
+pub mod notifications;
+pub mod pagination;
 pub mod post;
+pub mod proposal;
+pub mod review;
+pub mod saved_search;
+pub mod school_location;
+pub mod scheduler;
+pub mod subject;
 pub mod user;
+pub mod views;
@@ -0,0 +1,420 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    common::env_var,
+    db,
+    error::Result,
+    server::notifications::{self, NotificationEvent},
+};
+
+/// Independent open reports after which a post is hidden until a moderator looks at it,
+/// overridden by REPORT_AUTO_HIDE_THRESHOLD
+const DEFAULT_AUTO_HIDE_REPORTS: i64 = 3;
+
+pub const DEFAULT_SUSPENSION_DAYS: i64 = 7;
+pub const MAX_SUSPENSION_DAYS: i64 = 365;
+
+const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+const MAX_MODERATION_NOTE_LENGTH: usize = 1000;
+
+/// Why a post was reported
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Scam,
+    Spam,
+    Inappropriate,
+    Harassment,
+    /// Asking for or offering to do graded work
+    AcademicDishonesty,
+    /// Needs details
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 6] = [
+        ReportReason::Scam,
+        ReportReason::Spam,
+        ReportReason::Inappropriate,
+        ReportReason::Harassment,
+        ReportReason::AcademicDishonesty,
+        ReportReason::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Scam => "scam",
+            ReportReason::Spam => "spam",
+            ReportReason::Inappropriate => "inappropriate",
+            ReportReason::Harassment => "harassment",
+            ReportReason::AcademicDishonesty => "academic_dishonesty",
+            ReportReason::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.as_str() == value)
+    }
+}
+
+/// What a moderator decided about the open reports of a post
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportResolution {
+    /// Nothing wrong, a post hidden by reports becomes visible again
+    Dismiss,
+    HidePost,
+    /// The author is warned, a post hidden by reports becomes visible again
+    WarnUser,
+    /// The author cannot log in for a while and the post is hidden
+    SuspendUser,
+}
+
+impl ReportResolution {
+    pub const ALL: [ReportResolution; 4] = [
+        ReportResolution::Dismiss,
+        ReportResolution::HidePost,
+        ReportResolution::WarnUser,
+        ReportResolution::SuspendUser,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportResolution::Dismiss => "dismiss",
+            ReportResolution::HidePost => "hide_post",
+            ReportResolution::WarnUser => "warn_user",
+            ReportResolution::SuspendUser => "suspend_user",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|resolution| resolution.as_str() == value)
+    }
+
+    pub fn hides_post(&self) -> bool {
+        matches!(self, ReportResolution::HidePost | ReportResolution::SuspendUser)
+    }
+
+    /// Notification sent to everyone who reported the post
+    fn reporter_message(&self, title: &str) -> String {
+        let outcome = match self {
+            ReportResolution::Dismiss => "found no violation",
+            ReportResolution::HidePost => "removed the post",
+            ReportResolution::WarnUser => "warned its author",
+            ReportResolution::SuspendUser => "suspended its author",
+        };
+        format!("We reviewed your report of \"{}\" and {}", title, outcome)
+    }
+}
+
+/// Report as returned to the user who filed it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostReport {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub reporter_id: Uuid,
+    pub reason: String,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Single open report inside a moderation case
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseReport {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub reporter_username: Option<String>,
+    pub reason: String,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Post with open reports waiting for a moderator
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModerationCase {
+    pub post_id: Uuid,
+    pub post_title: String,
+    pub owner_id: Uuid,
+    pub owner_username: String,
+    /// Set when the post is already hidden, e.g. automatically after enough reports
+    pub hidden_at: Option<DateTime<Utc>>,
+    pub report_count: i64,
+    pub first_reported_at: DateTime<Utc>,
+    pub last_reported_at: DateTime<Utc>,
+    /// Oldest first
+    pub reports: sqlx::types::Json<Vec<CaseReport>>,
+}
+
+/// Result of filing a report
+pub enum ReportOutcome {
+    Created(PostReport),
+    /// The user already has an open report on this post
+    AlreadyReported,
+    PostNotFound,
+}
+
+/// Result of resolving the reports of a post
+pub struct ResolvedCase {
+    pub resolved_reports: usize,
+    pub suspended_until: Option<DateTime<Utc>>,
+}
+
+pub fn auto_hide_threshold() -> i64 {
+    env_var("REPORT_AUTO_HIDE_THRESHOLD")
+        .ok()
+        .and_then(|threshold| threshold.parse().ok())
+        .filter(|threshold| *threshold > 0)
+        .unwrap_or(DEFAULT_AUTO_HIDE_REPORTS)
+}
+
+/// Returns the reason of a report or a message describing the problem
+pub fn validate_report(reason: &str, details: Option<&str>) -> std::result::Result<ReportReason, String> {
+    let Some(reason) = ReportReason::parse(reason) else {
+        return Err(format!(
+            "Invalid reason '{}', expected one of: {}",
+            reason,
+            ReportReason::ALL.map(|r| r.as_str()).join(", ")
+        ));
+    };
+
+    if reason == ReportReason::Other && details.is_none() {
+        return Err("Describe the problem in details when the reason is 'other'".to_string());
+    }
+
+    if let Some(details) = details
+        && details.chars().count() > MAX_REPORT_DETAILS_LENGTH
+    {
+        return Err(format!(
+            "Details cannot be longer than {} characters",
+            MAX_REPORT_DETAILS_LENGTH
+        ));
+    }
+
+    Ok(reason)
+}
+
+/// Returns a message describing the problem with a moderator's decision
+pub fn validate_resolution(
+    resolution: ReportResolution,
+    note: Option<&str>,
+    suspend_days: Option<i64>,
+) -> std::result::Result<(), String> {
+    if let Some(note) = note
+        && note.chars().count() > MAX_MODERATION_NOTE_LENGTH
+    {
+        return Err(format!(
+            "Note cannot be longer than {} characters",
+            MAX_MODERATION_NOTE_LENGTH
+        ));
+    }
+
+    match suspend_days {
+        Some(_) if resolution != ReportResolution::SuspendUser => {
+            Err("suspend_days is only allowed with suspend_user".to_string())
+        }
+        Some(days) if !(1..=MAX_SUSPENSION_DAYS).contains(&days) => Err(format!(
+            "suspend_days must be between 1 and {}",
+            MAX_SUSPENSION_DAYS
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Files a report and hides the post once enough independent users reported it
+pub async fn report_post(
+    db: &PgPool,
+    post_id: Uuid,
+    reporter_id: Uuid,
+    reason: ReportReason,
+    details: Option<&str>,
+) -> Result<ReportOutcome> {
+    let mut tx = db.begin().await?;
+
+    // Locked so concurrent reports agree on when the threshold is crossed
+    let Some(post) = db::moderation::lock_post(&mut tx, post_id).await? else {
+        return Ok(ReportOutcome::PostNotFound);
+    };
+
+    let Some(report) =
+        db::moderation::create_report(&mut tx, post_id, reporter_id, reason, details).await?
+    else {
+        return Ok(ReportOutcome::AlreadyReported);
+    };
+
+    if post.hidden_at.is_none()
+        && db::moderation::count_open_reports(&mut tx, post_id).await? >= auto_hide_threshold()
+    {
+        db::moderation::set_post_hidden(&mut tx, post_id, true).await?;
+        db::moderation::record_action(&mut tx, post_id, post.owner_id, None, "auto_hide", None, None).await?;
+
+        let message = format!(
+            "Your post \"{}\" was hidden after several reports and waits for a moderator",
+            post.title
+        );
+        notifications::notify_in_app(
+            db,
+            &mut tx,
+            post.owner_id,
+            NotificationEvent::ModerationNotice,
+            Some(post_id),
+            &message,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(ReportOutcome::Created(report))
+}
+
+/// Resolves every open report of a post with one decision, applies it and lets the
+/// reporters and the author know. Returns None when the post has no open reports.
+pub async fn resolve_reports(
+    db: &PgPool,
+    post_id: Uuid,
+    moderator_id: Uuid,
+    resolution: ReportResolution,
+    note: Option<&str>,
+    suspend_days: Option<i64>,
+) -> Result<Option<ResolvedCase>> {
+    let mut tx = db.begin().await?;
+
+    let Some(post) = db::moderation::lock_post(&mut tx, post_id).await? else {
+        return Ok(None);
+    };
+
+    let reporter_ids =
+        db::moderation::resolve_open_reports(&mut tx, post_id, moderator_id, resolution).await?;
+    if reporter_ids.is_empty() {
+        return Ok(None);
+    }
+
+    if resolution.hides_post() {
+        db::moderation::set_post_hidden(&mut tx, post_id, true).await?;
+    } else if db::moderation::was_auto_hidden(&mut tx, post_id).await? {
+        db::moderation::set_post_hidden(&mut tx, post_id, false).await?;
+    }
+
+    let suspended_until = match resolution {
+        ReportResolution::SuspendUser => {
+            let until = Utc::now() + Duration::days(suspend_days.unwrap_or(DEFAULT_SUSPENSION_DAYS));
+            Some(db::moderation::suspend_user(&mut tx, post.owner_id, until).await?)
+        }
+        _ => None,
+    };
+
+    db::moderation::record_action(
+        &mut tx,
+        post_id,
+        post.owner_id,
+        Some(moderator_id),
+        resolution.as_str(),
+        note,
+        suspended_until,
+    )
+    .await?;
+
+    let reporter_message = resolution.reporter_message(&post.title);
+    for reporter_id in &reporter_ids {
+        notifications::notify_in_app(
+            db,
+            &mut tx,
+            *reporter_id,
+            NotificationEvent::ReportOutcome,
+            Some(post_id),
+            &reporter_message,
+        )
+        .await?;
+    }
+
+    let owner_message = match resolution {
+        ReportResolution::Dismiss => None,
+        ReportResolution::HidePost => {
+            Some(format!("Your post \"{}\" was hidden by a moderator", post.title))
+        }
+        ReportResolution::WarnUser => Some(format!(
+            "You received a warning from a moderator about \"{}\"",
+            post.title
+        )),
+        ReportResolution::SuspendUser => suspended_until.map(|until| {
+            format!(
+                "Your account is suspended until {} UTC because of \"{}\"",
+                until.format("%Y-%m-%d %H:%M"),
+                post.title
+            )
+        }),
+    };
+
+    if let Some(message) = owner_message {
+        let message = match note {
+            Some(note) => format!("{}: {}", message, note),
+            None => message,
+        };
+        notifications::notify_in_app(
+            db,
+            &mut tx,
+            post.owner_id,
+            NotificationEvent::ModerationNotice,
+            Some(post_id),
+            &message,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Some(ResolvedCase {
+        resolved_reports: reporter_ids.len(),
+        suspended_until,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_known_reasons() {
+        assert_eq!(validate_report("spam", None), Ok(ReportReason::Spam));
+        assert_eq!(
+            validate_report("academic_dishonesty", Some("Wants homework solved")),
+            Ok(ReportReason::AcademicDishonesty)
+        );
+        assert!(validate_report("boring", None).is_err());
+    }
+
+    #[test]
+    fn other_needs_details() {
+        assert!(validate_report("other", None).is_err());
+        assert_eq!(validate_report("other", Some("Duplicate post")), Ok(ReportReason::Other));
+    }
+
+    #[test]
+    fn limits_report_details() {
+        let details = "x".repeat(MAX_REPORT_DETAILS_LENGTH);
+        assert!(validate_report("scam", Some(&details)).is_ok());
+        let details = "x".repeat(MAX_REPORT_DETAILS_LENGTH + 1);
+        assert!(validate_report("scam", Some(&details)).is_err());
+    }
+
+    #[test]
+    fn suspension_days_only_with_suspend_user() {
+        assert!(validate_resolution(ReportResolution::SuspendUser, None, None).is_ok());
+        let suspend = |days| validate_resolution(ReportResolution::SuspendUser, None, Some(days));
+        assert!(suspend(1).is_ok());
+        assert!(suspend(MAX_SUSPENSION_DAYS).is_ok());
+        assert!(suspend(0).is_err());
+        assert!(suspend(MAX_SUSPENSION_DAYS + 1).is_err());
+        assert!(validate_resolution(ReportResolution::HidePost, None, Some(7)).is_err());
+    }
+
+    #[test]
+    fn limits_moderation_notes() {
+        let note = "x".repeat(MAX_MODERATION_NOTE_LENGTH + 1);
+        assert!(validate_resolution(ReportResolution::Dismiss, Some(&note), None).is_err());
+        assert!(validate_resolution(ReportResolution::Dismiss, Some("Looks fine"), None).is_ok());
+    }
+}
@@ -0,0 +1,151 @@
+use rust_decimal::Decimal;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{fmt, str::FromStr};
+
+/// Currency of posts that do not name one
+pub const DEFAULT_CURRENCY: &str = "PLN";
+
+/// ISO 4217 codes posts can be priced in
+pub const CURRENCIES: [&str; 4] = ["PLN", "EUR", "USD", "GBP"];
+
+/// Amounts are stored as NUMERIC(10, 2)
+const MAX_DECIMAL_PLACES: u32 = 2;
+const MAX_AMOUNT_CENTS: i64 = 9_999_999_999;
+
+/// Amount of money exactly as the client wrote it.
+///
+/// Clients should send a decimal string like "19.99". JSON numbers are still accepted for older
+/// clients, they are taken by their shortest textual form so 19.99 stays 19.99 instead of
+/// the nearest binary float.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountInput(String);
+
+impl<'de> Deserialize<'de> for AmountInput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl Visitor<'_> for AmountVisitor {
+            type Value = AmountInput;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a decimal amount like \"19.99\"")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<AmountInput, E> {
+                Ok(AmountInput(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<AmountInput, E> {
+                Ok(AmountInput(value.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<AmountInput, E> {
+                Ok(AmountInput(value.to_string()))
+            }
+
+            // Display prints the shortest text that reads back as the same float
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<AmountInput, E> {
+                Ok(AmountInput(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+impl AmountInput {
+    /// Exact amount, the error is a message for the client naming `field`
+    pub fn parse(&self, field: &str) -> Result<Decimal, String> {
+        parse_amount(field, &self.0)
+    }
+}
+
+/// Parses a non-negative amount with at most two decimal places
+pub fn parse_amount(field: &str, text: &str) -> Result<Decimal, String> {
+    let amount = Decimal::from_str(text.trim())
+        .map_err(|_| format!("{} must be a decimal amount like \"19.99\"", field))?;
+
+    if amount.is_sign_negative() && !amount.is_zero() {
+        return Err(format!("{} cannot be negative", field));
+    }
+
+    if amount.normalize().scale() > MAX_DECIMAL_PLACES {
+        return Err(format!(
+            "{} cannot have more than {} decimal places",
+            field, MAX_DECIMAL_PLACES
+        ));
+    }
+
+    let max_amount = Decimal::new(MAX_AMOUNT_CENTS, MAX_DECIMAL_PLACES);
+    if amount > max_amount {
+        return Err(format!("{} cannot be greater than {}", field, max_amount));
+    }
+
+    // Drops the sign of -0
+    Ok(amount.abs())
+}
+
+/// Amount as sent to clients, always with two decimal places
+pub fn format_amount(amount: Decimal) -> String {
+    format!("{:.2}", amount)
+}
+
+/// Upper cased currency code if it is supported
+pub fn parse_currency(code: &str) -> Result<String, String> {
+    let code = code.trim().to_uppercase();
+    if CURRENCIES.contains(&code.as_str()) {
+        Ok(code)
+    } else {
+        Err(format!(
+            "Invalid currency '{}', expected one of: {}",
+            code,
+            CURRENCIES.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(text: &str) -> Decimal {
+        Decimal::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn parses_amounts_with_up_to_two_decimal_places() {
+        assert_eq!(parse_amount("price", "19.99"), Ok(amount("19.99")));
+        assert_eq!(parse_amount("price", " 20 "), Ok(amount("20")));
+        assert_eq!(parse_amount("price", "0"), Ok(Decimal::ZERO));
+        assert_eq!(parse_amount("price", "5.100"), Ok(amount("5.1")));
+        assert_eq!(parse_amount("price", "99999999.99"), Ok(amount("99999999.99")));
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        assert!(parse_amount("price", "").is_err());
+        assert!(parse_amount("price", "abc").is_err());
+        assert!(parse_amount("price", "1,50").is_err());
+        assert!(parse_amount("price", "-1").is_err());
+        assert!(parse_amount("price", "1.999").is_err());
+        assert!(parse_amount("price", "100000000").is_err());
+    }
+
+    #[test]
+    fn negative_zero_is_zero() {
+        let zero = parse_amount("price", "-0").unwrap();
+        assert!(zero.is_zero());
+        assert!(!zero.is_sign_negative());
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        assert_eq!(
+            parse_amount("min_price", "-5"),
+            Err("min_price cannot be negative".to_string())
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{db, error::Result};
+
+/// Something a user can be notified about
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    NewMessage,
+    NewReview,
+    PostReply,
+    DeadlineReminder,
+    /// A moderator acted on a report the user filed
+    ReportOutcome,
+    /// Moderation hid a post of the user, warned or suspended them
+    ModerationNotice,
+    /// A new post matches a saved search with alerts on
+    SavedSearchMatch,
+}
+
+/// Way a notification is delivered
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    InApp,
+    Email,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 7] = [
+        NotificationEvent::NewMessage,
+        NotificationEvent::NewReview,
+        NotificationEvent::PostReply,
+        NotificationEvent::DeadlineReminder,
+        NotificationEvent::ReportOutcome,
+        NotificationEvent::ModerationNotice,
+        NotificationEvent::SavedSearchMatch,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::NewMessage => "new_message",
+            NotificationEvent::NewReview => "new_review",
+            NotificationEvent::PostReply => "post_reply",
+            NotificationEvent::DeadlineReminder => "deadline_reminder",
+            NotificationEvent::ReportOutcome => "report_outcome",
+            NotificationEvent::ModerationNotice => "moderation_notice",
+            NotificationEvent::SavedSearchMatch => "saved_search_match",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+
+    /// Used when the user has not stored a preference, chat messages and search matches
+    /// are too frequent for email
+    pub fn default_enabled(&self, channel: NotificationChannel) -> bool {
+        !matches!(
+            (self, channel),
+            (
+                NotificationEvent::NewMessage | NotificationEvent::SavedSearchMatch,
+                NotificationChannel::Email
+            )
+        )
+    }
+}
+
+impl NotificationChannel {
+    pub const ALL: [NotificationChannel; 2] = [NotificationChannel::InApp, NotificationChannel::Email];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannel::InApp => "in_app",
+            NotificationChannel::Email => "email",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|channel| channel.as_str() == value)
+    }
+}
+
+/// Channels enabled for a single event
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ChannelPreferences {
+    pub in_app: bool,
+    pub email: bool,
+}
+
+impl ChannelPreferences {
+    pub fn defaults_for(event: NotificationEvent) -> Self {
+        Self {
+            in_app: event.default_enabled(NotificationChannel::InApp),
+            email: event.default_enabled(NotificationChannel::Email),
+        }
+    }
+
+    pub fn get(&self, channel: NotificationChannel) -> bool {
+        match channel {
+            NotificationChannel::InApp => self.in_app,
+            NotificationChannel::Email => self.email,
+        }
+    }
+
+    pub fn set(&mut self, channel: NotificationChannel, enabled: bool) {
+        match channel {
+            NotificationChannel::InApp => self.in_app = enabled,
+            NotificationChannel::Email => self.email = enabled,
+        }
+    }
+}
+
+/// Local time range in which nothing is pushed, may wrap past midnight (22:00 - 07:00).
+/// Notifications created meanwhile are kept and pushed when it ends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub time_zone: String,
+}
+
+/// Everything a user controls about the notifications they receive
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationPreferences {
+    pub new_message: ChannelPreferences,
+    pub new_review: ChannelPreferences,
+    pub post_reply: ChannelPreferences,
+    pub deadline_reminder: ChannelPreferences,
+    // Newer events may be missing from clients that saved preferences before they existed
+    #[serde(default = "default_report_outcome")]
+    pub report_outcome: ChannelPreferences,
+    #[serde(default = "default_moderation_notice")]
+    pub moderation_notice: ChannelPreferences,
+    #[serde(default = "default_saved_search_match")]
+    pub saved_search_match: ChannelPreferences,
+    pub quiet_hours: Option<QuietHours>,
+    pub muted_thread_ids: Vec<Uuid>,
+}
+
+fn default_report_outcome() -> ChannelPreferences {
+    ChannelPreferences::defaults_for(NotificationEvent::ReportOutcome)
+}
+
+fn default_moderation_notice() -> ChannelPreferences {
+    ChannelPreferences::defaults_for(NotificationEvent::ModerationNotice)
+}
+
+fn default_saved_search_match() -> ChannelPreferences {
+    ChannelPreferences::defaults_for(NotificationEvent::SavedSearchMatch)
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            new_message: ChannelPreferences::defaults_for(NotificationEvent::NewMessage),
+            new_review: ChannelPreferences::defaults_for(NotificationEvent::NewReview),
+            post_reply: ChannelPreferences::defaults_for(NotificationEvent::PostReply),
+            deadline_reminder: ChannelPreferences::defaults_for(NotificationEvent::DeadlineReminder),
+            report_outcome: ChannelPreferences::defaults_for(NotificationEvent::ReportOutcome),
+            moderation_notice: ChannelPreferences::defaults_for(NotificationEvent::ModerationNotice),
+            saved_search_match: ChannelPreferences::defaults_for(NotificationEvent::SavedSearchMatch),
+            quiet_hours: None,
+            muted_thread_ids: Vec::new(),
+        }
+    }
+}
+
+impl NotificationPreferences {
+    pub fn event(&self, event: NotificationEvent) -> &ChannelPreferences {
+        match event {
+            NotificationEvent::NewMessage => &self.new_message,
+            NotificationEvent::NewReview => &self.new_review,
+            NotificationEvent::PostReply => &self.post_reply,
+            NotificationEvent::DeadlineReminder => &self.deadline_reminder,
+            NotificationEvent::ReportOutcome => &self.report_outcome,
+            NotificationEvent::ModerationNotice => &self.moderation_notice,
+            NotificationEvent::SavedSearchMatch => &self.saved_search_match,
+        }
+    }
+
+    pub fn event_mut(&mut self, event: NotificationEvent) -> &mut ChannelPreferences {
+        match event {
+            NotificationEvent::NewMessage => &mut self.new_message,
+            NotificationEvent::NewReview => &mut self.new_review,
+            NotificationEvent::PostReply => &mut self.post_reply,
+            NotificationEvent::DeadlineReminder => &mut self.deadline_reminder,
+            NotificationEvent::ReportOutcome => &mut self.report_outcome,
+            NotificationEvent::ModerationNotice => &mut self.moderation_notice,
+            NotificationEvent::SavedSearchMatch => &mut self.saved_search_match,
+        }
+    }
+
+    /// Returns a message describing the first problem found, the time zone is checked against the database
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if let Some(quiet_hours) = &self.quiet_hours {
+            if quiet_hours.start == quiet_hours.end {
+                return Err("Quiet hours start and end cannot be equal".to_string());
+            }
+            if quiet_hours.time_zone.trim().is_empty() {
+                return Err("Quiet hours time_zone cannot be empty".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What should happen to a notification for a user right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Now,
+    /// The user is in quiet hours, keep the notification and push it when they end
+    Deferred(DateTime<Utc>),
+    /// The user turned the notification off or muted the thread
+    Skip,
+}
+
+impl Delivery {
+    pub fn is_now(&self) -> bool {
+        *self == Delivery::Now
+    }
+}
+
+/// The single gate for every notification the server sends.
+///
+/// Checks the user's per event and channel preference, thread mutes and quiet hours.
+/// Quiet hours only hold back pushing the notification, they never drop it.
+/// Any code path delivering a notification must call this first.
+pub async fn should_notify(
+    db: &PgPool,
+    user_id: Uuid,
+    event: NotificationEvent,
+    channel: NotificationChannel,
+    thread_id: Option<Uuid>,
+) -> Result<Delivery> {
+    let state = db::notifications::get_delivery_state(db, user_id, event, channel, thread_id).await?;
+
+    let enabled = state.enabled.unwrap_or_else(|| event.default_enabled(channel));
+
+    Ok(match state.quiet_until {
+        _ if !enabled || state.thread_muted => Delivery::Skip,
+        Some(until) => Delivery::Deferred(until),
+        None => Delivery::Now,
+    })
+}
+
+/// Stored in-app notification, also pushed to the user's open WebSocket connections
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub event_type: String,
+    pub post_id: Option<Uuid>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// Creates an in-app notification if the user's preferences allow it, returns whether it was created.
+/// During quiet hours it is created right away and pushed when they end.
+/// Runs on the given connection so it can be part of the caller's transaction.
+pub async fn notify_in_app(
+    db: &PgPool,
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    event: NotificationEvent,
+    post_id: Option<Uuid>,
+    message: &str,
+) -> Result<bool> {
+    let deliver_after = match should_notify(db, user_id, event, NotificationChannel::InApp, None).await? {
+        Delivery::Now => None,
+        Delivery::Deferred(until) => Some(until),
+        Delivery::Skip => return Ok(false),
+    };
+
+    db::notifications::create_notification(conn, user_id, event, post_id, message, deliver_after).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet_hours(start: &str, end: &str, time_zone: &str) -> Option<QuietHours> {
+        Some(QuietHours {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+            time_zone: time_zone.to_string(),
+        })
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(NotificationPreferences::default().validate().is_ok());
+    }
+
+    #[test]
+    fn quiet_hours_may_span_midnight() {
+        let preferences = NotificationPreferences {
+            quiet_hours: quiet_hours("22:00:00", "07:00:00", "Europe/Warsaw"),
+            ..NotificationPreferences::default()
+        };
+        assert!(preferences.validate().is_ok());
+    }
+
+    #[test]
+    fn quiet_hours_need_a_length_and_a_time_zone() {
+        let preferences = NotificationPreferences {
+            quiet_hours: quiet_hours("22:00:00", "22:00:00", "Europe/Warsaw"),
+            ..NotificationPreferences::default()
+        };
+        assert!(preferences.validate().is_err());
+
+        let preferences = NotificationPreferences {
+            quiet_hours: quiet_hours("22:00:00", "07:00:00", "  "),
+            ..NotificationPreferences::default()
+        };
+        assert!(preferences.validate().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Upper bound for any per_page / limit parameter, larger values are clamped
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// Clamps a requested page size into 1..=MAX_PER_PAGE
+pub fn clamp_per_page(requested: Option<i64>, default: i64) -> i64 {
+    requested.unwrap_or(default).clamp(1, MAX_PER_PAGE)
+}
+
+/// Position in a listing ordered by a timestamp and id descending, e.g. (created_at, id) of reviews.
+///
+/// Clients receive it as an opaque string and pass it back unchanged to get the next page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(timestamp: DateTime<Utc>, id: Uuid) -> Self {
+        Self { timestamp, id }
+    }
+
+    pub fn encode(&self) -> String {
+        format!(
+            "{:016x}{}",
+            self.timestamp.timestamp_micros() as u64,
+            self.id.simple()
+        )
+    }
+
+    /// Returns None for anything not produced by [`Cursor::encode`]
+    pub fn decode(value: &str) -> Option<Self> {
+        if value.len() != 48 || !value.is_ascii() {
+            return None;
+        }
+        let (timestamp, id) = value.split_at(16);
+        let micros = u64::from_str_radix(timestamp, 16).ok()? as i64;
+
+        Some(Self {
+            timestamp: DateTime::from_timestamp_micros(micros)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = Cursor::new(
+            DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap(),
+            Uuid::parse_str("0d8052ca-9c8c-43de-85a7-790a8ff6d9ef").unwrap(),
+        );
+
+        let encoded = cursor.encode();
+        assert_eq!(encoded.len(), 48);
+        assert_eq!(Cursor::decode(&encoded), Some(cursor));
+    }
+
+    #[test]
+    fn rejects_foreign_cursors() {
+        let valid = Cursor::new(Utc::now(), Uuid::new_v4()).encode();
+
+        assert_eq!(Cursor::decode(""), None);
+        assert_eq!(Cursor::decode(&valid[1..]), None);
+        assert_eq!(Cursor::decode(&format!("{}0", valid)), None);
+        assert_eq!(Cursor::decode(&valid.replacen(&valid[..1], "z", 1)), None);
+        assert_eq!(Cursor::decode(&"ą".repeat(24)), None);
+    }
+
+    #[test]
+    fn clamps_page_sizes() {
+        assert_eq!(clamp_per_page(None, 20), 20);
+        assert_eq!(clamp_per_page(Some(0), 20), 1);
+        assert_eq!(clamp_per_page(Some(-3), 20), 1);
+        assert_eq!(clamp_per_page(Some(1000), 20), MAX_PER_PAGE);
+    }
+}
@@ -3,86 +3,515 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::server::{school_location::SchoolLocationTag, subject::SubjectTag};
+
+pub const POST_TYPES: [&str; 2] = ["request", "offer"];
+pub const ACADEMIC_LEVELS: [&str; 4] = ["undergraduate", "graduate", "phd", "other"];
+pub const DIFFICULTIES: [&str; 3] = ["beginner", "intermediate", "advanced"];
+
+/// Days a deleted post can be restored before the purge job may remove it for good
+pub const DELETED_POST_GRACE_DAYS: i64 = 30;
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Post {
     pub id: Uuid,
     pub title: String,
     pub description: String,
     pub r#type: String, // 'request' or 'offer'
-    pub subject: String,
-    pub price: rust_decimal::Decimal,
+    /// Taxonomy subjects the post is tagged with, the first one is the primary subject
+    pub subjects: sqlx::types::Json<Vec<SubjectTag>>,
+    /// Exact amount, missing for free posts and negotiable ones without a starting price
+    pub price: Option<rust_decimal::Decimal>,
+    pub currency: String,
+    pub pricing_type: String, // 'fixed', 'hourly', 'negotiable' or 'free'
     pub deadline: Option<DateTime<Utc>>,
     pub urgent: bool,
-    pub status: String, // 'active', 'completed', 'cancelled'
+    pub status: String, // 'draft', 'scheduled', 'active', 'in_progress', 'completed', 'cancelled', 'expired'
+    /// When a scheduled post goes live
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Set when moderation took the post down
+    pub hidden_at: Option<DateTime<Utc>>,
+    /// Set when the owner deleted the post, it can be restored until the grace period ends
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the post went live, drafts and scheduled posts have none yet
+    pub published_at: Option<DateTime<Utc>>,
     
-    // Owner information (denormalized for performance)
+    // Owner information, joined from users on every read so it is always current
     pub owner_id: Uuid,
     pub owner_name: String,
     pub owner_username: String,
     pub owner_email: String,
     pub owner_avatar: Option<Vec<u8>>,
     pub owner_rating: rust_decimal::Decimal,
+    pub owner_review_count: i32,
     
     // Post metadata
     pub view_count: i32,
     pub response_count: i32,
     
+    /// How help is given, 'online', 'in_person' or 'either'
+    pub meeting_mode: String,
+    /// Place at school for in-person help, never set on online posts
+    pub school_location: Option<sqlx::types::Json<SchoolLocationTag>>,
+
     // Optional enhanced fields
+    /// Free-text note on where or how to meet
     pub location: Option<String>,
     pub preferred_contact_method: Option<String>,
     pub academic_level: Option<String>,
     pub difficulty: Option<String>,
+
+    // Helper whose proposal was accepted
+    pub assigned_helper_id: Option<Uuid>,
+
+    // Viewer specific flags, only set when the request is authenticated
+    pub is_saved: Option<bool>,
 }
 
 impl Post {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        id: Uuid,
-        title: String,
-        description: String,
-        r#type: String,
-        subject: String,
-        price: rust_decimal::Decimal,
-        deadline: Option<DateTime<Utc>>,
-        urgent: bool,
-        owner_id: Uuid,
-        owner_name: String,
-        owner_username: String,
-        owner_email: String,
-        owner_avatar: Option<Vec<u8>>,
-        owner_rating: rust_decimal::Decimal,
-        location: Option<String>,
-        preferred_contact_method: Option<String>,
-        academic_level: Option<String>,
-        difficulty: Option<String>,
-    ) -> Self {
-        let now = Utc::now();
-        Self {
-            id,
-            title,
-            description,
-            r#type,
-            subject,
-            price,
-            deadline,
-            urgent,
-            status: "active".to_string(),
-            created_at: now,
-            updated_at: now,
-            owner_id,
-            owner_name,
-            owner_username,
-            owner_email,
-            owner_avatar,
-            owner_rating,
-            view_count: 0,
-            response_count: 0,
-            location,
-            preferred_contact_method,
-            academic_level,
-            difficulty,
+    /// Unpublished posts and posts hidden by moderation only exist for their owner,
+    /// deleted posts are gone for everyone
+    pub fn is_visible_to(&self, viewer_id: Option<Uuid>) -> bool {
+        self.deleted_at.is_none()
+            && (viewer_id == Some(self.owner_id)
+                || (self.hidden_at.is_none()
+                    && PostStatus::parse(&self.status).is_none_or(|status| status.is_published())))
+    }
+
+    /// Last moment a deleted post can be restored, after that the purge job may remove it
+    pub fn restorable_until(&self) -> Option<DateTime<Utc>> {
+        self.deleted_at
+            .map(|deleted_at| deleted_at + chrono::Duration::days(DELETED_POST_GRACE_DAYS))
+    }
+
+    /// Time newest first listings order by, the publication or the creation for unpublished posts
+    pub fn listed_at(&self) -> DateTime<Utc> {
+        self.published_at.unwrap_or(self.created_at)
+    }
+}
+
+/// Why a post matched a full text search, markup is HTML escaped with matches wrapped in <mark>
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostSearchMatch {
+    #[serde(skip)]
+    pub post_id: Uuid,
+    pub rank: f32,
+    pub title: String,
+    pub snippet: String,
+}
+
+/// Order of a post listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PostSort {
+    #[default]
+    Newest,
+    /// Closest deadline first, posts without a deadline last
+    Deadline,
+    PriceAsc,
+    PriceDesc,
+    /// Best rated owners first
+    Rating,
+    /// Best full text search match first, only valid together with a search query
+    Relevance,
+}
+
+impl PostSort {
+    pub const ALL: [PostSort; 6] = [
+        PostSort::Newest,
+        PostSort::Deadline,
+        PostSort::PriceAsc,
+        PostSort::PriceDesc,
+        PostSort::Rating,
+        PostSort::Relevance,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostSort::Newest => "newest",
+            PostSort::Deadline => "deadline",
+            PostSort::PriceAsc => "price_asc",
+            PostSort::PriceDesc => "price_desc",
+            PostSort::Rating => "rating",
+            PostSort::Relevance => "relevance",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sort| sort.as_str() == value)
+    }
+}
+
+/// How the price of a post is meant
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingType {
+    /// One price for the whole job
+    Fixed,
+    /// Price per hour of help
+    Hourly,
+    /// To be agreed, the price is an optional starting point
+    Negotiable,
+    /// No money changes hands, e.g. help in exchange for help
+    Free,
+}
+
+impl PricingType {
+    pub const ALL: [PricingType; 4] = [
+        PricingType::Fixed,
+        PricingType::Hourly,
+        PricingType::Negotiable,
+        PricingType::Free,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PricingType::Fixed => "fixed",
+            PricingType::Hourly => "hourly",
+            PricingType::Negotiable => "negotiable",
+            PricingType::Free => "free",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pricing_type| pricing_type.as_str() == value)
+    }
+}
+
+/// Price of a post together with what it means, the parts are only valid in combination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostPricing {
+    pub pricing_type: PricingType,
+    pub price: Option<rust_decimal::Decimal>,
+    pub currency: String,
+}
+
+impl PostPricing {
+    /// Checks that the price fits the pricing type, the error is a message for the client
+    pub fn validate(&self) -> Result<(), String> {
+        let pricing_type = self.pricing_type.as_str();
+
+        match (self.pricing_type, self.price) {
+            (PricingType::Fixed | PricingType::Hourly, None) => {
+                Err(format!("A price is required for {} pricing", pricing_type))
+            }
+            (PricingType::Fixed | PricingType::Hourly | PricingType::Negotiable, Some(price))
+                if price.is_zero() =>
+            {
+                Err(format!(
+                    "The price must be greater than 0 for {} pricing, use free pricing instead",
+                    pricing_type
+                ))
+            }
+            (PricingType::Free, Some(_)) => Err("Free posts cannot have a price".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How the people behind a post meet
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MeetingMode {
+    Online,
+    InPerson,
+    /// Online or in person, whatever suits both sides
+    Either,
+}
+
+impl MeetingMode {
+    pub const ALL: [MeetingMode; 3] = [MeetingMode::Online, MeetingMode::InPerson, MeetingMode::Either];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MeetingMode::Online => "online",
+            MeetingMode::InPerson => "in_person",
+            MeetingMode::Either => "either",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == value)
+    }
+}
+
+/// Meeting mode of a post together with its place at school, the parts are only valid in combination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostMeeting {
+    pub mode: MeetingMode,
+    pub school_location_id: Option<Uuid>,
+}
+
+impl PostMeeting {
+    /// Checks that the location fits the meeting mode, the error is a message for the client
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == MeetingMode::Online && self.school_location_id.is_some() {
+            return Err("Online posts cannot have a school location".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Criteria of a post listing, every field left empty matches all posts.
+/// Serialized as the argument of the post_matches_filters database function, which skips
+/// criteria that are missing.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PostFilters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    /// Any of these subjects or anything below them, as slugs, names or aliases
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    #[serde(with = "rust_decimal::serde::str_option", skip_serializing_if = "Option::is_none")]
+    pub min_price: Option<rust_decimal::Decimal>,
+    #[serde(with = "rust_decimal::serde::str_option", skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<rust_decimal::Decimal>,
+    /// Any of these pricing types
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pricing_types: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_to: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub academic_level: Option<String>,
+    /// Any of these difficulties
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub difficulties: Vec<String>,
+    /// Case insensitive substring of the location note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Any of these meeting modes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub meeting_modes: Vec<String>,
+    /// Slug of a school location, matches posts meeting there or anywhere inside it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub school_location: Option<String>,
+    /// Only offers whose owner is available at this moment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    /// Also list drafts, scheduled and hidden posts, only for an owner's own listing
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub include_unpublished: bool,
+    /// List deleted posts instead of live ones, only for an owner's own listing
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    #[serde(skip)]
+    pub sort: PostSort,
+}
+
+/// Lifecycle state of a post
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    /// Saved by the owner, not published yet
+    Draft,
+    /// Goes live on its own at `publish_at`
+    Scheduled,
+    Active,
+    InProgress,
+    Completed,
+    Cancelled,
+    /// Set by the server once an active post passes its deadline
+    Expired,
+}
+
+impl PostStatus {
+    pub const ALL: [PostStatus; 7] = [
+        PostStatus::Draft,
+        PostStatus::Scheduled,
+        PostStatus::Active,
+        PostStatus::InProgress,
+        PostStatus::Completed,
+        PostStatus::Cancelled,
+        PostStatus::Expired,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Active => "active",
+            PostStatus::InProgress => "in_progress",
+            PostStatus::Completed => "completed",
+            PostStatus::Cancelled => "cancelled",
+            PostStatus::Expired => "expired",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == value)
+    }
+
+    /// Allowed moves of the state machine, completed posts are final and published posts
+    /// cannot become drafts again. Going back to active reopens the post.
+    pub fn can_transition_to(&self, next: PostStatus) -> bool {
+        use PostStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Active)
+                | (Draft, Scheduled)
+                | (Scheduled, Active)
+                | (Scheduled, Draft)
+                | (Active, InProgress)
+                | (Active, Cancelled)
+                | (InProgress, Completed)
+                | (InProgress, Cancelled)
+                | (InProgress, Active)
+                | (Cancelled, Active)
+                | (Active, Expired)
+                | (Expired, Active)
+        )
+    }
+
+    /// Statuses only the server moves posts into
+    pub fn is_automatic(&self) -> bool {
+        matches!(self, PostStatus::Expired)
+    }
+
+    /// Whether posts in this status are visible to everyone, unpublished ones only to their owner
+    pub fn is_published(&self) -> bool {
+        !matches!(self, PostStatus::Draft | PostStatus::Scheduled)
+    }
+}
+
+/// Checks the time a post should go live at, the error is a message for the client
+pub fn validate_publish_at(publish_at: DateTime<Utc>, deadline: Option<DateTime<Utc>>) -> Result<(), String> {
+    if publish_at <= Utc::now() {
+        return Err("publishAt must be in the future".to_string());
+    }
+
+    if let Some(deadline) = deadline
+        && deadline <= publish_at
+    {
+        return Err("The deadline must be after publishAt".to_string());
+    }
+
+    Ok(())
+}
+
+/// Single entry of a post's status history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostStatusChange {
+    pub id: Uuid,
+    pub from_status: String,
+    pub to_status: String,
+    pub changed_by: Option<Uuid>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fields of a post its owner can edit, every edit is recorded as a revision
+#[derive(Debug, Serialize, Clone)]
+pub struct PostContent {
+    pub title: String,
+    pub description: String,
+    pub r#type: String,
+    /// Subject names in tag order
+    pub subjects: Vec<String>,
+    #[serde(with = "rust_decimal::serde::str_option")]
+    pub price: Option<rust_decimal::Decimal>,
+    pub currency: String,
+    pub pricing_type: String,
+    pub deadline: Option<DateTime<Utc>>,
+    pub urgent: bool,
+    pub meeting_mode: String,
+    /// Path of the school location
+    pub school_location: Option<String>,
+    pub location: Option<String>,
+    pub preferred_contact_method: Option<String>,
+    pub academic_level: Option<String>,
+    pub difficulty: Option<String>,
+}
+
+impl PostContent {
+    /// Fields that differ between the two versions, in alphabetical order
+    pub fn diff(&self, new: &PostContent) -> Vec<PostFieldChange> {
+        let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(mut new))) =
+            (serde_json::to_value(self), serde_json::to_value(new))
+        else {
+            return Vec::new();
+        };
+
+        old.into_iter()
+            .filter_map(|(field, old)| {
+                let new = new.remove(&field).unwrap_or_default();
+                (old != new).then_some(PostFieldChange { field, old, new })
+            })
+            .collect()
+    }
+}
+
+/// Single field changed by an edit
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostFieldChange {
+    pub field: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+/// One edit of a post. The old values of the earliest revisions are what was originally posted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostRevision {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub revision: i32,
+    pub editor_id: Option<Uuid>,
+    pub editor_username: Option<String>,
+    pub changes: sqlx::types::Json<Vec<PostFieldChange>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drafts_are_published_directly_or_scheduled() {
+        assert!(PostStatus::Draft.can_transition_to(PostStatus::Active));
+        assert!(PostStatus::Draft.can_transition_to(PostStatus::Scheduled));
+        assert!(PostStatus::Scheduled.can_transition_to(PostStatus::Active));
+        assert!(PostStatus::Scheduled.can_transition_to(PostStatus::Draft));
+        assert!(!PostStatus::Draft.can_transition_to(PostStatus::InProgress));
+        assert!(!PostStatus::Draft.can_transition_to(PostStatus::Cancelled));
+    }
+
+    #[test]
+    fn published_posts_never_go_back_to_draft() {
+        for status in PostStatus::ALL.into_iter().filter(|s| s.is_published()) {
+            assert!(!status.can_transition_to(PostStatus::Draft), "{:?}", status);
+            assert!(!status.can_transition_to(PostStatus::Scheduled), "{:?}", status);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn work_on_a_post_follows_its_lifecycle() {
+        assert!(PostStatus::Active.can_transition_to(PostStatus::InProgress));
+        assert!(PostStatus::InProgress.can_transition_to(PostStatus::Completed));
+        assert!(PostStatus::InProgress.can_transition_to(PostStatus::Active));
+        assert!(PostStatus::Cancelled.can_transition_to(PostStatus::Active));
+        assert!(PostStatus::Expired.can_transition_to(PostStatus::Active));
+        assert!(!PostStatus::Active.can_transition_to(PostStatus::Completed));
+        assert!(!PostStatus::Completed.can_transition_to(PostStatus::Active));
+    }
+
+    #[test]
+    fn no_status_transitions_to_itself() {
+        for status in PostStatus::ALL {
+            assert!(!status.can_transition_to(status), "{:?}", status);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::server::money::AmountInput;
+
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// State of a proposal, only pending proposals can change
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Withdrawn,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Pending => "pending",
+            ProposalStatus::Accepted => "accepted",
+            ProposalStatus::Rejected => "rejected",
+            ProposalStatus::Withdrawn => "withdrawn",
+        }
+    }
+}
+
+/// Application to a request or booking of an offer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Proposal {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub applicant_id: Uuid,
+    pub applicant_username: String,
+    pub message: String,
+    /// Decimal string, in the currency of the post
+    #[serde(with = "rust_decimal::serde::str_option")]
+    pub proposed_price: Option<rust_decimal::Decimal>,
+    pub proposed_deadline: Option<DateTime<Utc>>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Checks the applicant's input and returns the exact proposed price,
+/// the error is a message describing the first problem found
+pub fn validate_proposal(
+    message: &str,
+    proposed_price: Option<&AmountInput>,
+    proposed_deadline: Option<DateTime<Utc>>,
+) -> Result<Option<rust_decimal::Decimal>, String> {
+    if message.trim().is_empty() {
+        return Err("Message cannot be empty".to_string());
+    }
+
+    if message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(format!(
+            "Message cannot be longer than {} characters",
+            MAX_MESSAGE_LENGTH
+        ));
+    }
+
+    let proposed_price = proposed_price
+        .map(|price| price.parse("proposed_price"))
+        .transpose()?;
+
+    if let Some(deadline) = proposed_deadline
+        && deadline <= Utc::now()
+    {
+        return Err("Proposed deadline must be in the future".to_string());
+    }
+
+    Ok(proposed_price)
+}
@@ -0,0 +1,116 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    server::notifications::{self, NotificationEvent},
+};
+
+/// Lets the receiver of a new review know about it, returns whether a notification was created
+pub async fn notify_review_received(
+    db: &PgPool,
+    receiver_id: Uuid,
+    sender_name: &str,
+    score: i32,
+    post_id: Option<Uuid>,
+) -> Result<bool> {
+    let mut conn = db.acquire().await?;
+
+    let message = format!("{} left you a {} star review", sender_name, score);
+
+    notifications::notify_in_app(
+        db,
+        &mut conn,
+        receiver_id,
+        NotificationEvent::NewReview,
+        post_id,
+        &message,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn add_user(db: &PgPool, username: &str) -> Uuid {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO users (email, username, salt, password_hash)
+            VALUES ($1 || '@example.com', $1, '', '')
+            RETURNING id
+            "#,
+        )
+        .bind(username)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    async fn review_notifications(db: &PgPool, user_id: Uuid) -> Vec<(String, bool)> {
+        sqlx::query_as(
+            r#"
+            SELECT message, deliver_after IS NOT NULL
+            FROM notifications
+            WHERE user_id = $1 AND event_type = 'new_review'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn review_creates_a_notification(db: PgPool) {
+        let receiver = add_user(&db, "receiver").await;
+
+        assert!(notify_review_received(&db, receiver, "sender", 5, None).await.unwrap());
+
+        assert_eq!(
+            review_notifications(&db, receiver).await,
+            vec![("sender left you a 5 star review".to_string(), false)]
+        );
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn review_during_quiet_hours_is_deferred(db: PgPool) {
+        let receiver = add_user(&db, "receiver").await;
+        sqlx::query(
+            r#"
+            INSERT INTO notification_settings (user_id, quiet_hours_start, quiet_hours_end, time_zone)
+            VALUES ($1, (NOW() AT TIME ZONE 'UTC' - INTERVAL '1 hour')::time,
+                    (NOW() AT TIME ZONE 'UTC' + INTERVAL '1 hour')::time, 'UTC')
+            "#,
+        )
+        .bind(receiver)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        assert!(notify_review_received(&db, receiver, "sender", 4, None).await.unwrap());
+
+        assert_eq!(
+            review_notifications(&db, receiver).await,
+            vec![("sender left you a 4 star review".to_string(), true)]
+        );
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn review_respects_the_preference(db: PgPool) {
+        let receiver = add_user(&db, "receiver").await;
+        sqlx::query(
+            r#"
+            INSERT INTO notification_preferences (user_id, event_type, channel, enabled)
+            VALUES ($1, 'new_review', 'in_app', false)
+            "#,
+        )
+        .bind(receiver)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        assert!(!notify_review_received(&db, receiver, "sender", 3, None).await.unwrap());
+        assert!(review_notifications(&db, receiver).await.is_empty());
+    }
+}
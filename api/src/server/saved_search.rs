@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    api::post::GetPostsQuery,
+    db,
+    error::Result,
+    server::{
+        notifications::{self, NotificationEvent},
+        post::{PostFilters, PostStatus},
+    },
+};
+
+/// Searches a single user can save
+pub const MAX_SAVED_SEARCHES: i64 = 20;
+
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Query parameters of GET /posts that pick a page of results, they are not stored
+const PAGING_PARAMETERS: [&str; 4] = ["page", "per_page", "cursor", "include_total"];
+
+/// Search a user saved to run again later and, with alerts on, to hear about new matching posts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// Filters with the query parameter names of GET /posts
+    pub filters: sqlx::types::Json<Map<String, Value>>,
+    pub alerts: bool,
+    /// When a new post last matched and the user was alerted
+    pub last_alerted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Checks a search before it is saved, returns the trimmed name, the filters in stored form
+/// and the filters new posts are matched against. The error is a message for the client.
+pub fn validate_saved_search(
+    name: &str,
+    query: &GetPostsQuery,
+) -> std::result::Result<(String, Map<String, Value>, PostFilters), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Name cannot be longer than {} characters", MAX_NAME_LENGTH));
+    }
+
+    let filters = query.filters()?;
+
+    if let Some(status) = filters.status.as_deref().and_then(PostStatus::parse)
+        && !status.is_published()
+    {
+        return Err("Drafts and scheduled posts cannot be searched".to_string());
+    }
+
+    if query.available_at.is_some() {
+        return Err("available_at is a single moment and cannot be saved".to_string());
+    }
+
+    let Ok(Value::Object(mut stored)) = serde_json::to_value(query) else {
+        return Err("Invalid filters".to_string());
+    };
+    stored.retain(|parameter, value| !value.is_null() && !PAGING_PARAMETERS.contains(&parameter.as_str()));
+
+    if stored.keys().all(|parameter| parameter == "sort") {
+        return Err("A saved search needs at least one filter".to_string());
+    }
+
+    Ok((name.to_string(), stored, filters))
+}
+
+/// Notifies users whose saved searches match a post that just went live, each user once
+/// however many of their searches match. Returns how many users were notified.
+pub async fn alert_saved_searches(db: &PgPool, post_id: Uuid) -> Result<u64> {
+    let mut conn = db.acquire().await?;
+
+    let matches = db::saved_searches::get_matching_searches(&mut conn, post_id).await?;
+
+    let mut alerted = 0;
+    for search in &matches {
+        let message = format!(
+            "New post matching your saved search \"{}\": {}",
+            search.name, search.post_title
+        );
+        if notifications::notify_in_app(
+            db,
+            &mut conn,
+            search.user_id,
+            NotificationEvent::SavedSearchMatch,
+            Some(post_id),
+            &message,
+        )
+        .await?
+        {
+            alerted += 1;
+        }
+    }
+
+    let search_ids: Vec<Uuid> = matches.iter().map(|search| search.id).collect();
+    db::saved_searches::mark_alerted(&mut conn, &search_ids).await?;
+
+    Ok(alerted)
+}
+
+/// Alerts saved searches about a post that was just published, in the background
+/// so the author does not wait for it
+pub fn spawn_saved_search_alerts(db: PgPool, post_id: Uuid) {
+    tokio::spawn(async move {
+        if let Err(e) = alert_saved_searches(&db, post_id).await {
+            tracing::error!("Failed to alert saved searches about post {}: {:?}", post_id, e);
+        }
+    });
+}
@@ -0,0 +1,258 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    common::env_var,
+    db,
+    error::Result,
+    server::{
+        blob_store::BlobStore,
+        notifications::{self, NotificationEvent},
+        post::DELETED_POST_GRACE_DAYS,
+        saved_search,
+    },
+};
+
+/// Default time before the deadline at which owners are reminded, overridden by DEADLINE_REMINDER_HOURS
+const DEFAULT_REMINDER_LEAD_HOURS: u64 = 24;
+
+/// Work the server does on its own. Every instance runs the scheduler, an advisory lock
+/// makes sure a single one runs a given job at a time, the others skip that round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    PublishScheduledPosts,
+    ExpirePosts,
+    DeadlineReminders,
+    PurgeDeletedPosts,
+    DeliverDeferredNotifications,
+}
+
+impl Job {
+    pub const ALL: [Job; 5] = [
+        Job::PublishScheduledPosts,
+        Job::ExpirePosts,
+        Job::DeadlineReminders,
+        Job::PurgeDeletedPosts,
+        Job::DeliverDeferredNotifications,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Job::PublishScheduledPosts => "publish_scheduled_posts",
+            Job::ExpirePosts => "expire_posts",
+            Job::DeadlineReminders => "deadline_reminders",
+            Job::PurgeDeletedPosts => "purge_deleted_posts",
+            Job::DeliverDeferredNotifications => "deliver_deferred_notifications",
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        match self {
+            // Scheduled posts should go live close to the chosen time
+            Job::PublishScheduledPosts => Duration::from_secs(60),
+            Job::ExpirePosts => Duration::from_secs(5 * 60),
+            Job::DeadlineReminders => Duration::from_secs(5 * 60),
+            Job::PurgeDeletedPosts => Duration::from_secs(60 * 60),
+            // Notifications held back by quiet hours should arrive soon after they end
+            Job::DeliverDeferredNotifications => Duration::from_secs(60),
+        }
+    }
+}
+
+/// Outcome of the last run of a job, as stored in the database
+#[derive(Debug, Serialize, Clone)]
+pub struct JobStatus {
+    pub name: String,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_succeeded: Option<bool>,
+    pub last_error: Option<String>,
+    /// Rows the job changed, e.g. posts expired or reminders sent
+    pub last_affected: Option<i64>,
+    pub last_instance: Option<String>,
+    pub run_count: i64,
+}
+
+/// What a run of a job did
+#[derive(Debug, Default)]
+struct JobRun {
+    affected: u64,
+    /// Attachment files of removed rows, deleted from storage once the transaction committed
+    orphaned_blobs: Vec<String>,
+    /// Posts that went live, saved searches are alerted once the transaction committed
+    published_posts: Vec<Uuid>,
+}
+
+impl From<u64> for JobRun {
+    fn from(affected: u64) -> Self {
+        Self {
+            affected,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    db: PgPool,
+    blob_store: Arc<dyn BlobStore>,
+    reminder_lead: Duration,
+    instance: String,
+}
+
+impl Scheduler {
+    pub fn from_env(db: PgPool, blob_store: Arc<dyn BlobStore>) -> Self {
+        let lead_hours = env_var("DEADLINE_REMINDER_HOURS")
+            .ok()
+            .and_then(|hours| hours.parse().ok())
+            .unwrap_or(DEFAULT_REMINDER_LEAD_HOURS);
+
+        let host = env_var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+
+        Self {
+            db,
+            blob_store,
+            reminder_lead: Duration::from_secs(lead_hours * 60 * 60),
+            instance: format!("{}:{}", host, std::process::id()),
+        }
+    }
+
+    /// Starts one loop per job for the lifetime of the process
+    pub fn spawn(self) {
+        for job in Job::ALL {
+            let scheduler = self.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(job.interval());
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = scheduler.run_once(job).await {
+                        tracing::error!("Scheduler failed to run job {}: {:?}", job.name(), e);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Runs the job in a transaction holding its lock, does nothing if another instance holds it
+    async fn run_once(&self, job: Job) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+
+        if !db::jobs::try_lock_job(&mut tx, job.name()).await? {
+            tracing::debug!("Job {} is running elsewhere, skipping", job.name());
+            return Ok(());
+        }
+
+        db::jobs::record_job_start(&self.db, job.name(), &self.instance).await?;
+
+        let result = match self.run_job(job, &mut tx).await {
+            Ok(run) => match tx.commit().await {
+                Ok(()) => {
+                    self.delete_blobs(&run.orphaned_blobs).await;
+                    self.alert_saved_searches(&run.published_posts).await;
+                    Ok(run.affected)
+                }
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+
+        if let Err(error) = &result {
+            tracing::error!("Job {} failed: {}", job.name(), error);
+        }
+
+        db::jobs::record_job_finish(&self.db, job.name(), result).await
+    }
+
+    async fn run_job(&self, job: Job, conn: &mut PgConnection) -> Result<JobRun> {
+        match job {
+            Job::PublishScheduledPosts => self.publish_scheduled_posts(conn).await,
+            Job::ExpirePosts => db::jobs::expire_overdue_posts(conn).await.map(JobRun::from),
+            Job::DeadlineReminders => self.send_deadline_reminders(conn).await.map(JobRun::from),
+            Job::PurgeDeletedPosts => self.purge_deleted_posts(conn).await,
+            Job::DeliverDeferredNotifications => {
+                db::jobs::release_deferred_notifications(conn).await.map(JobRun::from)
+            }
+        }
+    }
+
+    /// A file that cannot be deleted is only logged, its rows are already gone
+    async fn delete_blobs(&self, keys: &[String]) {
+        for key in keys {
+            if let Err(e) = self.blob_store.delete(key).await {
+                tracing::error!("Failed to remove attachment blob {}: {:?}", key, e);
+            }
+        }
+    }
+
+    /// A failed alert is only logged, the posts are already live
+    async fn alert_saved_searches(&self, post_ids: &[Uuid]) {
+        for post_id in post_ids {
+            if let Err(e) = saved_search::alert_saved_searches(&self.db, *post_id).await {
+                tracing::error!("Failed to alert saved searches about post {}: {:?}", post_id, e);
+            }
+        }
+    }
+
+    /// Publishes due posts. Saved searches are alerted about them after the transaction
+    /// committed, so the job does not hold its locks while matching them.
+    async fn publish_scheduled_posts(&self, conn: &mut PgConnection) -> Result<JobRun> {
+        let published = db::jobs::publish_scheduled_posts(conn).await?;
+
+        Ok(JobRun {
+            affected: published.len() as u64,
+            published_posts: published,
+            ..JobRun::default()
+        })
+    }
+
+    /// Removes posts whose grace period ended. Their files are only deleted after the
+    /// transaction committed, so a failed run leaves the posts complete for the next one.
+    async fn purge_deleted_posts(&self, conn: &mut PgConnection) -> Result<JobRun> {
+        let purged = db::jobs::purge_deleted_posts(conn, DELETED_POST_GRACE_DAYS).await?;
+
+        Ok(JobRun {
+            affected: purged.count,
+            orphaned_blobs: purged.storage_keys,
+            ..JobRun::default()
+        })
+    }
+
+    /// Reminds owners once per deadline. A reminder counts as sent once it is stored, during the
+    /// owner's quiet hours it is stored right away and pushed when they end. Owners that turned
+    /// the reminder off are skipped but still marked so they are not considered again.
+    async fn send_deadline_reminders(&self, conn: &mut PgConnection) -> Result<u64> {
+        let due = db::jobs::get_due_deadline_reminders(conn, self.reminder_lead.as_secs_f64()).await?;
+
+        let mut sent = 0;
+        for reminder in due {
+            let message = format!(
+                "The deadline of \"{}\" is {} UTC",
+                reminder.title,
+                reminder.deadline.format("%Y-%m-%d %H:%M")
+            );
+
+            let stored = notifications::notify_in_app(
+                &self.db,
+                conn,
+                reminder.owner_id,
+                NotificationEvent::DeadlineReminder,
+                Some(reminder.post_id),
+                &message,
+            )
+            .await?;
+
+            // Not stored means turned off, quiet hours only defer the push
+            if stored {
+                sent += 1;
+            }
+            db::jobs::mark_deadline_reminded(conn, reminder.post_id).await?;
+        }
+
+        Ok(sent)
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const MAX_LOCATION_NAME_LENGTH: usize = 100;
+
+/// Level of a place at school
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchoolLocationKind {
+    Campus,
+    Building,
+    Room,
+}
+
+impl SchoolLocationKind {
+    pub const ALL: [SchoolLocationKind; 3] = [
+        SchoolLocationKind::Campus,
+        SchoolLocationKind::Building,
+        SchoolLocationKind::Room,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchoolLocationKind::Campus => "campus",
+            SchoolLocationKind::Building => "building",
+            SchoolLocationKind::Room => "room",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    /// Kind of location this one lies in, campuses are at the top
+    pub fn parent_kind(&self) -> Option<SchoolLocationKind> {
+        match self {
+            SchoolLocationKind::Campus => None,
+            SchoolLocationKind::Building => Some(SchoolLocationKind::Campus),
+            SchoolLocationKind::Room => Some(SchoolLocationKind::Building),
+        }
+    }
+}
+
+/// School location a post meets at, as embedded in posts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SchoolLocationTag {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub kind: String,
+    /// Names from the campus down, e.g. "Kampus Główny › Budynek A › 101"
+    pub path: String,
+}
+
+/// Place defined by the school
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SchoolLocation {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub kind: String, // 'campus', 'building' or 'room'
+    pub parent_id: Option<Uuid>,
+    /// Active posts meeting at this location or anywhere inside it
+    pub post_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Returns a message describing the problem with a location name
+pub fn validate_location_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Location name cannot be empty".to_string());
+    }
+
+    if name.chars().count() > MAX_LOCATION_NAME_LENGTH {
+        return Err(format!(
+            "Location name cannot be longer than {} characters",
+            MAX_LOCATION_NAME_LENGTH
+        ));
+    }
+
+    Ok(())
+}
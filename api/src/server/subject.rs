@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Most subjects a single post can be tagged with
+pub const MAX_SUBJECTS_PER_POST: usize = 5;
+
+const MAX_SUBJECT_NAME_LENGTH: usize = 100;
+
+/// Subject a post is tagged with, as embedded in posts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SubjectTag {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+}
+
+/// Entry of the subject taxonomy
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Subject {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    /// Other spellings and merged subjects that resolve to this one
+    pub aliases: Vec<String>,
+    /// Active posts tagged with exactly this subject
+    pub post_count: i64,
+    /// Active posts tagged with this subject or anything below it
+    pub total_post_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Returns a message describing the problem with a subject name
+pub fn validate_subject_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Subject name cannot be empty".to_string());
+    }
+
+    if name.chars().count() > MAX_SUBJECT_NAME_LENGTH {
+        return Err(format!(
+            "Subject name cannot be longer than {} characters",
+            MAX_SUBJECT_NAME_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
+/// Subjects a post is tagged with, the legacy single `subject` field is used when
+/// `subjects` is not given. Duplicates are dropped keeping the first occurrence.
+pub fn requested_subjects(
+    subject: Option<&str>,
+    subjects: Option<&[String]>,
+) -> Result<Option<Vec<String>>, String> {
+    let requested: Vec<String> = match (subjects, subject) {
+        (Some(subjects), _) => subjects.to_vec(),
+        (None, Some(subject)) => vec![subject.to_string()],
+        (None, None) => return Ok(None),
+    };
+
+    let mut cleaned: Vec<String> = Vec::new();
+    for subject in requested {
+        let subject = subject.trim().to_string();
+        if subject.is_empty() {
+            return Err("Subject cannot be empty".to_string());
+        }
+        if !cleaned.iter().any(|s| s.eq_ignore_ascii_case(&subject)) {
+            cleaned.push(subject);
+        }
+    }
+
+    if cleaned.is_empty() {
+        return Err("At least one subject is required".to_string());
+    }
+
+    if cleaned.len() > MAX_SUBJECTS_PER_POST {
+        return Err(format!(
+            "A post can have at most {} subjects",
+            MAX_SUBJECTS_PER_POST
+        ));
+    }
+
+    Ok(Some(cleaned))
+}
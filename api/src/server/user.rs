@@ -1 +1,61 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use uuid::Uuid;
 
+/// Aggregated rating of a user, maintained from the reviews they received
+#[derive(Debug, Clone, Default)]
+pub struct UserRating {
+    pub review_count: i32,
+    pub average_score: Decimal,
+    pub one_star: i32,
+    pub two_stars: i32,
+    pub three_stars: i32,
+    pub four_stars: i32,
+    pub five_stars: i32,
+}
+
+/// Reliability signals shown on a user's profile
+#[derive(Debug, Clone, Serialize)]
+pub struct UserStats {
+    pub user_id: Uuid,
+    pub requests_created: i64,
+    pub offers_created: i64,
+    pub completed_jobs: i64,
+    /// Median time between receiving a chat message and the user's reply
+    pub median_response_time_seconds: Option<f64>,
+    pub member_since: DateTime<Utc>,
+    pub last_active: Option<DateTime<Utc>>,
+}
+
+/// How long computed stats are served from memory before hitting the database again
+const USER_STATS_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// In memory cache of computed [`UserStats`], shared across requests through the app state
+#[derive(Clone, Default)]
+pub struct UserStatsCache {
+    entries: Arc<RwLock<HashMap<Uuid, (Instant, UserStats)>>>,
+}
+
+impl UserStatsCache {
+    pub async fn get(&self, user_id: &Uuid) -> Option<UserStats> {
+        let entries = self.entries.read().await;
+        entries
+            .get(user_id)
+            .filter(|(cached_at, _)| cached_at.elapsed() < USER_STATS_TTL)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    pub async fn insert(&self, stats: UserStats) {
+        let mut entries = self.entries.write().await;
+        // Drop expired entries so the map does not grow with every profile ever viewed
+        entries.retain(|_, (cached_at, _)| cached_at.elapsed() < USER_STATS_TTL);
+        entries.insert(stats.user_id, (Instant::now(), stats));
+    }
+}
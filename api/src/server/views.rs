@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    common::env_var,
+    db,
+    error::{AppError, Result},
+};
+
+/// Repeated views of a post by the same viewer within this window count once
+const VIEW_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// How often buffered views are written to the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Proxies trusted to pass the client address in X-Real-IP when TRUSTED_PROXIES is not set,
+/// nginx runs on the same host
+const DEFAULT_TRUSTED_PROXIES: [IpAddr; 2] = [IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)];
+
+/// Who looked at a post. Anonymous viewers are identified by an HMAC of their IP keyed with
+/// VIEWER_HASH_SECRET, so raw addresses are never kept and the same address keeps its hash
+/// across restarts and instances as long as the secret stays the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Viewer {
+    User(Uuid),
+    Ip(u64),
+}
+
+impl Viewer {
+    /// User id and anonymous visitor hash, as stored with analytics events
+    fn identity(&self) -> (Option<Uuid>, Option<i64>) {
+        match *self {
+            Viewer::User(user_id) => (Some(user_id), None),
+            // Same bits, Postgres has no unsigned integers
+            Viewer::Ip(hash) => (None, Some(hash as i64)),
+        }
+    }
+}
+
+/// Counted view waiting to be written as an analytics event
+#[derive(Debug, Clone, Copy)]
+struct ViewEvent {
+    post_id: Uuid,
+    viewer: Viewer,
+    viewed_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct ViewBuffer {
+    last_counted: HashMap<(Uuid, Viewer), Instant>,
+    pending: HashMap<Uuid, i32>,
+    events: Vec<ViewEvent>,
+}
+
+/// Deduplicates post views in memory and writes them to the database in batches,
+/// both as view counts and as events for the owner's analytics.
+///
+/// Deduplication is per server instance, with several instances a viewer may be counted once per instance.
+#[derive(Clone)]
+pub struct ViewCounter {
+    buffer: Arc<Mutex<ViewBuffer>>,
+    viewer_hash_key: Arc<[u8]>,
+    trusted_proxies: Arc<[IpAddr]>,
+}
+
+impl ViewCounter {
+    /// Reads the key for anonymous viewer hashes from VIEWER_HASH_SECRET and the proxies
+    /// allowed to forward client addresses from TRUSTED_PROXIES, a comma separated list of IPs
+    pub fn from_env() -> Result<Self> {
+        let viewer_hash_key = env_var("VIEWER_HASH_SECRET")?.into_bytes().into();
+
+        let trusted_proxies = match env_var("TRUSTED_PROXIES") {
+            Ok(list) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|ip| !ip.is_empty())
+                .map(|ip| {
+                    ip.parse().map_err(|_| {
+                        AppError::InternalServerError(format!("Invalid IP address '{}' in TRUSTED_PROXIES", ip))
+                    })
+                })
+                .collect::<Result<_>>()?,
+            Err(_) => DEFAULT_TRUSTED_PROXIES.into(),
+        };
+
+        Ok(Self {
+            buffer: Arc::default(),
+            viewer_hash_key,
+            trusted_proxies,
+        })
+    }
+
+    /// Anonymous viewer behind a connection from `peer`. `forwarded_ip` is the client address
+    /// reported by a proxy, it is only believed when `peer` is a trusted proxy.
+    pub fn anonymous_viewer(&self, peer: IpAddr, forwarded_ip: Option<IpAddr>) -> Viewer {
+        let ip = match forwarded_ip {
+            Some(ip) if self.trusted_proxies.contains(&peer) => ip,
+            _ => peer,
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.viewer_hash_key).expect("HMAC accepts keys of any length");
+        match ip {
+            IpAddr::V4(ip) => mac.update(&ip.octets()),
+            IpAddr::V6(ip) => mac.update(&ip.octets()),
+        }
+
+        let mut hash = [0u8; 8];
+        hash.copy_from_slice(&mac.finalize().into_bytes()[..8]);
+        Viewer::Ip(u64::from_be_bytes(hash))
+    }
+
+    /// Counts a view unless the viewer was already counted for this post within the window
+    pub async fn record(&self, post_id: Uuid, viewer: Viewer) {
+        let now = Instant::now();
+        let mut buffer = self.buffer.lock().await;
+
+        if let Some(last) = buffer.last_counted.get(&(post_id, viewer))
+            && now.duration_since(*last) < VIEW_WINDOW
+        {
+            return;
+        }
+
+        buffer.last_counted.insert((post_id, viewer), now);
+        *buffer.pending.entry(post_id).or_insert(0) += 1;
+        buffer.events.push(ViewEvent {
+            post_id,
+            viewer,
+            viewed_at: Utc::now(),
+        });
+    }
+
+    /// Writes all buffered views, they are kept for the next flush if the write fails
+    pub async fn flush(&self, db: &PgPool) -> Result<()> {
+        let (pending, events) = {
+            let mut buffer = self.buffer.lock().await;
+            buffer
+                .last_counted
+                .retain(|_, last| last.elapsed() < VIEW_WINDOW);
+            (std::mem::take(&mut buffer.pending), std::mem::take(&mut buffer.events))
+        };
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = write_views(db, &pending, &events).await {
+            let mut buffer = self.buffer.lock().await;
+            for (post_id, count) in pending {
+                *buffer.pending.entry(post_id).or_insert(0) += count;
+            }
+            buffer.events.extend(events);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Flushes periodically for the lifetime of the process
+    pub fn spawn_flusher(&self, db: PgPool) {
+        let counter = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = counter.flush(&db).await {
+                    tracing::error!("Failed to flush post views: {:?}", e);
+                }
+            }
+        });
+    }
+}
+
+/// Adds the counts and events together so a failed flush can be retried without counting twice
+async fn write_views(db: &PgPool, pending: &HashMap<Uuid, i32>, events: &[ViewEvent]) -> Result<()> {
+    let (post_ids, counts): (Vec<Uuid>, Vec<i32>) = pending.iter().map(|(id, n)| (*id, *n)).unzip();
+
+    let event_post_ids: Vec<Uuid> = events.iter().map(|event| event.post_id).collect();
+    let (user_ids, visitor_hashes): (Vec<Option<Uuid>>, Vec<Option<i64>>) =
+        events.iter().map(|event| event.viewer.identity()).unzip();
+    let viewed_at: Vec<DateTime<Utc>> = events.iter().map(|event| event.viewed_at).collect();
+
+    let mut tx = db.begin().await?;
+    db::posts::add_view_counts(&mut *tx, &post_ids, &counts).await?;
+    db::post_events::add_view_events(&mut *tx, &event_post_ids, &user_ids, &visitor_hashes, &viewed_at).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
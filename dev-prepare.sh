@@ -34,3 +34,4 @@ export ACCESS_TOKEN_LIFETIME="15" # MIN
 export REFRESH_TOKEN_LIFETIME="14" # DAYS
 export ACCESS_TOKEN_SECRET=$(head -c 50 /dev/random | base64)
 export REFRESH_TOKEN_SECRET=$(head -c 50 /dev/random | base64)
+export VIEWER_HASH_SECRET=$(head -c 50 /dev/random | base64)
@@ -237,7 +237,7 @@ export const reviewsAPI = {
   }): Promise<Review[]> => {
     try {
       const response = await api.get('/reviews', { params })
-      return response.data
+      return response.data.reviews
     } catch (error) {
       console.error('Failed to fetch reviews:', error)
       throw error